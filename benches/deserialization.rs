@@ -1,5 +1,17 @@
 use criterion::{criterion_group, criterion_main, Criterion};
-use pwmp_msg::{mac::Mac, request::Request, version::Version, Message};
+use pwmp_msg::{
+    aliases::{AirPressure, BatteryVoltage, Humidity, Rssi, Temperature},
+    compression::Compression,
+    diagnostics::{Diagnostics, ResetReason, WakeReason},
+    mac::Mac,
+    measurement::{Measurements, Reading, Sample, SampleTime, SensorKind},
+    ota::RollbackReason,
+    request::Request,
+    response::Response,
+    rollout::ReleaseChannel,
+    version::Version,
+    Message, MessageRef,
+};
 use std::hint::black_box;
 
 macro_rules! bb {
@@ -30,7 +42,9 @@ generate_benchmark!(
     "Message(Request::Handshake)::deserialize",
     Message::new_request(
         bb!(Request::Handshake {
-            mac: bb!(Mac::new(bb!(0), bb!(1), bb!(2), bb!(3), bb!(4), bb!(5)))
+            mac: bb!(Mac::new(bb!(0), bb!(1), bb!(2), bb!(3), bb!(4), bb!(5))),
+            protocol: bb!(1),
+            firmware: bb!(Version::new(bb!(1), bb!(4), bb!(2))),
         }),
         bb!(55)
     )
@@ -41,22 +55,71 @@ generate_benchmark!(
     "Message(Request::PostResults)::deserialize",
     Message::new_request(
         bb!(Request::PostResults {
-            temperature: bb!(45.78),
-            humidity: bb!(45),
-            air_pressure: bb!(Some(bb!(65520))),
+            temperature: bb!(Temperature::from_centi_celsius(bb!(4578))),
+            humidity: bb!(Humidity::new(45).unwrap()),
+            air_pressure: bb!(Some(AirPressure::MAX)),
         }),
         bb!(55)
     )
 );
 
+generate_benchmark!(
+    benchmark_request_post_measurements_deserialization,
+    "Message(Request::PostMeasurements)::deserialize",
+    Message::new_request(
+        bb!(Request::PostMeasurements(
+            [
+                Reading::new(SensorKind::Temperature, bb!(21.5)),
+                Reading::new(SensorKind::Temperature, bb!(4.25)).with_index(bb!(1)),
+                Reading::new(SensorKind::Humidity, bb!(45.0)),
+                Reading::new(SensorKind::Co2, bb!(612.0)),
+                Reading::new(SensorKind::Pm2_5, bb!(8.4)),
+            ]
+            .into_iter()
+            .collect()
+        )),
+        bb!(55)
+    )
+);
+
+generate_benchmark!(
+    benchmark_request_post_measurement_batch_deserialization,
+    "Message(Request::PostMeasurementBatch)::deserialize",
+    Message::new_request(
+        bb!(Request::PostMeasurementBatch({
+            let measurements: Measurements = [
+                Reading::new(SensorKind::Temperature, bb!(21.5)),
+                Reading::new(SensorKind::Humidity, bb!(45.0)),
+            ]
+            .into_iter()
+            .collect();
+
+            (0..12)
+                .map(|i| Sample::new(SampleTime::Age(bb!(i * 600)), &measurements))
+                .collect()
+        })),
+        bb!(55)
+    )
+);
+
 generate_benchmark!(
     benchmark_request_post_stats_deserialization,
     "Message(Request::PostStats)::deserialize",
     Message::new_request(
         bb!(Request::PostStats {
-            battery: bb!(4.20),
+            battery: bb!(BatteryVoltage::from_millivolts(bb!(4200))),
             wifi_ssid: bb!("Hello, World!".to_string().into_boxed_str()),
-            wifi_rssi: bb!(-45),
+            wifi_rssi: bb!(Rssi::new(-45).unwrap()),
+            diagnostics: bb!(Diagnostics {
+                reset_reason: Some(ResetReason::Wake(WakeReason::Timer)),
+                free_heap: Some(182_340),
+                min_free_heap: Some(151_208),
+                wifi_channel: Some(11),
+                bssid: Some(Mac::new(0x02, 0x00, 0x5E, 0x10, 0x20, 0x30)),
+                connect_time: Some(1_840),
+                failed_sessions: Some(2),
+                ..Diagnostics::new(5_312)
+            }),
         }),
         bb!(55)
     )
@@ -83,11 +146,12 @@ generate_benchmark!(
     benchmark_request_update_check_deserialization,
     "Message(Request::UpdateCheck)::deserialize",
     Message::new_request(
-        bb!(Request::UpdateCheck(bb!(Version::new(
-            bb!(5),
-            bb!(4),
-            bb!(7)
-        )))),
+        bb!(Request::UpdateCheck {
+            firmware: bb!(Version::new(bb!(5), bb!(4), bb!(7))),
+            channel: bb!(ReleaseChannel::Stable),
+            board: bb!("pws-v2".to_string().into_boxed_str()),
+            delta: bb!(true),
+        }),
         bb!(55)
     )
 );
@@ -95,13 +159,29 @@ generate_benchmark!(
 generate_benchmark!(
     benchmark_request_next_update_chunk_deserialization,
     "Message(Request::NextUpdateChunk)::deserialize",
-    Message::new_request(bb!(Request::NextUpdateChunk(bb!(128_000_000))), bb!(55))
+    Message::new_request(
+        bb!(Request::NextUpdateChunk {
+            offset: bb!(0),
+            max_size: bb!(128_000_000),
+            compression: bb!(Compression::Lzss),
+            base: bb!(Some(Version::new(bb!(5), bb!(4), bb!(6)))),
+        }),
+        bb!(55)
+    )
 );
 
 generate_benchmark!(
     benchmark_request_report_fw_update_deserialization,
     "Message(Request::ReportFirmwareUpdate)::deserialize",
-    Message::new_request(bb!(Request::ReportFirmwareUpdate(bb!(true))), bb!(55))
+    Message::new_request(
+        bb!(Request::ReportFirmwareUpdate {
+            previous: bb!(Version::new(bb!(5), bb!(4), bb!(7))),
+            new: bb!(Version::new(bb!(5), bb!(5), bb!(0))),
+            rollback: bb!(Some(bb!(RollbackReason::SelfTestFailed))),
+            diagnostic: bb!(Some(bb!("BME280 not found".to_string().into_boxed_str()))),
+        }),
+        bb!(55)
+    )
 );
 
 generate_benchmark!(
@@ -110,12 +190,41 @@ generate_benchmark!(
     Message::new_request(bb!(Request::Bye), bb!(55))
 );
 
+generate_benchmark!(
+    benchmark_response_update_part_deserialization,
+    "Message(Response::UpdatePart)::deserialize",
+    Message::new_response(
+        bb!(Response::update_part(
+            bb!(0),
+            bb!(1_048_576),
+            bb!(vec![0xAB; 4096].into_boxed_slice())
+        )),
+        bb!(55)
+    )
+);
+
+fn benchmark_response_update_part_ref_deserialization(c: &mut Criterion) {
+    let serialized = Message::serialize(bb!(Message::new_response(
+        bb!(Response::update_part(
+            bb!(0),
+            bb!(1_048_576),
+            bb!(vec![0xAB; 4096].into_boxed_slice())
+        )),
+        bb!(55)
+    )));
+    c.bench_function("MessageRef(ResponseRef::UpdatePart)::deserialize", |b| {
+        b.iter(|| MessageRef::deserialize(&serialized))
+    });
+}
+
 criterion_group!(
     benches,
     benchmark_request_ping_deserialization,
     benchmark_request_ping_deserialization,
     benchmark_request_handshake_deserialization,
     benchmark_request_post_results_deserialization,
+    benchmark_request_post_measurements_deserialization,
+    benchmark_request_post_measurement_batch_deserialization,
     benchmark_request_post_stats_deserialization,
     benchmark_request_send_notification_deserialization,
     benchmark_request_get_settings_deserialization,
@@ -124,6 +233,8 @@ criterion_group!(
     benchmark_request_update_check_deserialization,
     benchmark_request_next_update_chunk_deserialization,
     benchmark_request_report_fw_update_deserialization,
-    benchmark_request_bye_deserialization
+    benchmark_request_bye_deserialization,
+    benchmark_response_update_part_deserialization,
+    benchmark_response_update_part_ref_deserialization
 );
 criterion_main!(benches);
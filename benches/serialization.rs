@@ -1,5 +1,16 @@
 use criterion::{criterion_group, criterion_main, Criterion};
-use pwmp_msg::{mac::Mac, request::Request, version::Version, Message};
+use pwmp_msg::{
+    aliases::{AirPressure, BatteryVoltage, Humidity, Rssi, Temperature},
+    compression::Compression,
+    diagnostics::{Diagnostics, ResetReason, WakeReason},
+    mac::Mac,
+    measurement::{Measurements, Reading, Sample, SampleTime, SensorKind},
+    ota::RollbackReason,
+    request::Request,
+    rollout::ReleaseChannel,
+    version::Version,
+    Message,
+};
 use std::hint::black_box;
 
 macro_rules! bb {
@@ -27,7 +38,9 @@ generate_benchmark!(
     "Message(Request::Handshake)::serialize",
     Message::new_request(
         bb!(Request::Handshake {
-            mac: bb!(Mac::new(bb!(0), bb!(1), bb!(2), bb!(3), bb!(4), bb!(5)))
+            mac: bb!(Mac::new(bb!(0), bb!(1), bb!(2), bb!(3), bb!(4), bb!(5))),
+            protocol: bb!(1),
+            firmware: bb!(Version::new(bb!(1), bb!(4), bb!(2))),
         }),
         bb!(55)
     )
@@ -38,22 +51,71 @@ generate_benchmark!(
     "Message(Request::PostResults)::serialize",
     Message::new_request(
         bb!(Request::PostResults {
-            temperature: bb!(45.78),
-            humidity: bb!(45),
-            air_pressure: bb!(Some(bb!(65520))),
+            temperature: bb!(Temperature::from_centi_celsius(bb!(4578))),
+            humidity: bb!(Humidity::new(45).unwrap()),
+            air_pressure: bb!(Some(AirPressure::MAX)),
         }),
         bb!(55)
     )
 );
 
+generate_benchmark!(
+    benchmark_request_post_measurements_serialization,
+    "Message(Request::PostMeasurements)::serialize",
+    Message::new_request(
+        bb!(Request::PostMeasurements(
+            [
+                Reading::new(SensorKind::Temperature, bb!(21.5)),
+                Reading::new(SensorKind::Temperature, bb!(4.25)).with_index(bb!(1)),
+                Reading::new(SensorKind::Humidity, bb!(45.0)),
+                Reading::new(SensorKind::Co2, bb!(612.0)),
+                Reading::new(SensorKind::Pm2_5, bb!(8.4)),
+            ]
+            .into_iter()
+            .collect()
+        )),
+        bb!(55)
+    )
+);
+
+generate_benchmark!(
+    benchmark_request_post_measurement_batch_serialization,
+    "Message(Request::PostMeasurementBatch)::serialize",
+    Message::new_request(
+        bb!(Request::PostMeasurementBatch({
+            let measurements: Measurements = [
+                Reading::new(SensorKind::Temperature, bb!(21.5)),
+                Reading::new(SensorKind::Humidity, bb!(45.0)),
+            ]
+            .into_iter()
+            .collect();
+
+            (0..12)
+                .map(|i| Sample::new(SampleTime::Age(bb!(i * 600)), &measurements))
+                .collect()
+        })),
+        bb!(55)
+    )
+);
+
 generate_benchmark!(
     benchmark_request_post_stats_serialization,
     "Message(Request::PostStats)::serialize",
     Message::new_request(
         bb!(Request::PostStats {
-            battery: bb!(4.20),
+            battery: bb!(BatteryVoltage::from_millivolts(bb!(4200))),
             wifi_ssid: bb!("Hello, World!".to_string().into_boxed_str()),
-            wifi_rssi: bb!(-45),
+            wifi_rssi: bb!(Rssi::new(-45).unwrap()),
+            diagnostics: bb!(Diagnostics {
+                reset_reason: Some(ResetReason::Wake(WakeReason::Timer)),
+                free_heap: Some(182_340),
+                min_free_heap: Some(151_208),
+                wifi_channel: Some(11),
+                bssid: Some(Mac::new(0x02, 0x00, 0x5E, 0x10, 0x20, 0x30)),
+                connect_time: Some(1_840),
+                failed_sessions: Some(2),
+                ..Diagnostics::new(5_312)
+            }),
         }),
         bb!(55)
     )
@@ -80,11 +142,12 @@ generate_benchmark!(
     benchmark_request_update_check_serialization,
     "Message(Request::UpdateCheck)::serialize",
     Message::new_request(
-        bb!(Request::UpdateCheck(bb!(Version::new(
-            bb!(5),
-            bb!(4),
-            bb!(7)
-        )))),
+        bb!(Request::UpdateCheck {
+            firmware: bb!(Version::new(bb!(5), bb!(4), bb!(7))),
+            channel: bb!(ReleaseChannel::Stable),
+            board: bb!("pws-v2".to_string().into_boxed_str()),
+            delta: bb!(true),
+        }),
         bb!(55)
     )
 );
@@ -92,13 +155,29 @@ generate_benchmark!(
 generate_benchmark!(
     benchmark_request_next_update_chunk_serialization,
     "Message(Request::NextUpdateChunk)::serialize",
-    Message::new_request(bb!(Request::NextUpdateChunk(bb!(128_000_000))), bb!(55))
+    Message::new_request(
+        bb!(Request::NextUpdateChunk {
+            offset: bb!(0),
+            max_size: bb!(128_000_000),
+            compression: bb!(Compression::Lzss),
+            base: bb!(Some(Version::new(bb!(5), bb!(4), bb!(6)))),
+        }),
+        bb!(55)
+    )
 );
 
 generate_benchmark!(
     benchmark_request_report_fw_update_serialization,
     "Message(Request::ReportFirmwareUpdate)::serialize",
-    Message::new_request(bb!(Request::ReportFirmwareUpdate(bb!(true))), bb!(55))
+    Message::new_request(
+        bb!(Request::ReportFirmwareUpdate {
+            previous: bb!(Version::new(bb!(5), bb!(4), bb!(7))),
+            new: bb!(Version::new(bb!(5), bb!(5), bb!(0))),
+            rollback: bb!(Some(bb!(RollbackReason::SelfTestFailed))),
+            diagnostic: bb!(Some(bb!("BME280 not found".to_string().into_boxed_str()))),
+        }),
+        bb!(55)
+    )
 );
 
 generate_benchmark!(
@@ -113,6 +192,8 @@ criterion_group!(
     benchmark_request_ping_serialization,
     benchmark_request_handshake_serialization,
     benchmark_request_post_results_serialization,
+    benchmark_request_post_measurements_serialization,
+    benchmark_request_post_measurement_batch_serialization,
     benchmark_request_post_stats_serialization,
     benchmark_request_send_notification_serialization,
     benchmark_request_get_settings_serialization,
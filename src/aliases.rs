@@ -1,16 +1,372 @@
-//! Type aliases.
+//! Types of measured values.
+//!
+//! [`Temperature`] and [`BatteryVoltage`] are fixed-point numbers. They're transferred as
+//! integers, which is more compact than a float, and can't be NaN or infinite.
+//!
+//! [`Humidity`], [`AirPressure`] and [`Rssi`] only accept physically plausible values.
+//! Their constructors and deserialization reject everything else with [`ValueError::OutOfRange`].
 
-/// A fixed-precision decimal number that represents temperature in *Celsius*.
-pub type Temperature = f32;
+use core::fmt::Display;
+use serde::{Deserialize, Serialize};
 
-/// Relative humidity represented as percentage (*%*).
-pub type Humidity = u8;
+/// Temperature in *Celsius*, with a precision of 0.01 °C.
+///
+/// Stored as hundredths of a degree, so the range is -327.68 °C to 327.67 °C.
+///
+/// ```rust
+/// use pwmp_msg::aliases::{Temperature, ValueError};
+///
+/// let temperature = Temperature::try_from(21.5).unwrap();
+///
+/// assert_eq!(temperature.centi_celsius(), 2150);
+/// assert_eq!(f32::from(temperature), 21.5);
+/// assert_eq!(Temperature::try_from(f32::NAN), Err(ValueError::NotFinite));
+/// ```
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize,
+)]
+pub struct Temperature(i16);
 
-/// Air pressure represented in hecto-Pascal (*hPa*).
-pub type AirPressure = u16;
+/// Relative humidity in percent (*%*), from 0 % to 100 %.
+///
+/// ```rust
+/// use pwmp_msg::aliases::{Humidity, ValueError};
+///
+/// assert_eq!(Humidity::new(45).unwrap().percent(), 45);
+/// assert_eq!(Humidity::new(250), Err(ValueError::OutOfRange));
+/// ```
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize,
+)]
+#[serde(try_from = "u8", into = "u8")]
+pub struct Humidity(u8);
 
-/// A fixed-precision decimal number that represents voltage (*V*).
-pub type BatteryVoltage = f32;
+/// Air pressure at the node in hecto-Pascal (*hPa*), from 300 hPa to 1100 hPa.
+///
+/// This covers the station pressure at any inhabited altitude, and every pressure ever recorded
+/// at sea level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "u16", into = "u16")]
+pub struct AirPressure(u16);
 
-/// Signal strength represented in decibel-milliwatts (*dBm*).
-pub type Rssi = i8;
+/// Battery voltage in *volts*, with a precision of 1 mV.
+///
+/// Stored as millivolts, so the range is 0 V to 65.535 V.
+///
+/// ```rust
+/// use pwmp_msg::aliases::{BatteryVoltage, ValueError};
+///
+/// let voltage = BatteryVoltage::try_from(3.7).unwrap();
+///
+/// assert_eq!(voltage.millivolts(), 3700);
+/// assert_eq!(f32::from(voltage), 3.7);
+/// assert_eq!(BatteryVoltage::try_from(-1.0), Err(ValueError::OutOfRange));
+/// ```
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize,
+)]
+pub struct BatteryVoltage(u16);
+
+/// Signal strength in decibel-milliwatts (*dBm*), from -128 dBm to 0 dBm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "i8", into = "i8")]
+pub struct Rssi(i8);
+
+/// Errors that can occur while converting a measured value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValueError {
+    /// The value is NaN or infinite.
+    NotFinite,
+
+    /// The value is outside of the range of the type.
+    OutOfRange,
+}
+
+impl Temperature {
+    /// Create a temperature from hundredths of a degree Celsius.
+    #[must_use]
+    pub const fn from_centi_celsius(centi_celsius: i16) -> Self {
+        Self(centi_celsius)
+    }
+
+    /// Returns the temperature in hundredths of a degree Celsius.
+    #[must_use]
+    pub const fn centi_celsius(self) -> i16 {
+        self.0
+    }
+
+    /// Returns the temperature in degrees Celsius.
+    #[must_use]
+    pub fn celsius(self) -> f32 {
+        f32::from(self.0) / 100.0
+    }
+}
+
+impl BatteryVoltage {
+    /// Create a voltage from millivolts.
+    #[must_use]
+    pub const fn from_millivolts(millivolts: u16) -> Self {
+        Self(millivolts)
+    }
+
+    /// Returns the voltage in millivolts.
+    #[must_use]
+    pub const fn millivolts(self) -> u16 {
+        self.0
+    }
+
+    /// Returns the voltage in volts.
+    #[must_use]
+    pub fn volts(self) -> f32 {
+        f32::from(self.0) / 1000.0
+    }
+}
+
+impl Humidity {
+    /// The highest humidity, 100 %.
+    pub const MAX: Self = Self(100);
+
+    /// Create a humidity from a percentage.
+    ///
+    /// # Errors
+    /// Returns [`ValueError::OutOfRange`] if the percentage is above 100.
+    pub const fn new(percent: u8) -> Result<Self, ValueError> {
+        if percent > Self::MAX.0 {
+            return Err(ValueError::OutOfRange);
+        }
+
+        Ok(Self(percent))
+    }
+
+    /// Returns the humidity in percent.
+    #[must_use]
+    pub const fn percent(self) -> u8 {
+        self.0
+    }
+}
+
+impl AirPressure {
+    /// The lowest air pressure, 300 hPa.
+    pub const MIN: Self = Self(300);
+
+    /// The highest air pressure, 1100 hPa.
+    pub const MAX: Self = Self(1100);
+
+    /// Create an air pressure from hecto-Pascals.
+    ///
+    /// # Errors
+    /// Returns [`ValueError::OutOfRange`] if the pressure is implausible.
+    pub const fn new(hectopascals: u16) -> Result<Self, ValueError> {
+        if hectopascals < Self::MIN.0 || hectopascals > Self::MAX.0 {
+            return Err(ValueError::OutOfRange);
+        }
+
+        Ok(Self(hectopascals))
+    }
+
+    /// Returns the air pressure in hecto-Pascals.
+    #[must_use]
+    pub const fn hectopascals(self) -> u16 {
+        self.0
+    }
+}
+
+impl Rssi {
+    /// The weakest signal, -128 dBm.
+    pub const MIN: Self = Self(i8::MIN);
+
+    /// The strongest signal, 0 dBm.
+    pub const MAX: Self = Self(0);
+
+    /// Create a signal strength from decibel-milliwatts.
+    ///
+    /// # Errors
+    /// Returns [`ValueError::OutOfRange`] if the signal strength is positive.
+    pub const fn new(dbm: i8) -> Result<Self, ValueError> {
+        if dbm > Self::MAX.0 {
+            return Err(ValueError::OutOfRange);
+        }
+
+        Ok(Self(dbm))
+    }
+
+    /// Returns the signal strength in decibel-milliwatts.
+    #[must_use]
+    pub const fn dbm(self) -> i8 {
+        self.0
+    }
+}
+
+/// Convert a value into a fixed-point number with the given scale, rounding to the nearest step.
+///
+/// The result saturates at the limits of [`i64`], the caller checks the range of its type.
+#[allow(clippy::cast_possible_truncation)] // Saturating on purpose.
+fn to_fixed(value: f32, scale: f32) -> Result<i64, ValueError> {
+    if !value.is_finite() {
+        return Err(ValueError::NotFinite);
+    }
+
+    // Round half away from zero, since `f32::round()` is not available without `std`.
+    let scaled = value * scale;
+    let rounded = if scaled < 0.0 {
+        scaled - 0.5
+    } else {
+        scaled + 0.5
+    };
+
+    Ok(rounded as i64)
+}
+
+impl TryFrom<f32> for Temperature {
+    type Error = ValueError;
+
+    /// Convert degrees Celsius, rounded to 0.01 °C.
+    fn try_from(value: f32) -> Result<Self, Self::Error> {
+        let centi_celsius =
+            i16::try_from(to_fixed(value, 100.0)?).map_err(|_| ValueError::OutOfRange)?;
+
+        Ok(Self(centi_celsius))
+    }
+}
+
+impl From<Temperature> for f32 {
+    fn from(value: Temperature) -> Self {
+        value.celsius()
+    }
+}
+
+impl TryFrom<f32> for BatteryVoltage {
+    type Error = ValueError;
+
+    /// Convert volts, rounded to 1 mV.
+    fn try_from(value: f32) -> Result<Self, Self::Error> {
+        let millivolts =
+            u16::try_from(to_fixed(value, 1000.0)?).map_err(|_| ValueError::OutOfRange)?;
+
+        Ok(Self(millivolts))
+    }
+}
+
+impl From<BatteryVoltage> for f32 {
+    fn from(value: BatteryVoltage) -> Self {
+        value.volts()
+    }
+}
+
+impl TryFrom<u8> for Humidity {
+    type Error = ValueError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+impl TryFrom<f32> for Humidity {
+    type Error = ValueError;
+
+    /// Convert a percentage, rounded to 1 %.
+    fn try_from(value: f32) -> Result<Self, Self::Error> {
+        let percent = u8::try_from(to_fixed(value, 1.0)?).map_err(|_| ValueError::OutOfRange)?;
+
+        Self::new(percent)
+    }
+}
+
+impl From<Humidity> for u8 {
+    fn from(value: Humidity) -> Self {
+        value.0
+    }
+}
+
+impl From<Humidity> for f32 {
+    fn from(value: Humidity) -> Self {
+        value.0.into()
+    }
+}
+
+impl TryFrom<u16> for AirPressure {
+    type Error = ValueError;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+impl TryFrom<f32> for AirPressure {
+    type Error = ValueError;
+
+    /// Convert hecto-Pascals, rounded to 1 hPa.
+    fn try_from(value: f32) -> Result<Self, Self::Error> {
+        let hectopascals =
+            u16::try_from(to_fixed(value, 1.0)?).map_err(|_| ValueError::OutOfRange)?;
+
+        Self::new(hectopascals)
+    }
+}
+
+impl From<AirPressure> for u16 {
+    fn from(value: AirPressure) -> Self {
+        value.0
+    }
+}
+
+impl From<AirPressure> for f32 {
+    fn from(value: AirPressure) -> Self {
+        value.0.into()
+    }
+}
+
+impl TryFrom<i8> for Rssi {
+    type Error = ValueError;
+
+    fn try_from(value: i8) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+impl From<Rssi> for i8 {
+    fn from(value: Rssi) -> Self {
+        value.0
+    }
+}
+
+impl Display for Temperature {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:.2} °C", self.celsius())
+    }
+}
+
+impl Display for BatteryVoltage {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:.3} V", self.volts())
+    }
+}
+
+impl Display for Humidity {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} %", self.0)
+    }
+}
+
+impl Display for AirPressure {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} hPa", self.0)
+    }
+}
+
+impl Display for Rssi {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} dBm", self.0)
+    }
+}
+
+impl Display for ValueError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::NotFinite => write!(f, "value is not finite"),
+            Self::OutOfRange => write!(f, "value is out of range"),
+        }
+    }
+}
+
+impl core::error::Error for ValueError {}
@@ -0,0 +1,501 @@
+//! Reassembly of firmware updates on the node's side.
+//!
+//! The [`OtaAssembler`] is created from an
+//! [`UpdateAvailable`](crate::response::Response::UpdateAvailable) response and is then fed
+//! every [`UpdatePart`](crate::response::Response::UpdatePart). It verifies the manifest and
+//! every chunk, writes the chunks into a [`FirmwareSink`], and checks the complete image once
+//! the server sends [`UpdateEnd`](crate::response::Response::UpdateEnd).
+//!
+//! Chunks are requested with a fixed maximum size, which the node chooses based on its memory.
+//! Chunks that are larger, even after decompression, are rejected before anything is allocated.
+//! [Compressed](crate::compression) chunks are decompressed into a buffer that is reused for
+//! every chunk, so it never exceeds the maximum chunk size.
+//!
+//! If the update is announced as a [delta patch](crate::delta), the patch is downloaded instead
+//! of the image. Wrap the sink in a [`DeltaApplier`](crate::delta::DeltaApplier) to apply it.
+
+use crate::{
+    compression::{Compression, DecompressError},
+    manifest::{FirmwareManifest, ManifestError, VerifyingKey},
+    ota::{ImageVerifier, VerifyError},
+    request::Request,
+    response::ResponseRef,
+};
+use alloc::vec::Vec;
+use core::{convert::Infallible, fmt::Display};
+#[cfg(feature = "std")]
+use std::io::{self, Seek, SeekFrom, Write};
+
+/// Destination of a firmware image, such as memory, a file or a flash partition.
+pub trait FirmwareSink {
+    /// Error returned by the sink.
+    type Error;
+
+    /// Prepare the sink for an image of `size` bytes, for example by erasing a flash partition.
+    ///
+    /// # Errors
+    /// Returns an error if the image can't be stored, for example because it's too large.
+    fn begin(&mut self, size: u32) -> Result<(), Self::Error> {
+        let _ = size;
+        Ok(())
+    }
+
+    /// Write a chunk of the image, starting at `offset`.
+    ///
+    /// # Errors
+    /// Returns an error if the chunk can't be written.
+    fn write(&mut self, offset: u32, data: &[u8]) -> Result<(), Self::Error>;
+
+    /// Called after the whole image has been written and verified.
+    ///
+    /// # Errors
+    /// Returns an error if the image can't be finalized, for example because flushing failed.
+    fn finish(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// A sink that collects the image in memory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemorySink(Vec<u8>);
+
+/// A sink that writes the image into a seekable writer, such as a [`File`](std::fs::File).
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct IoSink<W>(W);
+
+/// Assembles a firmware image from update chunks.
+///
+/// ```rust
+/// use pwmp_msg::{
+///     assembler::{MemorySink, OtaAssembler},
+///     manifest::{FirmwareManifest, SigningKey},
+///     ota::sha256,
+///     response::Response,
+///     version::Version,
+/// };
+///
+/// let image = b"firmware image";
+/// let key = SigningKey::from_bytes(&[7; 32]);
+/// let announcement = Response::UpdateAvailable(
+///     FirmwareManifest {
+///         version: Version::new(1, 1, 0),
+///         size: image.len() as u32,
+///         sha256: sha256(image),
+///         min_version: Version::new(1, 0, 0),
+///         target: "esp32s3".into(),
+///         delta: None,
+///     }
+///     .sign(&key),
+/// );
+///
+/// let mut assembler =
+///     OtaAssembler::new(&announcement, &key.verifying_key(), MemorySink::new(), 8).unwrap();
+///
+/// for (i, chunk) in image.chunks(8).enumerate() {
+///     let part = Response::update_part(i as u32 * 8, image.len() as u32, chunk.into());
+///     assembler.push_part(&part).unwrap();
+/// }
+///
+/// let sink = assembler.finish(&Response::UpdateEnd).unwrap();
+/// assert_eq!(sink.as_slice(), image);
+/// ```
+#[derive(Debug)]
+pub struct OtaAssembler<S> {
+    /// The verified manifest of the image.
+    manifest: FirmwareManifest,
+
+    /// Verifier of the received chunks.
+    verifier: ImageVerifier,
+
+    /// Destination of the image.
+    sink: S,
+
+    /// Compression advertised in chunk requests.
+    compression: Compression,
+
+    /// Buffer for decompressed chunks.
+    buffer: Vec<u8>,
+
+    /// Largest chunk size *(after decompression)* that is requested and accepted.
+    max_chunk_size: u32,
+}
+
+/// Errors that can occur while assembling a firmware image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssembleError<E> {
+    /// The response is not the expected kind of response.
+    UnexpectedResponse,
+
+    /// The manifest of the update was not accepted.
+    Manifest(ManifestError),
+
+    /// A chunk announces a different image size than the manifest.
+    SizeMismatch {
+        /// Size of the image according to the manifest.
+        announced: u32,
+
+        /// Size of the image according to the chunk.
+        actual: u32,
+    },
+
+    /// A chunk or the complete image failed verification.
+    Verify(VerifyError),
+
+    /// A chunk is larger than requested.
+    ChunkTooLarge {
+        /// Maximum size of a chunk.
+        requested: u32,
+
+        /// Size of the chunk, after decompression.
+        actual: u32,
+    },
+
+    /// A compressed chunk could not be decompressed.
+    Decompress(DecompressError),
+
+    /// The sink has returned an error.
+    Sink(E),
+}
+
+impl<S: FirmwareSink> OtaAssembler<S> {
+    /// Start assembling the update announced by an
+    /// [`UpdateAvailable`](crate::response::Response::UpdateAvailable) response.
+    ///
+    /// The manifest's signature is checked against the trusted `key`, and the sink is
+    /// [prepared](FirmwareSink::begin) for the announced size. Checking whether the firmware
+    /// is [compatible](FirmwareManifest::check_compatibility) with the node is left to the caller.
+    ///
+    /// Chunks of at most `max_chunk_size` *(decompressed)* bytes are requested, and larger
+    /// chunks are rejected.
+    ///
+    /// # Errors
+    /// Returns [`AssembleError::UnexpectedResponse`] if the response doesn't announce an update,
+    /// [`AssembleError::Manifest`] if the signature is invalid, and [`AssembleError::Sink`] if the
+    /// sink can't store the image.
+    pub fn new<'a>(
+        response: impl Into<ResponseRef<'a>>,
+        key: &VerifyingKey,
+        mut sink: S,
+        max_chunk_size: u32,
+    ) -> Result<Self, AssembleError<S::Error>> {
+        let ResponseRef::UpdateAvailable(signed) = response.into() else {
+            return Err(AssembleError::UnexpectedResponse);
+        };
+
+        let manifest = signed.verify(key).map_err(AssembleError::Manifest)?.clone();
+        sink.begin(manifest.download_size())
+            .map_err(AssembleError::Sink)?;
+
+        Ok(Self {
+            verifier: manifest.verifier(),
+            manifest,
+            sink,
+            compression: Compression::None,
+            buffer: Vec::new(),
+            max_chunk_size,
+        })
+    }
+
+    /// Set the compression that is advertised in [chunk requests](Self::next_request).
+    ///
+    /// Compressed chunks are always accepted, regardless of this setting.
+    #[must_use]
+    pub const fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Returns the verified manifest of the image.
+    #[must_use]
+    pub const fn manifest(&self) -> &FirmwareManifest {
+        &self.manifest
+    }
+
+    /// Returns the number of bytes received so far.
+    #[must_use]
+    pub const fn received(&self) -> u32 {
+        self.verifier.received()
+    }
+
+    /// Returns the size of the download.
+    ///
+    /// This is the size of the [delta patch](crate::delta) if one is announced, and the size of
+    /// the image otherwise.
+    #[must_use]
+    pub const fn size(&self) -> u32 {
+        self.manifest.download_size()
+    }
+
+    /// Returns the progress of the download in percent.
+    #[must_use]
+    pub fn progress(&self) -> u8 {
+        if self.size() == 0 {
+            return 100;
+        }
+
+        let percent = u64::from(self.received()) * 100 / u64::from(self.size());
+        u8::try_from(percent).unwrap_or(100)
+    }
+
+    /// Returns whether all bytes of the image have been received.
+    #[must_use]
+    pub const fn is_complete(&self) -> bool {
+        self.verifier.is_complete()
+    }
+
+    /// Returns the maximum size of a chunk *(after decompression)*.
+    #[must_use]
+    pub const fn max_chunk_size(&self) -> u32 {
+        self.max_chunk_size
+    }
+
+    /// Returns the request for the next chunk.
+    #[must_use]
+    pub const fn next_request(&self) -> Request {
+        Request::NextUpdateChunk {
+            offset: self.received(),
+            max_size: self.max_chunk_size,
+            compression: self.compression,
+            base: match &self.manifest.delta {
+                Some(delta) => Some(delta.base),
+                None => None,
+            },
+        }
+    }
+
+    /// Verify an [`UpdatePart`](crate::response::Response::UpdatePart) and write it into the sink.
+    ///
+    /// # Errors
+    /// Returns [`AssembleError::UnexpectedResponse`] if the response is not an update part,
+    /// [`AssembleError::SizeMismatch`] if it belongs to an image of a different size,
+    /// [`AssembleError::ChunkTooLarge`] if it's larger than requested,
+    /// [`AssembleError::Decompress`] if it can't be decompressed,
+    /// [`AssembleError::Verify`] if it's corrupted, out of order or exceeds the image size,
+    /// and [`AssembleError::Sink`] if it can't be written. The part is not accepted in these
+    /// cases, so the [next request](Self::next_request) asks for it again. Sinks that can't
+    /// retry a failed write, such as a [`DeltaApplier`](crate::delta::DeltaApplier), return an
+    /// error for the retry, too, and the update has to be started again.
+    pub fn push_part<'a>(
+        &mut self,
+        response: impl Into<ResponseRef<'a>>,
+    ) -> Result<(), AssembleError<S::Error>> {
+        let ResponseRef::UpdatePart {
+            offset,
+            total_size,
+            crc32,
+            compression,
+            data,
+        } = response.into()
+        else {
+            return Err(AssembleError::UnexpectedResponse);
+        };
+
+        if total_size != self.size() {
+            return Err(AssembleError::SizeMismatch {
+                announced: self.size(),
+                actual: total_size,
+            });
+        }
+
+        let data = match compression {
+            Compression::None => {
+                let size = u32::try_from(data.len()).unwrap_or(u32::MAX);
+                self.check_chunk_size(size)?;
+                data
+            }
+            Compression::Lzss => {
+                let len = self.decompress(offset, compression, data)?;
+                &self.buffer[..len]
+            }
+        };
+
+        // Only accept the chunk once it's stored, so a failed write is requested again.
+        self.verifier
+            .check_chunk(offset, crc32, data)
+            .map_err(AssembleError::Verify)?;
+        self.sink.write(offset, data).map_err(AssembleError::Sink)?;
+        self.verifier.add_checked_chunk(data);
+
+        Ok(())
+    }
+
+    /// Decompress a chunk that starts at `offset` into the buffer, and return its size.
+    ///
+    /// Chunks that would extend past the end of the image, or are larger than requested, are
+    /// rejected before allocating anything.
+    fn decompress(
+        &mut self,
+        offset: u32,
+        compression: Compression,
+        data: &[u8],
+    ) -> Result<usize, AssembleError<S::Error>> {
+        let size = compression
+            .decompressed_size(data)
+            .map_err(AssembleError::Decompress)?;
+
+        if offset.checked_add(size).is_none_or(|end| end > self.size()) {
+            return Err(AssembleError::Verify(VerifyError::Overflow {
+                size: self.size(),
+            }));
+        }
+
+        self.check_chunk_size(size)?;
+        self.buffer.resize(size as usize, 0);
+        compression
+            .decompress(data, &mut self.buffer)
+            .map_err(AssembleError::Decompress)
+    }
+
+    /// Check that a chunk of `size` *(decompressed)* bytes is not larger than requested.
+    const fn check_chunk_size(&self, size: u32) -> Result<(), AssembleError<S::Error>> {
+        if size > self.max_chunk_size {
+            return Err(AssembleError::ChunkTooLarge {
+                requested: self.max_chunk_size,
+                actual: size,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Check the complete image once the server has sent
+    /// [`UpdateEnd`](crate::response::Response::UpdateEnd), and return the sink.
+    ///
+    /// # Errors
+    /// Returns [`AssembleError::UnexpectedResponse`] if the response is not `UpdateEnd`,
+    /// [`AssembleError::Verify`] if the image is incomplete or its digest doesn't match,
+    /// and [`AssembleError::Sink`] if the sink can't be finalized.
+    pub fn finish<'a>(
+        self,
+        response: impl Into<ResponseRef<'a>>,
+    ) -> Result<S, AssembleError<S::Error>> {
+        if !matches!(response.into(), ResponseRef::UpdateEnd) {
+            return Err(AssembleError::UnexpectedResponse);
+        }
+
+        let mut sink = self.sink;
+
+        self.verifier.finish().map_err(AssembleError::Verify)?;
+        sink.finish().map_err(AssembleError::Sink)?;
+
+        Ok(sink)
+    }
+}
+
+impl MemorySink {
+    /// Create a new, empty sink.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Returns the received bytes.
+    #[must_use]
+    pub fn as_slice(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Returns the received bytes, consuming the sink.
+    #[must_use]
+    pub fn into_inner(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl FirmwareSink for MemorySink {
+    type Error = Infallible;
+
+    fn begin(&mut self, size: u32) -> Result<(), Self::Error> {
+        self.0.clear();
+        self.0.reserve(size as usize);
+        Ok(())
+    }
+
+    fn write(&mut self, offset: u32, data: &[u8]) -> Result<(), Self::Error> {
+        let start = offset as usize;
+        let end = start + data.len();
+
+        if self.0.len() < end {
+            self.0.resize(end, 0);
+        }
+
+        self.0[start..end].copy_from_slice(data);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W> IoSink<W> {
+    /// Wrap a seekable writer.
+    pub const fn new(writer: W) -> Self {
+        Self(writer)
+    }
+
+    /// Returns the wrapped writer.
+    pub fn into_inner(self) -> W {
+        self.0
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write + Seek> FirmwareSink for IoSink<W> {
+    type Error = io::Error;
+
+    fn write(&mut self, offset: u32, data: &[u8]) -> Result<(), Self::Error> {
+        self.0.seek(SeekFrom::Start(offset.into()))?;
+        self.0.write_all(data)
+    }
+
+    fn finish(&mut self) -> Result<(), Self::Error> {
+        self.0.flush()
+    }
+}
+
+impl<T: FirmwareSink + ?Sized> FirmwareSink for &mut T {
+    type Error = T::Error;
+
+    fn begin(&mut self, size: u32) -> Result<(), Self::Error> {
+        (**self).begin(size)
+    }
+
+    fn write(&mut self, offset: u32, data: &[u8]) -> Result<(), Self::Error> {
+        (**self).write(offset, data)
+    }
+
+    fn finish(&mut self) -> Result<(), Self::Error> {
+        (**self).finish()
+    }
+}
+
+impl<E: Display> Display for AssembleError<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::UnexpectedResponse => write!(f, "unexpected response"),
+            Self::Manifest(err) => write!(f, "manifest not accepted: {err}"),
+            Self::SizeMismatch { announced, actual } => write!(
+                f,
+                "chunk belongs to an image of {actual} bytes, but {announced} bytes were announced"
+            ),
+            Self::Verify(err) => write!(f, "verification failed: {err}"),
+            Self::ChunkTooLarge { requested, actual } => write!(
+                f,
+                "chunk of {actual} bytes exceeds the requested size of {requested} bytes"
+            ),
+            Self::Decompress(err) => write!(f, "decompression failed: {err}"),
+            Self::Sink(err) => write!(f, "sink error: {err}"),
+        }
+    }
+}
+
+impl<E: core::error::Error + 'static> core::error::Error for AssembleError<E> {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::Manifest(err) => Some(err),
+            Self::Verify(err) => Some(err),
+            Self::Decompress(err) => Some(err),
+            Self::Sink(err) => Some(err),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,248 @@
+//! Battery state of charge estimation.
+//!
+//! Nodes only report their [battery voltage](crate::request::Request::PostStats). A
+//! [`DischargeCurve`] maps that voltage to a percentage for a battery chemistry, and a
+//! [`BatteryProfile`] classifies the percentage into a [`BatteryLevel`]. Nodes and the server use
+//! the same profile, so they agree on when to warn about the battery and when to enable the
+//! over-discharge protection of the [node settings](crate::settings::NodeSettings).
+//!
+//! ```rust
+//! use pwmp_msg::{
+//!     aliases::BatteryVoltage,
+//!     battery::{BatteryLevel, BatteryProfile, DischargeCurve},
+//! };
+//!
+//! let profile = BatteryProfile::new(DischargeCurve::LI_ION);
+//! let voltage = BatteryVoltage::from_millivolts(3650);
+//!
+//! assert_eq!(profile.percentage(voltage), 15);
+//! assert_eq!(profile.level(voltage), BatteryLevel::Low);
+//! ```
+
+use crate::{aliases::BatteryVoltage, settings::NodeSettings};
+
+/// Discharge curve of a single Li-ion or LiPo cell.
+const LI_ION_POINTS: [CurvePoint; 12] = [
+    CurvePoint::new(3000, 0),
+    CurvePoint::new(3300, 5),
+    CurvePoint::new(3600, 10),
+    CurvePoint::new(3700, 20),
+    CurvePoint::new(3750, 30),
+    CurvePoint::new(3790, 40),
+    CurvePoint::new(3830, 50),
+    CurvePoint::new(3870, 60),
+    CurvePoint::new(3920, 70),
+    CurvePoint::new(3970, 80),
+    CurvePoint::new(4100, 90),
+    CurvePoint::new(4200, 100),
+];
+
+/// Discharge curve of a single LiFePO4 cell.
+const LIFEPO4_POINTS: [CurvePoint; 11] = [
+    CurvePoint::new(2500, 0),
+    CurvePoint::new(3000, 9),
+    CurvePoint::new(3200, 14),
+    CurvePoint::new(3220, 17),
+    CurvePoint::new(3250, 20),
+    CurvePoint::new(3260, 30),
+    CurvePoint::new(3270, 40),
+    CurvePoint::new(3300, 70),
+    CurvePoint::new(3320, 90),
+    CurvePoint::new(3350, 99),
+    CurvePoint::new(3400, 100),
+];
+
+/// Discharge curve of two alkaline AA cells in series.
+const ALKALINE_2AA_POINTS: [CurvePoint; 7] = [
+    CurvePoint::new(2000, 0),
+    CurvePoint::new(2200, 8),
+    CurvePoint::new(2400, 20),
+    CurvePoint::new(2600, 40),
+    CurvePoint::new(2800, 65),
+    CurvePoint::new(3000, 85),
+    CurvePoint::new(3200, 100),
+];
+
+/// A point of a [`DischargeCurve`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CurvePoint {
+    /// Resting voltage of the battery.
+    pub voltage: BatteryVoltage,
+
+    /// Remaining charge at this voltage in percent.
+    pub percent: u8,
+}
+
+/// Maps the voltage of a battery to its remaining charge.
+///
+/// The curve is a list of points, sorted by voltage. The charge between two points is
+/// interpolated linearly. Voltages outside of the curve have the charge of its first or last point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DischargeCurve<'a>(&'a [CurvePoint]);
+
+/// Charge level of a battery, ordered from the best one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum BatteryLevel {
+    /// The battery has enough charge.
+    Normal,
+
+    /// The battery should be charged or replaced soon.
+    Low,
+
+    /// The battery is almost empty, and must not be discharged any further.
+    Critical,
+}
+
+/// Describes the battery of a node, and when its charge is considered low or critical.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BatteryProfile<'a> {
+    /// Discharge curve of the battery.
+    pub curve: DischargeCurve<'a>,
+
+    /// Highest charge in percent that is considered [low](BatteryLevel::Low).
+    pub low: u8,
+
+    /// Highest charge in percent that is considered [critical](BatteryLevel::Critical).
+    ///
+    /// This takes precedence over [`low`](Self::low).
+    pub critical: u8,
+}
+
+impl CurvePoint {
+    /// Create a point of a discharge curve.
+    #[must_use]
+    pub const fn new(millivolts: u16, percent: u8) -> Self {
+        Self {
+            voltage: BatteryVoltage::from_millivolts(millivolts),
+            percent,
+        }
+    }
+}
+
+impl<'a> DischargeCurve<'a> {
+    /// A single Li-ion or LiPo cell, from 3.0 V to 4.2 V.
+    pub const LI_ION: DischargeCurve<'static> = DischargeCurve(&LI_ION_POINTS);
+
+    /// A single LiFePO4 cell, from 2.5 V to 3.4 V.
+    pub const LIFEPO4: DischargeCurve<'static> = DischargeCurve(&LIFEPO4_POINTS);
+
+    /// Two alkaline AA cells in series, from 2.0 V to 3.2 V.
+    pub const ALKALINE_2AA: DischargeCurve<'static> = DischargeCurve(&ALKALINE_2AA_POINTS);
+
+    /// Create a custom discharge curve.
+    ///
+    /// Returns [`None`] if there are no points, the voltages are not strictly increasing,
+    /// the charge decreases, or a charge is above 100 %.
+    ///
+    /// ```rust
+    /// use pwmp_msg::{
+    ///     aliases::BatteryVoltage,
+    ///     battery::{CurvePoint, DischargeCurve},
+    /// };
+    ///
+    /// let points = [CurvePoint::new(3000, 0), CurvePoint::new(4000, 100)];
+    /// let curve = DischargeCurve::new(&points).unwrap();
+    ///
+    /// assert_eq!(curve.percentage(BatteryVoltage::from_millivolts(3250)), 25);
+    /// assert!(DischargeCurve::new(&[]).is_none());
+    /// ```
+    #[must_use]
+    pub const fn new(points: &'a [CurvePoint]) -> Option<Self> {
+        if points.is_empty() {
+            return None;
+        }
+
+        let mut i = 0;
+        while i < points.len() {
+            if points[i].percent > 100 {
+                return None;
+            }
+
+            if i > 0 {
+                let previous = points[i - 1];
+
+                if previous.voltage.millivolts() >= points[i].voltage.millivolts()
+                    || previous.percent > points[i].percent
+                {
+                    return None;
+                }
+            }
+
+            i += 1;
+        }
+
+        Some(Self(points))
+    }
+
+    /// Returns the points of the curve.
+    #[must_use]
+    pub const fn points(&self) -> &'a [CurvePoint] {
+        self.0
+    }
+
+    /// Returns the remaining charge in percent at the given voltage.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)] // The result is between two percentages.
+    pub fn percentage(&self, voltage: BatteryVoltage) -> u8 {
+        let upper = self.0.partition_point(|point| point.voltage <= voltage);
+
+        let Some(high) = self.0.get(upper) else {
+            return self.0[self.0.len() - 1].percent;
+        };
+        let Some(low) = upper.checked_sub(1).map(|lower| self.0[lower]) else {
+            return high.percent;
+        };
+
+        let offset = u32::from(voltage.millivolts() - low.voltage.millivolts());
+        let span = u32::from(high.voltage.millivolts() - low.voltage.millivolts());
+        let gain = u32::from(high.percent - low.percent);
+
+        low.percent + (gain * offset / span) as u8
+    }
+}
+
+impl<'a> BatteryProfile<'a> {
+    /// Create a profile that considers 20 % and less low, and 5 % and less critical.
+    #[must_use]
+    pub const fn new(curve: DischargeCurve<'a>) -> Self {
+        Self {
+            curve,
+            low: 20,
+            critical: 5,
+        }
+    }
+
+    /// Returns the remaining charge in percent at the given voltage.
+    #[must_use]
+    pub fn percentage(&self, voltage: BatteryVoltage) -> u8 {
+        self.curve.percentage(voltage)
+    }
+
+    /// Returns the charge level at the given voltage.
+    #[must_use]
+    pub fn level(&self, voltage: BatteryVoltage) -> BatteryLevel {
+        let percent = self.percentage(voltage);
+
+        if percent <= self.critical {
+            BatteryLevel::Critical
+        } else if percent <= self.low {
+            BatteryLevel::Low
+        } else {
+            BatteryLevel::Normal
+        }
+    }
+
+    /// Returns whether the node should warn about its battery, which is the case for a low or
+    /// critical level, unless [`battery_ignore`](NodeSettings::battery_ignore) is set.
+    #[must_use]
+    pub fn should_warn(&self, voltage: BatteryVoltage, settings: &NodeSettings) -> bool {
+        !settings.battery_ignore && self.level(voltage) >= BatteryLevel::Low
+    }
+
+    /// Returns whether the node should enable its over-discharge protection, which is the case
+    /// for a critical level, if [`sbop`](NodeSettings::sbop) is set.
+    #[must_use]
+    pub fn should_protect(&self, voltage: BatteryVoltage, settings: &NodeSettings) -> bool {
+        settings.sbop && self.level(voltage) == BatteryLevel::Critical
+    }
+}
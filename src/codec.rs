@@ -0,0 +1,229 @@
+//! Glue between the message types and `postcard`.
+
+use crate::error::{DecodeError, EncodeError};
+use alloc::vec::Vec;
+use postcard::{
+    de_flavors::{self, Slice},
+    ser_flavors,
+};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "std")]
+use std::io::{self, Write};
+
+/// Deserialize a value from raw bytes, keeping track of the offset in case of failure.
+pub(crate) fn from_bytes<'de, T: Deserialize<'de>>(bytes: &'de [u8]) -> Result<T, DecodeError> {
+    decode(Tracked::new(bytes))
+}
+
+/// Deserialize a message, also accepting responses without the ID of the answered request.
+///
+/// Older peers don't append the `Option<MsgId>` to their responses, so their messages end where
+/// its tag would start. If a message ends there, it's decoded again as if a `None` tag followed,
+/// and accepted if `is_legacy_response()` confirms that the tag was decoded as a missing
+/// `reply_to` of a response.
+pub(crate) fn message_from_bytes<'de, T: Deserialize<'de>>(
+    bytes: &'de [u8],
+    is_legacy_response: impl FnOnce(&T) -> bool,
+) -> Result<T, DecodeError> {
+    let err = match from_bytes(bytes) {
+        Ok(value) => return Ok(value),
+        Err(err) => err,
+    };
+
+    if err
+        != (DecodeError::UnexpectedEnd {
+            offset: bytes.len(),
+        })
+    {
+        return Err(err);
+    }
+
+    let mut flavor = Tracked::new(bytes);
+    flavor.none_tag = true;
+
+    decode(flavor).ok().filter(is_legacy_response).ok_or(err)
+}
+
+/// Deserialize a value with the given flavor.
+fn decode<'de, T: Deserialize<'de>>(flavor: Tracked<'de>) -> Result<T, DecodeError> {
+    let mut deserializer = postcard::Deserializer::from_flavor(flavor);
+
+    T::deserialize(&mut deserializer).map_err(|err| {
+        // `Tracked::finalize()` never fails.
+        let offset = deserializer.finalize().unwrap_or_default();
+        DecodeError::from_postcard(&err, offset)
+    })
+}
+
+/// Serialize a value into the given buffer, returning the used part of it.
+pub(crate) fn to_slice<'a, T: Serialize>(
+    value: &T,
+    buf: &'a mut [u8],
+) -> Result<&'a mut [u8], EncodeError> {
+    postcard::to_slice(value, buf).map_err(|err| EncodeError::from_postcard(&err))
+}
+
+/// Serialize a value and append it to the given vector, returning the number of appended bytes.
+///
+/// On failure, the vector is left unchanged.
+pub(crate) fn append<T: Serialize>(value: &T, vec: &mut Vec<u8>) -> Result<usize, EncodeError> {
+    let start = vec.len();
+
+    postcard::serialize_with_flavor(value, Append(vec)).map_err(|err| {
+        vec.truncate(start);
+        EncodeError::from_postcard(&err)
+    })?;
+
+    Ok(vec.len() - start)
+}
+
+/// Append an integer to the given vector, encoded as an LEB128 varint like postcard encodes it.
+pub(crate) fn append_varint(value: u64, vec: &mut Vec<u8>) {
+    // Serializing into a vector can't fail.
+    let _ = append(&value, vec);
+}
+
+/// Serialize a value into the given writer, returning the number of written bytes.
+#[cfg(feature = "std")]
+pub(crate) fn to_writer<T: Serialize, W: Write>(
+    value: &T,
+    writer: W,
+) -> Result<usize, EncodeError> {
+    let mut flavor = IoWriter {
+        inner: writer,
+        written: 0,
+        error: None,
+    };
+
+    match postcard::serialize_with_flavor(value, &mut flavor) {
+        Ok(()) => Ok(flavor.written),
+        Err(err) => Err(flavor
+            .error
+            .take()
+            .map_or_else(|| EncodeError::from_postcard(&err), EncodeError::Io)),
+    }
+}
+
+/// Slice flavor that remembers where the last read started.
+struct Tracked<'de> {
+    /// The underlying slice flavor.
+    inner: Slice<'de>,
+
+    /// Number of bytes consumed so far.
+    consumed: usize,
+
+    /// Offset of the most recently started read.
+    last_read: usize,
+
+    /// Whether a single `0` byte *(a `None` tag)* is read after the end of the slice.
+    none_tag: bool,
+}
+
+/// Flavor that appends to an existing vector.
+struct Append<'a>(&'a mut Vec<u8>);
+
+/// Flavor that passes bytes through to a writer.
+#[cfg(feature = "std")]
+struct IoWriter<W> {
+    /// The wrapped writer.
+    inner: W,
+
+    /// Number of bytes written so far.
+    written: usize,
+
+    /// The I/O error that stopped serialization, if any.
+    error: Option<io::Error>,
+}
+
+impl<'de> Tracked<'de> {
+    /// Create a new instance reading from the given bytes.
+    fn new(bytes: &'de [u8]) -> Self {
+        Self {
+            inner: Slice::new(bytes),
+            consumed: 0,
+            last_read: 0,
+            none_tag: false,
+        }
+    }
+}
+
+impl<'de> de_flavors::Flavor<'de> for Tracked<'de> {
+    /// Offset of the most recently started read.
+    type Remainder = usize;
+    /// The data being deserialized.
+    type Source = &'de [u8];
+
+    fn pop(&mut self) -> postcard::Result<u8> {
+        self.last_read = self.consumed;
+        let byte = match self.inner.pop() {
+            Err(postcard::Error::DeserializeUnexpectedEnd) if self.none_tag => {
+                self.none_tag = false;
+                0
+            }
+            result => result?,
+        };
+        self.consumed += 1;
+
+        Ok(byte)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.inner.size_hint()
+    }
+
+    fn try_take_n(&mut self, ct: usize) -> postcard::Result<&'de [u8]> {
+        self.last_read = self.consumed;
+        let bytes = self.inner.try_take_n(ct)?;
+        self.consumed += ct;
+
+        Ok(bytes)
+    }
+
+    fn finalize(self) -> postcard::Result<Self::Remainder> {
+        Ok(self.last_read)
+    }
+}
+
+impl ser_flavors::Flavor for Append<'_> {
+    type Output = ();
+
+    fn try_extend(&mut self, data: &[u8]) -> postcard::Result<()> {
+        self.0.extend_from_slice(data);
+        Ok(())
+    }
+
+    fn try_push(&mut self, data: u8) -> postcard::Result<()> {
+        self.0.push(data);
+        Ok(())
+    }
+
+    fn finalize(self) -> postcard::Result<Self::Output> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> ser_flavors::Flavor for &mut IoWriter<W> {
+    type Output = ();
+
+    fn try_extend(&mut self, data: &[u8]) -> postcard::Result<()> {
+        if let Err(err) = self.inner.write_all(data) {
+            self.error = Some(err);
+            return Err(postcard::Error::SerializeBufferFull);
+        }
+
+        self.written += data.len();
+        Ok(())
+    }
+
+    fn try_push(&mut self, data: u8) -> postcard::Result<()> {
+        self.try_extend(&[data])
+    }
+
+    fn finalize(self) -> postcard::Result<Self::Output> {
+        self.inner.flush().map_err(|err| {
+            self.error = Some(err);
+            postcard::Error::SerializeBufferFull
+        })
+    }
+}
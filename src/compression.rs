@@ -0,0 +1,330 @@
+//! Compression of firmware update chunks.
+//!
+//! Nodes advertise the [`Compression`] they can decode in every
+//! [`NextUpdateChunk`](crate::request::Request::NextUpdateChunk) request, and the server
+//! may answer with a compressed [`UpdatePart`](crate::response::Response::UpdatePart).
+//! Every chunk is compressed on its own, so offsets and sizes always refer to the
+//! uncompressed image, and an interrupted download can be resumed at any chunk.
+//!
+//! # LZSS format
+//! [`Compression::Lzss`] is a small LZ77 variant, which can be decoded without any memory
+//! besides the output buffer of the chunk:
+//! - The decompressed size of the chunk, as a little-endian 32-bit unsigned integer.
+//! - Groups of up to 8 tokens, each preceded by a flag byte. Bit `n` *(starting at the
+//!   least significant one)* of the flag byte describes the `n`-th token of the group:
+//!     - `0`: A literal byte.
+//!     - `1`: A back-reference of two bytes. The upper 12 bits contain the distance minus `1`
+//!       *(up to [`WINDOW_SIZE`])*, and the lower 4 bits contain the length minus [`MIN_MATCH`]
+//!       *(up to [`MAX_MATCH`])*.
+//!
+//! ```rust
+//! use pwmp_msg::compression::{compress, decompress, decompressed_size};
+//!
+//! let chunk = b"weather weather weather weather";
+//! let compressed = compress(chunk);
+//! assert!(compressed.len() < chunk.len());
+//!
+//! let mut output = vec![0; decompressed_size(&compressed).unwrap() as usize];
+//! let len = decompress(&compressed, &mut output).unwrap();
+//! assert_eq!(&output[..len], chunk);
+//! ```
+
+#[cfg(feature = "alloc")]
+use alloc::{vec, vec::Vec};
+use core::fmt::Display;
+use serde::{Deserialize, Serialize};
+
+/// Maximum distance of a back-reference.
+pub const WINDOW_SIZE: usize = 4096;
+
+/// Minimum length of a back-reference.
+pub const MIN_MATCH: usize = 3;
+
+/// Maximum length of a back-reference.
+pub const MAX_MATCH: usize = MIN_MATCH + 15;
+
+/// Size of the header containing the decompressed size.
+const HEADER_SIZE: usize = 4;
+
+/// Number of bits of the hash used to find back-references.
+#[cfg(feature = "alloc")]
+const HASH_BITS: u32 = 12;
+
+/// Maximum number of candidates that are checked for every back-reference.
+#[cfg(feature = "alloc")]
+const MAX_CANDIDATES: usize = 64;
+
+/// Compression of firmware update chunks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Compression {
+    /// Chunks are sent as raw bytes.
+    #[default]
+    None,
+
+    /// Chunks are compressed with LZSS, see the [module documentation](self).
+    Lzss,
+}
+
+/// Errors that can occur while decompressing a chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DecompressError {
+    /// The compressed data ended before the chunk was complete.
+    Truncated,
+
+    /// A back-reference points before the start of the chunk, or past its end.
+    InvalidReference,
+
+    /// There is data left after the chunk was complete.
+    TrailingData,
+
+    /// The output buffer is too small for the decompressed chunk.
+    OutputTooSmall {
+        /// Decompressed size of the chunk.
+        required: u32,
+    },
+}
+
+/// Returns the decompressed size of an LZSS-compressed chunk.
+///
+/// # Errors
+/// Returns [`DecompressError::Truncated`] if the data is too short to contain the size.
+pub fn decompressed_size(data: &[u8]) -> Result<u32, DecompressError> {
+    let header = data
+        .first_chunk::<HEADER_SIZE>()
+        .ok_or(DecompressError::Truncated)?;
+
+    Ok(u32::from_le_bytes(*header))
+}
+
+/// Decompress an LZSS-compressed chunk into `output`.
+///
+/// Returns the decompressed size of the chunk.
+///
+/// # Errors
+/// Returns [`DecompressError::OutputTooSmall`] if the chunk doesn't fit into `output`,
+/// and another [`DecompressError`] if the data is corrupted.
+pub fn decompress(data: &[u8], output: &mut [u8]) -> Result<usize, DecompressError> {
+    let required = decompressed_size(data)?;
+    let size = usize::try_from(required)
+        .ok()
+        .filter(|size| *size <= output.len())
+        .ok_or(DecompressError::OutputTooSmall { required })?;
+
+    let mut input = data[HEADER_SIZE..].iter().copied();
+    let mut next = || input.next().ok_or(DecompressError::Truncated);
+    let mut pos = 0;
+
+    while pos < size {
+        let flags = next()?;
+
+        for bit in 0..8 {
+            if pos == size {
+                break;
+            }
+
+            if flags & (1 << bit) == 0 {
+                output[pos] = next()?;
+                pos += 1;
+                continue;
+            }
+
+            let token = u16::from_be_bytes([next()?, next()?]);
+            let distance = usize::from(token >> 4) + 1;
+            let len = usize::from(token & 0xF) + MIN_MATCH;
+
+            if distance > pos || pos + len > size {
+                return Err(DecompressError::InvalidReference);
+            }
+
+            // Copy byte by byte, since the reference may overlap with its own output.
+            for i in pos..pos + len {
+                output[i] = output[i - distance];
+            }
+            pos += len;
+        }
+    }
+
+    if next().is_ok() {
+        return Err(DecompressError::TrailingData);
+    }
+
+    Ok(size)
+}
+
+/// Compress a chunk with LZSS.
+///
+/// The result may be larger than the chunk if it can't be compressed.
+///
+/// # Panics
+/// This will panic if the chunk is larger than [`u32::MAX`] bytes.
+#[cfg(feature = "alloc")]
+#[must_use]
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let size = u32::try_from(data.len()).expect("chunk is too large");
+
+    let mut output = Vec::with_capacity(HEADER_SIZE + data.len() + data.len() / 8 + 1);
+    output.extend_from_slice(&size.to_le_bytes());
+
+    let mut matcher = Matcher::new(data);
+    let mut flags_index = 0;
+    let mut token = 8;
+    let mut pos = 0;
+
+    while pos < data.len() {
+        if token == 8 {
+            flags_index = output.len();
+            output.push(0);
+            token = 0;
+        }
+
+        let (distance, len) = matcher.longest_match(pos);
+
+        if len >= MIN_MATCH {
+            #[allow(clippy::cast_possible_truncation)] // Both fit into their bits.
+            let encoded = (((distance - 1) as u16) << 4) | (len - MIN_MATCH) as u16;
+
+            output[flags_index] |= 1 << token;
+            output.extend_from_slice(&encoded.to_be_bytes());
+
+            for i in pos..pos + len {
+                matcher.insert(i);
+            }
+            pos += len;
+        } else {
+            output.push(data[pos]);
+            matcher.insert(pos);
+            pos += 1;
+        }
+
+        token += 1;
+    }
+
+    output
+}
+
+/// Finds back-references while compressing a chunk.
+#[cfg(feature = "alloc")]
+struct Matcher<'a> {
+    /// The chunk being compressed.
+    data: &'a [u8],
+
+    /// Most recent position of every hash.
+    head: Vec<usize>,
+
+    /// Previous position with the same hash as the position at the index.
+    prev: Vec<usize>,
+}
+
+#[cfg(feature = "alloc")]
+impl<'a> Matcher<'a> {
+    /// Create a new matcher for a chunk.
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            head: vec![usize::MAX; 1 << HASH_BITS],
+            prev: vec![usize::MAX; data.len()],
+        }
+    }
+
+    /// Make the data at `pos` available for back-references.
+    fn insert(&mut self, pos: usize) {
+        if pos + MIN_MATCH <= self.data.len() {
+            let hash = Self::hash(&self.data[pos..pos + MIN_MATCH]);
+            self.prev[pos] = self.head[hash];
+            self.head[hash] = pos;
+        }
+    }
+
+    /// Find the longest back-reference for the data at `pos`.
+    ///
+    /// Returns the distance and the length of the reference.
+    fn longest_match(&self, pos: usize) -> (usize, usize) {
+        if pos + MIN_MATCH > self.data.len() {
+            return (0, 0);
+        }
+
+        let max_len = MAX_MATCH.min(self.data.len() - pos);
+        let mut best = (0, 0);
+        let mut candidate = self.head[Self::hash(&self.data[pos..pos + MIN_MATCH])];
+
+        for _ in 0..MAX_CANDIDATES {
+            if candidate == usize::MAX || pos - candidate > WINDOW_SIZE {
+                break;
+            }
+
+            let len = self.data[candidate..]
+                .iter()
+                .zip(&self.data[pos..pos + max_len])
+                .take_while(|(a, b)| a == b)
+                .count();
+
+            if len > best.1 {
+                best = (pos - candidate, len);
+
+                if len == max_len {
+                    break;
+                }
+            }
+
+            candidate = self.prev[candidate];
+        }
+
+        best
+    }
+
+    /// Hash the first bytes of a back-reference.
+    fn hash(bytes: &[u8]) -> usize {
+        let value = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]);
+        (value.wrapping_mul(2_654_435_761) >> (32 - HASH_BITS)) as usize
+    }
+}
+
+impl Compression {
+    /// Decompress a chunk that was compressed with this method into `output`.
+    ///
+    /// Returns the decompressed size of the chunk.
+    ///
+    /// # Errors
+    /// Returns [`DecompressError::OutputTooSmall`] if the chunk doesn't fit into `output`,
+    /// and another [`DecompressError`] if the data is corrupted.
+    pub fn decompress(self, data: &[u8], output: &mut [u8]) -> Result<usize, DecompressError> {
+        match self {
+            Self::None => {
+                let required = u32::try_from(data.len()).unwrap_or(u32::MAX);
+                output
+                    .get_mut(..data.len())
+                    .ok_or(DecompressError::OutputTooSmall { required })?
+                    .copy_from_slice(data);
+
+                Ok(data.len())
+            }
+            Self::Lzss => decompress(data, output),
+        }
+    }
+
+    /// Returns the decompressed size of a chunk that was compressed with this method.
+    ///
+    /// # Errors
+    /// Returns [`DecompressError::Truncated`] if the data is too short to contain the size.
+    pub fn decompressed_size(self, data: &[u8]) -> Result<u32, DecompressError> {
+        match self {
+            Self::None => Ok(u32::try_from(data.len()).unwrap_or(u32::MAX)),
+            Self::Lzss => decompressed_size(data),
+        }
+    }
+}
+
+impl Display for DecompressError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Truncated => write!(f, "compressed data is truncated"),
+            Self::InvalidReference => write!(f, "invalid back-reference"),
+            Self::TrailingData => write!(f, "trailing data after the compressed chunk"),
+            Self::OutputTooSmall { required } => {
+                write!(f, "output buffer is too small for {required} bytes")
+            }
+        }
+    }
+}
+
+impl core::error::Error for DecompressError {}
@@ -0,0 +1,749 @@
+//! Delta (binary diff) firmware updates.
+//!
+//! Instead of the whole firmware image, the server may send a patch that turns the node's
+//! running firmware *(the base image)* into the new one. The patch is announced with a
+//! [`DeltaManifest`](crate::manifest::DeltaManifest) and downloaded like a full image. The server
+//! creates patches with [`diff()`], and nodes apply them while they're being downloaded with a
+//! [`DeltaApplier`], which reads the base image through a [`BaseImage`] and writes the new
+//! image into a [`FirmwareSink`].
+//!
+//! If the server has no patch for the node's firmware, or the node doesn't support patches,
+//! the full image is sent instead.
+//!
+//! # Patch format
+//! - A header:
+//!     - The magic bytes `PWDP`.
+//!     - The size of the base image, as a little-endian 32-bit unsigned integer.
+//!     - The SHA-256 digest of the base image.
+//!     - The size of the new image, as a little-endian 32-bit unsigned integer.
+//!     - The SHA-256 digest of the new image.
+//! - Operations, until the new image is complete. Each one starts with a tag byte, and its
+//!   arguments are LEB128-encoded 32-bit unsigned integers:
+//!     - `0` *(copy)*: Offset within the base image and length. Copies bytes from the base image.
+//!     - `1` *(insert)*: Length, followed by that many bytes, which are inserted as they are.
+//!
+//! ```rust
+//! use pwmp_msg::{
+//!     assembler::{FirmwareSink, MemorySink},
+//!     delta::{diff, DeltaApplier},
+//! };
+//!
+//! let base = b"temperature: 21.5, humidity: 40, pressure: 1013".as_slice();
+//! let target = b"temperature: 21.5, humidity: 45, pressure: 1013, battery: 3.9";
+//! let patch = diff(base, target);
+//!
+//! let mut applier = DeltaApplier::new(base, MemorySink::new());
+//! applier.begin(patch.len() as u32).unwrap();
+//! for (i, chunk) in patch.chunks(16).enumerate() {
+//!     applier.write(i as u32 * 16, chunk).unwrap();
+//! }
+//! applier.finish().unwrap();
+//!
+//! assert_eq!(applier.into_inner().1.as_slice(), target);
+//! ```
+
+use crate::{
+    assembler::FirmwareSink,
+    codec,
+    ota::{self, Sha256Digest},
+};
+use alloc::{vec, vec::Vec};
+use core::{convert::Infallible, fmt::Display};
+use sha2::{Digest, Sha256};
+#[cfg(feature = "std")]
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// Magic bytes at the start of every patch.
+const MAGIC: [u8; 4] = *b"PWDP";
+
+/// Size of the patch header.
+const HEADER_SIZE: usize = MAGIC.len() + 2 * (4 + 32);
+
+/// Tag of a copy operation.
+const OP_COPY: u8 = 0;
+
+/// Tag of an insert operation.
+const OP_INSERT: u8 = 1;
+
+/// Size of the buffer used to copy data from the base image.
+const COPY_BUFFER_SIZE: usize = 256;
+
+/// Length of the blocks that are matched against the base image while creating a patch.
+const BLOCK_SIZE: usize = 8;
+
+/// Shortest copy that is worth encoding, since shorter ones are cheaper to insert.
+const MIN_COPY: usize = 12;
+
+/// Number of bits of the hash used to find blocks in the base image.
+const HASH_BITS: u32 = 16;
+
+/// Maximum number of candidates that are checked for every copy.
+const MAX_CANDIDATES: usize = 32;
+
+/// Random-access source of the base image, such as the running firmware's flash partition.
+pub trait BaseImage {
+    /// Error returned by the base image.
+    type Error;
+
+    /// Returns the size of the base image.
+    fn size(&self) -> u32;
+
+    /// Fill `buf` with the bytes starting at `offset`.
+    ///
+    /// The requested range is always within the [size](Self::size) of the image.
+    ///
+    /// # Errors
+    /// Returns an error if the bytes can't be read.
+    fn read(&mut self, offset: u32, buf: &mut [u8]) -> Result<(), Self::Error>;
+}
+
+/// A base image that is read from a seekable reader, such as a [`File`](std::fs::File).
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct IoBase<R> {
+    /// The wrapped reader.
+    reader: R,
+
+    /// Size of the image.
+    size: u32,
+}
+
+/// Applies a delta patch as it's downloaded.
+///
+/// The applier is a [`FirmwareSink`] for the patch, so it can be passed to an
+/// [`OtaAssembler`](crate::assembler::OtaAssembler), which verifies the patch before it's applied.
+/// Patch chunks must be written in order. The new image is written into the wrapped sink
+/// in order, too.
+///
+/// The base image is checked against the digest in the patch before anything is written,
+/// and the new image is checked against its digest when the applier is
+/// [finished](FirmwareSink::finish).
+///
+/// A write that fails may have applied part of the chunk already, so the applier can't retry it.
+/// All further writes return [`DeltaError::Aborted`], until the update is started again with
+/// [`begin()`](FirmwareSink::begin).
+#[derive(Debug)]
+pub struct DeltaApplier<B, S> {
+    /// The base image.
+    base: B,
+
+    /// Destination of the new image.
+    sink: S,
+
+    /// Header of the patch, once it's complete.
+    header: Option<Header>,
+
+    /// Bytes of the header received so far.
+    header_buf: [u8; HEADER_SIZE],
+
+    /// Number of patch bytes received so far.
+    received: u32,
+
+    /// Decoding state of the operations.
+    state: State,
+
+    /// Number of bytes of the new image written so far.
+    written: u32,
+
+    /// Digest of the new image written so far.
+    hasher: Sha256,
+
+    /// Whether a write has failed.
+    failed: bool,
+}
+
+/// Errors that can occur while applying a delta patch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeltaError<B, S> {
+    /// A chunk of the patch doesn't start where the previous one ended.
+    UnexpectedOffset {
+        /// Offset at which the next chunk was expected to start.
+        expected: u32,
+
+        /// Offset at which the chunk actually starts.
+        actual: u32,
+    },
+
+    /// The data is not a delta patch.
+    InvalidMagic,
+
+    /// The size or digest of the base image doesn't match the patch.
+    BaseMismatch,
+
+    /// An operation has an unknown tag.
+    InvalidOperation {
+        /// The unknown tag.
+        tag: u8,
+    },
+
+    /// An argument of an operation is not a valid 32-bit integer.
+    InvalidVarint,
+
+    /// A copy operation reads past the end of the base image.
+    CopyOutOfBounds,
+
+    /// An operation writes past the end of the new image.
+    Overflow,
+
+    /// There is data left after the new image is complete.
+    TrailingData,
+
+    /// The patch ended before the new image was complete.
+    Incomplete,
+
+    /// The digest of the new image doesn't match the patch.
+    DigestMismatch,
+
+    /// A previous write has failed, so the patch can't be applied any further.
+    Aborted,
+
+    /// The base image has returned an error.
+    Base(B),
+
+    /// The sink has returned an error.
+    Sink(S),
+}
+
+/// Header of a delta patch.
+#[derive(Debug, Clone, Copy)]
+struct Header {
+    /// Size of the new image.
+    target_size: u32,
+
+    /// SHA-256 digest of the new image.
+    target_sha256: Sha256Digest,
+}
+
+/// Decoding state of the patch operations.
+#[derive(Debug, Clone, Copy)]
+enum State {
+    /// Waiting for the tag of the next operation.
+    Tag,
+
+    /// Decoding the arguments of an operation.
+    Args {
+        /// Tag of the operation.
+        tag: u8,
+
+        /// Arguments decoded so far.
+        args: [u32; 2],
+
+        /// Index of the argument being decoded.
+        index: usize,
+
+        /// Bit position of the next byte of the argument.
+        shift: u32,
+    },
+
+    /// Inserting the given number of remaining bytes.
+    Insert(u32),
+}
+
+impl<B: BaseImage, S: FirmwareSink> DeltaApplier<B, S> {
+    /// Create an applier that reads the `base` image and writes the new image into `sink`.
+    pub fn new(base: B, sink: S) -> Self {
+        Self {
+            base,
+            sink,
+            header: None,
+            header_buf: [0; HEADER_SIZE],
+            received: 0,
+            state: State::Tag,
+            written: 0,
+            hasher: Sha256::new(),
+            failed: false,
+        }
+    }
+
+    /// Returns the number of bytes of the new image written so far.
+    #[must_use]
+    pub const fn written(&self) -> u32 {
+        self.written
+    }
+
+    /// Returns the base image and the sink, consuming the applier.
+    pub fn into_inner(self) -> (B, S) {
+        (self.base, self.sink)
+    }
+
+    /// Consume the bytes of the header, and check the base image once it's complete.
+    ///
+    /// Returns the remaining data.
+    fn push_header<'a>(
+        &mut self,
+        data: &'a [u8],
+    ) -> Result<&'a [u8], DeltaError<B::Error, S::Error>> {
+        let filled = self.received as usize;
+        let (head, rest) = data.split_at(data.len().min(HEADER_SIZE - filled));
+        self.header_buf[filled..filled + head.len()].copy_from_slice(head);
+
+        if filled + head.len() < HEADER_SIZE {
+            return Ok(rest);
+        }
+
+        let header_buf = self.header_buf;
+        let (magic, fields) = header_buf.split_at(MAGIC.len());
+        if magic != MAGIC {
+            return Err(DeltaError::InvalidMagic);
+        }
+
+        let (base_size, fields) = fields.split_at(4);
+        let (base_sha256, fields) = fields.split_at(32);
+        let (target_size, target_sha256) = fields.split_at(4);
+        let base_size = u32::from_le_bytes(base_size.try_into().unwrap_or_default());
+
+        if base_size != self.base.size() || self.base_sha256()? != base_sha256 {
+            return Err(DeltaError::BaseMismatch);
+        }
+
+        let header = Header {
+            target_size: u32::from_le_bytes(target_size.try_into().unwrap_or_default()),
+            target_sha256: target_sha256.try_into().unwrap_or_default(),
+        };
+        self.sink
+            .begin(header.target_size)
+            .map_err(DeltaError::Sink)?;
+        self.header = Some(header);
+
+        Ok(rest)
+    }
+
+    /// Compute the digest of the base image.
+    fn base_sha256(&mut self) -> Result<Sha256Digest, DeltaError<B::Error, S::Error>> {
+        let mut hasher = Sha256::new();
+        let mut buf = [0; COPY_BUFFER_SIZE];
+        let size = self.base.size();
+        let mut offset = 0;
+
+        while offset < size {
+            let len = (size - offset).min(COPY_BUFFER_SIZE as u32);
+            let buf = &mut buf[..len as usize];
+
+            self.base.read(offset, buf).map_err(DeltaError::Base)?;
+            hasher.update(&*buf);
+            offset += len;
+        }
+
+        Ok(hasher.finalize().into())
+    }
+
+    /// Append bytes to the new image.
+    fn output(
+        &mut self,
+        header: &Header,
+        data: &[u8],
+    ) -> Result<(), DeltaError<B::Error, S::Error>> {
+        let end = u32::try_from(data.len())
+            .ok()
+            .and_then(|len| self.written.checked_add(len))
+            .filter(|end| *end <= header.target_size)
+            .ok_or(DeltaError::Overflow)?;
+
+        self.sink
+            .write(self.written, data)
+            .map_err(DeltaError::Sink)?;
+        self.hasher.update(data);
+        self.written = end;
+
+        Ok(())
+    }
+
+    /// Execute a copy operation.
+    fn copy(
+        &mut self,
+        header: &Header,
+        offset: u32,
+        len: u32,
+    ) -> Result<(), DeltaError<B::Error, S::Error>> {
+        if offset
+            .checked_add(len)
+            .is_none_or(|end| end > self.base.size())
+        {
+            return Err(DeltaError::CopyOutOfBounds);
+        }
+
+        let mut buf = [0; COPY_BUFFER_SIZE];
+        let mut copied = 0;
+
+        while copied < len {
+            let n = (len - copied).min(COPY_BUFFER_SIZE as u32);
+            let buf = &mut buf[..n as usize];
+
+            self.base
+                .read(offset + copied, buf)
+                .map_err(DeltaError::Base)?;
+            self.output(header, buf)?;
+            copied += n;
+        }
+
+        Ok(())
+    }
+
+    /// Apply the next chunk of the patch.
+    fn push_chunk(&mut self, data: &[u8]) -> Result<(), DeltaError<B::Error, S::Error>> {
+        let len = u32::try_from(data.len()).map_err(|_| DeltaError::Overflow)?;
+        let ops = match self.header {
+            Some(..) => data,
+            None => self.push_header(data)?,
+        };
+
+        if let Some(header) = self.header {
+            self.push_ops(&header, ops)?;
+        }
+
+        self.received = self.received.saturating_add(len);
+        Ok(())
+    }
+
+    /// Decode and execute operations.
+    fn push_ops(
+        &mut self,
+        header: &Header,
+        mut data: &[u8],
+    ) -> Result<(), DeltaError<B::Error, S::Error>> {
+        while let Some((&byte, rest)) = data.split_first() {
+            match self.state {
+                State::Tag => {
+                    if self.written == header.target_size {
+                        return Err(DeltaError::TrailingData);
+                    }
+
+                    if byte != OP_COPY && byte != OP_INSERT {
+                        return Err(DeltaError::InvalidOperation { tag: byte });
+                    }
+
+                    self.state = State::Args {
+                        tag: byte,
+                        args: [0; 2],
+                        index: 0,
+                        shift: 0,
+                    };
+                    data = rest;
+                }
+                State::Args {
+                    tag,
+                    mut args,
+                    index,
+                    shift,
+                } => {
+                    let bits = u32::from(byte & 0x7F);
+                    if shift > 28 || (shift == 28 && bits > 0xF) {
+                        return Err(DeltaError::InvalidVarint);
+                    }
+                    args[index] |= bits << shift;
+                    data = rest;
+
+                    self.state = if byte & 0x80 != 0 {
+                        State::Args {
+                            tag,
+                            args,
+                            index,
+                            shift: shift + 7,
+                        }
+                    } else if tag == OP_COPY && index == 0 {
+                        State::Args {
+                            tag,
+                            args,
+                            index: 1,
+                            shift: 0,
+                        }
+                    } else if tag == OP_COPY {
+                        self.copy(header, args[0], args[1])?;
+                        State::Tag
+                    } else {
+                        State::Insert(args[0])
+                    };
+                }
+                State::Insert(remaining) => {
+                    let len = data.len().min(remaining as usize);
+                    let (insert, rest) = data.split_at(len);
+
+                    self.output(header, insert)?;
+                    #[allow(clippy::cast_possible_truncation)] // Not more than `remaining`.
+                    let remaining = remaining - len as u32;
+                    self.state = State::Insert(remaining);
+                    data = rest;
+                }
+            }
+
+            if matches!(self.state, State::Insert(0)) {
+                self.state = State::Tag;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<B: BaseImage, S: FirmwareSink> FirmwareSink for DeltaApplier<B, S> {
+    type Error = DeltaError<B::Error, S::Error>;
+
+    /// Prepare for a patch of `size` bytes.
+    ///
+    /// The wrapped sink is prepared once the size of the new image is known from the patch header.
+    fn begin(&mut self, size: u32) -> Result<(), Self::Error> {
+        let _ = size;
+
+        self.header = None;
+        self.received = 0;
+        self.state = State::Tag;
+        self.written = 0;
+        self.hasher = Sha256::new();
+        self.failed = false;
+
+        Ok(())
+    }
+
+    fn write(&mut self, offset: u32, data: &[u8]) -> Result<(), Self::Error> {
+        if self.failed {
+            return Err(DeltaError::Aborted);
+        }
+
+        if offset != self.received {
+            return Err(DeltaError::UnexpectedOffset {
+                expected: self.received,
+                actual: offset,
+            });
+        }
+
+        let result = self.push_chunk(data);
+        self.failed = result.is_err();
+        result
+    }
+
+    fn finish(&mut self) -> Result<(), Self::Error> {
+        let Some(header) = self.header else {
+            return Err(DeltaError::Incomplete);
+        };
+
+        if self.written != header.target_size || !matches!(self.state, State::Tag) {
+            return Err(DeltaError::Incomplete);
+        }
+
+        if Sha256Digest::from(self.hasher.clone().finalize()) != header.target_sha256 {
+            return Err(DeltaError::DigestMismatch);
+        }
+
+        self.sink.finish().map_err(DeltaError::Sink)
+    }
+}
+
+/// Create a patch that turns the `base` image into the `target` image.
+///
+/// # Panics
+/// This will panic if either image is larger than [`u32::MAX`] bytes.
+#[must_use]
+pub fn diff(base: &[u8], target: &[u8]) -> Vec<u8> {
+    let base_size = u32::try_from(base.len()).expect("base image is too large");
+    let target_size = u32::try_from(target.len()).expect("target image is too large");
+
+    let mut patch = Vec::with_capacity(HEADER_SIZE + target.len() / 4);
+    patch.extend_from_slice(&MAGIC);
+    patch.extend_from_slice(&base_size.to_le_bytes());
+    patch.extend_from_slice(&ota::sha256(base));
+    patch.extend_from_slice(&target_size.to_le_bytes());
+    patch.extend_from_slice(&ota::sha256(target));
+
+    let index = BlockIndex::new(base);
+    let mut insert_start = 0;
+    let mut pos = 0;
+    // Where the base image continues after the last copy, which is the best candidate
+    // for the next one if only a few bytes have changed in between.
+    let mut continuation = 0;
+
+    while pos < target.len() {
+        let guess = continuation + (pos - insert_start);
+        let (offset, len) = index.longest_match(target, pos, guess);
+
+        if len < MIN_COPY {
+            pos += 1;
+            continue;
+        }
+
+        encode_insert(&mut patch, &target[insert_start..pos]);
+        patch.push(OP_COPY);
+        codec::append_varint(offset as u64, &mut patch);
+        codec::append_varint(len as u64, &mut patch);
+
+        pos += len;
+        insert_start = pos;
+        continuation = offset + len;
+    }
+
+    encode_insert(&mut patch, &target[insert_start..]);
+    patch
+}
+
+/// Append an insert operation, unless `data` is empty.
+fn encode_insert(patch: &mut Vec<u8>, data: &[u8]) {
+    if data.is_empty() {
+        return;
+    }
+
+    patch.push(OP_INSERT);
+    codec::append_varint(data.len() as u64, patch);
+    patch.extend_from_slice(data);
+}
+
+/// Finds blocks of the base image while creating a patch.
+struct BlockIndex<'a> {
+    /// The base image.
+    base: &'a [u8],
+
+    /// Most recent position of every hash.
+    head: Vec<usize>,
+
+    /// Previous position with the same hash as the position at the index.
+    prev: Vec<usize>,
+}
+
+impl<'a> BlockIndex<'a> {
+    /// Index every block of the base image.
+    fn new(base: &'a [u8]) -> Self {
+        let mut head = vec![usize::MAX; 1 << HASH_BITS];
+        let mut prev = vec![usize::MAX; base.len()];
+
+        for pos in 0..base.len().saturating_sub(BLOCK_SIZE - 1) {
+            let hash = Self::hash(&base[pos..pos + BLOCK_SIZE]);
+            prev[pos] = head[hash];
+            head[hash] = pos;
+        }
+
+        Self { base, head, prev }
+    }
+
+    /// Find the longest part of the base image that matches the target at `pos`.
+    ///
+    /// The base offset `guess` is checked first. Returns the offset and length of the match.
+    fn longest_match(&self, target: &[u8], pos: usize, guess: usize) -> (usize, usize) {
+        let matching = |offset: usize| {
+            self.base[offset..]
+                .iter()
+                .zip(&target[pos..])
+                .take_while(|(a, b)| a == b)
+                .count()
+        };
+
+        let mut best = if guess < self.base.len() {
+            (guess, matching(guess))
+        } else {
+            (0, 0)
+        };
+
+        if pos + BLOCK_SIZE > target.len() {
+            return best;
+        }
+
+        let mut candidate = self.head[Self::hash(&target[pos..pos + BLOCK_SIZE])];
+        for _ in 0..MAX_CANDIDATES {
+            if candidate == usize::MAX {
+                break;
+            }
+
+            let len = matching(candidate);
+            if len > best.1 {
+                best = (candidate, len);
+            }
+
+            candidate = self.prev[candidate];
+        }
+
+        best
+    }
+
+    /// Hash a block.
+    fn hash(block: &[u8]) -> usize {
+        let value = u64::from_le_bytes(block.try_into().unwrap_or_default());
+        (value.wrapping_mul(0x9E37_79B9_7F4A_7C15) >> (64 - HASH_BITS)) as usize
+    }
+}
+
+impl BaseImage for &[u8] {
+    type Error = Infallible;
+
+    #[allow(clippy::cast_possible_truncation)]
+    fn size(&self) -> u32 {
+        self.len().min(u32::MAX as usize) as u32
+    }
+
+    fn read(&mut self, offset: u32, buf: &mut [u8]) -> Result<(), Self::Error> {
+        let start = offset as usize;
+        buf.copy_from_slice(&self[start..start + buf.len()]);
+        Ok(())
+    }
+}
+
+impl<T: BaseImage + ?Sized> BaseImage for &mut T {
+    type Error = T::Error;
+
+    fn size(&self) -> u32 {
+        (**self).size()
+    }
+
+    fn read(&mut self, offset: u32, buf: &mut [u8]) -> Result<(), Self::Error> {
+        (**self).read(offset, buf)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> IoBase<R> {
+    /// Wrap a seekable reader that contains an image of `size` bytes.
+    pub const fn new(reader: R, size: u32) -> Self {
+        Self { reader, size }
+    }
+
+    /// Returns the wrapped reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: Read + Seek> BaseImage for IoBase<R> {
+    type Error = io::Error;
+
+    fn size(&self) -> u32 {
+        self.size
+    }
+
+    fn read(&mut self, offset: u32, buf: &mut [u8]) -> Result<(), Self::Error> {
+        self.reader.seek(SeekFrom::Start(offset.into()))?;
+        self.reader.read_exact(buf)
+    }
+}
+
+impl<B: Display, S: Display> Display for DeltaError<B, S> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::UnexpectedOffset { expected, actual } => {
+                write!(f, "expected patch chunk at offset {expected}, got {actual}")
+            }
+            Self::InvalidMagic => write!(f, "data is not a delta patch"),
+            Self::BaseMismatch => write!(f, "base image doesn't match the patch"),
+            Self::InvalidOperation { tag } => write!(f, "invalid patch operation {tag}"),
+            Self::InvalidVarint => write!(f, "invalid operation argument"),
+            Self::CopyOutOfBounds => write!(f, "copy exceeds the base image"),
+            Self::Overflow => write!(f, "patch exceeds the size of the new image"),
+            Self::TrailingData => write!(f, "trailing data after the new image is complete"),
+            Self::Incomplete => write!(f, "patch is incomplete"),
+            Self::DigestMismatch => write!(f, "new image digest mismatch"),
+            Self::Aborted => write!(f, "patch aborted after a failed write"),
+            Self::Base(err) => write!(f, "base image error: {err}"),
+            Self::Sink(err) => write!(f, "sink error: {err}"),
+        }
+    }
+}
+
+impl<B, S> core::error::Error for DeltaError<B, S>
+where
+    B: core::error::Error + 'static,
+    S: core::error::Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::Base(err) => Some(err),
+            Self::Sink(err) => Some(err),
+            _ => None,
+        }
+    }
+}
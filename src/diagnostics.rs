@@ -0,0 +1,120 @@
+//! Diagnostics of a node.
+//!
+//! Nodes post [`Diagnostics`] along with their statistics in
+//! [`PostStats`](crate::request::Request::PostStats), to help finding out why a node misbehaves
+//! in the field. Except for the uptime, every value is optional, so simple nodes can omit what
+//! they don't track.
+//!
+//! ```rust
+//! use pwmp_msg::{
+//!     diagnostics::{Diagnostics, ResetReason, WakeReason},
+//!     mac::Mac,
+//! };
+//!
+//! let diagnostics = Diagnostics {
+//!     reset_reason: Some(ResetReason::Wake(WakeReason::Timer)),
+//!     bssid: Some(Mac::new(0x02, 0x00, 0x5E, 0x10, 0x20, 0x30)),
+//!     wifi_channel: Some(6),
+//!     ..Diagnostics::new(4_250)
+//! };
+//!
+//! assert_eq!(diagnostics.uptime, 4_250);
+//! assert_eq!(diagnostics.free_heap, None);
+//! ```
+
+use crate::mac::Mac;
+use serde::{Deserialize, Serialize};
+
+/// Diagnostic values of a node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Diagnostics {
+    /// Time since the node has started in milliseconds.
+    ///
+    /// Nodes that sleep between sessions restart after every wake-up.
+    pub uptime: u64,
+
+    /// Why the node has started.
+    pub reset_reason: Option<ResetReason>,
+
+    /// Free heap memory in bytes.
+    pub free_heap: Option<u32>,
+
+    /// Lowest amount of free heap memory in bytes since the node has started.
+    pub min_free_heap: Option<u32>,
+
+    /// Channel of the wireless network.
+    pub wifi_channel: Option<u8>,
+
+    /// BSSID *(MAC address of the access point)* of the wireless network.
+    pub bssid: Option<Mac>,
+
+    /// Time it took to connect to the wireless network and the server in milliseconds.
+    pub connect_time: Option<u32>,
+
+    /// Number of sessions that have failed since the last successful one.
+    pub failed_sessions: Option<u16>,
+}
+
+/// Reason why a node has started.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ResetReason {
+    /// The node was powered on.
+    PowerOn,
+
+    /// The reset pin was triggered.
+    External,
+
+    /// The firmware has restarted the node.
+    Software,
+
+    /// The firmware has panicked.
+    Panic,
+
+    /// A watchdog has reset the node.
+    Watchdog,
+
+    /// The supply voltage has dropped too low.
+    Brownout,
+
+    /// The node woke up from deep sleep.
+    Wake(WakeReason),
+
+    /// Any other reason.
+    Other,
+}
+
+/// Reason why a node woke up from deep sleep.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum WakeReason {
+    /// The sleep timer has expired.
+    Timer,
+
+    /// An external signal on a pin, such as a button or a sensor interrupt.
+    External,
+
+    /// A touch pad was touched.
+    Touch,
+
+    /// The ultra-low-power coprocessor has woken the node.
+    Ulp,
+
+    /// Any other reason.
+    Other,
+}
+
+impl Diagnostics {
+    /// Create diagnostics with only the uptime.
+    #[must_use]
+    pub const fn new(uptime: u64) -> Self {
+        Self {
+            uptime,
+            reset_reason: None,
+            free_heap: None,
+            min_free_heap: None,
+            wifi_channel: None,
+            bssid: None,
+            connect_time: None,
+            failed_sessions: None,
+        }
+    }
+}
@@ -0,0 +1,178 @@
+//! Error types returned when encoding and decoding messages.
+
+use core::fmt::Display;
+#[cfg(feature = "std")]
+use std::io;
+
+/// Describes why a message could not be decoded.
+///
+/// Every variant carries the byte offset of the read that failed, relative to the
+/// start of the decoded buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DecodeError {
+    /// The buffer ended before the message was complete.
+    UnexpectedEnd {
+        /// Offset of the read that ran past the end of the buffer.
+        offset: usize,
+    },
+
+    /// A variable-length integer was malformed or too large for its type.
+    InvalidVarint {
+        /// Offset of the last byte of the integer.
+        offset: usize,
+    },
+
+    /// A boolean was neither `0` nor `1`.
+    InvalidBool {
+        /// Offset of the boolean.
+        offset: usize,
+    },
+
+    /// An optional value's tag was neither `0` nor `1`.
+    InvalidOption {
+        /// Offset of the tag.
+        offset: usize,
+    },
+
+    /// A string contained invalid UTF-8.
+    InvalidUtf8 {
+        /// Offset of the start of the string's contents.
+        offset: usize,
+    },
+
+    /// A value was rejected by its type, such as an enum tag that does not name any known variant.
+    InvalidValue {
+        /// Offset of the last byte of the rejected value.
+        offset: usize,
+    },
+
+    /// The data is not a valid encoding for any other reason.
+    Malformed {
+        /// Offset where decoding stopped.
+        offset: usize,
+    },
+}
+
+impl DecodeError {
+    /// Returns the byte offset at which decoding failed.
+    ///
+    /// ```rust
+    /// use pwmp_msg::{error::DecodeError, Message};
+    ///
+    /// // The buffer ends right after the message ID.
+    /// let error = Message::deserialize(&[1]).unwrap_err();
+    ///
+    /// assert_eq!(error, DecodeError::UnexpectedEnd { offset: 1 });
+    /// assert_eq!(error.offset(), 1);
+    /// ```
+    #[must_use]
+    pub const fn offset(&self) -> usize {
+        match self {
+            Self::UnexpectedEnd { offset }
+            | Self::InvalidVarint { offset }
+            | Self::InvalidBool { offset }
+            | Self::InvalidOption { offset }
+            | Self::InvalidUtf8 { offset }
+            | Self::InvalidValue { offset }
+            | Self::Malformed { offset } => *offset,
+        }
+    }
+
+    /// Map a `postcard` error to a decode error at the given offset.
+    #[cfg(feature = "alloc")]
+    pub(crate) const fn from_postcard(error: &postcard::Error, offset: usize) -> Self {
+        match error {
+            postcard::Error::DeserializeUnexpectedEnd => Self::UnexpectedEnd { offset },
+            postcard::Error::DeserializeBadVarint => Self::InvalidVarint { offset },
+            postcard::Error::DeserializeBadBool => Self::InvalidBool { offset },
+            postcard::Error::DeserializeBadOption => Self::InvalidOption { offset },
+            postcard::Error::DeserializeBadUtf8 => Self::InvalidUtf8 { offset },
+            postcard::Error::DeserializeBadEnum | postcard::Error::SerdeDeCustom => {
+                Self::InvalidValue { offset }
+            }
+            _ => Self::Malformed { offset },
+        }
+    }
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let reason = match self {
+            Self::UnexpectedEnd { .. } => "unexpected end of data",
+            Self::InvalidVarint { .. } => "invalid variable-length integer",
+            Self::InvalidBool { .. } => "invalid boolean",
+            Self::InvalidOption { .. } => "invalid optional value tag",
+            Self::InvalidUtf8 { .. } => "invalid UTF-8 in string",
+            Self::InvalidValue { .. } => "invalid value",
+            Self::Malformed { .. } => "malformed data",
+        };
+
+        write!(f, "{reason} at offset {}", self.offset())
+    }
+}
+
+impl core::error::Error for DecodeError {}
+
+/// Describes why a message could not be encoded.
+#[derive(Debug)]
+pub enum EncodeError {
+    /// The provided buffer is too small to hold the serialized message.
+    BufferFull,
+
+    /// The message could not be represented in the wire format.
+    Unsupported,
+
+    /// An I/O error occurred while writing the serialized message.
+    #[cfg(feature = "std")]
+    Io(io::Error),
+}
+
+impl EncodeError {
+    /// Map a `postcard` error to an encode error.
+    #[cfg(feature = "alloc")]
+    pub(crate) const fn from_postcard(error: &postcard::Error) -> Self {
+        match error {
+            postcard::Error::SerializeBufferFull => Self::BufferFull,
+            _ => Self::Unsupported,
+        }
+    }
+}
+
+impl PartialEq for EncodeError {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::BufferFull, Self::BufferFull) | (Self::Unsupported, Self::Unsupported) => true,
+            #[cfg(feature = "std")]
+            (Self::Io(err), Self::Io(other_err)) => err.kind() == other_err.kind(),
+            _ => false,
+        }
+    }
+}
+
+impl Display for EncodeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::BufferFull => write!(f, "buffer is too small for the serialized message"),
+            Self::Unsupported => write!(f, "message cannot be represented in the wire format"),
+            #[cfg(feature = "std")]
+            Self::Io(err) => write!(f, "I/O error: {err}"),
+        }
+    }
+}
+
+impl core::error::Error for EncodeError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "std")]
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for EncodeError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
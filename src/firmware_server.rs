@@ -0,0 +1,327 @@
+//! Serving firmware updates on the server's side.
+//!
+//! The [`FirmwareServer`] holds a firmware image with its signed manifest, and answers
+//! [`UpdateCheck`](Request::UpdateCheck) and [`NextUpdateChunk`](Request::NextUpdateChunk)
+//! requests. Since chunk requests carry their offset, it doesn't keep any state per node.
+//!
+//! Besides the full image, a server can hold [delta patches](crate::delta) from older firmware
+//! versions, which are offered to nodes that ask for them. Nodes running any other version
+//! receive the full image.
+//!
+//! A server holds a single release. Choosing the release for a node's channel and board
+//! is left to the caller, see the [`rollout`](crate::rollout) module.
+
+use crate::{
+    compression::Compression, manifest::SignedManifest, ota::sha256, request::Request,
+    response::Response, version::Version,
+};
+use alloc::{boxed::Box, vec::Vec};
+use core::fmt::Display;
+
+/// Default maximum size of a served chunk.
+///
+/// This leaves plenty of room for the rest of the message within the
+/// [default frame size](crate::framing::DEFAULT_MAX_FRAME_SIZE).
+pub const DEFAULT_MAX_CHUNK_SIZE: u32 = 4096;
+
+/// Answers update requests with a firmware image, and optionally with delta patches.
+///
+/// ```rust
+/// use pwmp_msg::{
+///     compression::Compression,
+///     firmware_server::FirmwareServer,
+///     manifest::{FirmwareManifest, SigningKey},
+///     ota::sha256,
+///     request::Request,
+///     response::Response,
+///     rollout::ReleaseChannel,
+///     version::Version,
+/// };
+///
+/// let image = vec![0xAB; 10_000];
+/// let manifest = FirmwareManifest {
+///     version: Version::new(1, 1, 0),
+///     size: image.len() as u32,
+///     sha256: sha256(&image),
+///     min_version: Version::new(1, 0, 0),
+///     target: "esp32s3".into(),
+///     delta: None,
+/// }
+/// .sign(&SigningKey::from_bytes(&[7; 32]));
+///
+/// let server = FirmwareServer::new(image, manifest).unwrap();
+///
+/// assert_eq!(
+///     server.respond(&Request::UpdateCheck {
+///         firmware: Version::new(1, 1, 0),
+///         channel: ReleaseChannel::Stable,
+///         board: "esp32s3".into(),
+///         delta: false,
+///     }),
+///     Some(Response::FirmwareUpToDate)
+/// );
+///
+/// // Requested sizes are clamped to the server's maximum.
+/// let part = server.respond(&Request::NextUpdateChunk {
+///     offset: 0,
+///     max_size: u32::MAX,
+///     compression: Compression::None,
+///     base: None,
+/// });
+/// assert!(matches!(part, Some(Response::UpdatePart { data, .. }) if data.len() == 4096));
+/// ```
+#[derive(Debug, Clone)]
+pub struct FirmwareServer {
+    /// The firmware image.
+    image: Box<[u8]>,
+
+    /// Signed manifest of the image.
+    manifest: SignedManifest,
+
+    /// Delta patches to the image.
+    deltas: Vec<DeltaPatch>,
+
+    /// Largest chunk that will be served.
+    max_chunk_size: u32,
+}
+
+/// A delta patch held by a [`FirmwareServer`].
+#[derive(Debug, Clone)]
+struct DeltaPatch {
+    /// Firmware version the patch applies to.
+    base: Version,
+
+    /// The patch.
+    patch: Box<[u8]>,
+
+    /// Signed manifest announcing the patch.
+    manifest: SignedManifest,
+}
+
+/// Errors that can occur while creating a [`FirmwareServer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FirmwareServerError {
+    /// The image is larger than the protocol allows *([`u32::MAX`] bytes)*.
+    ImageTooLarge,
+
+    /// The image doesn't match the size or digest of its manifest.
+    ManifestMismatch,
+
+    /// The delta patch doesn't match its manifest, or the manifest doesn't describe the served
+    /// image.
+    DeltaMismatch,
+}
+
+impl FirmwareServer {
+    /// Create a new server for the given image and its signed manifest.
+    ///
+    /// The signature is not checked, but the manifest must describe the image, and must not
+    /// announce a delta patch.
+    ///
+    /// # Errors
+    /// Returns [`FirmwareServerError::ImageTooLarge`] if the image can't be transferred, and
+    /// [`FirmwareServerError::ManifestMismatch`] if the manifest describes a different image.
+    pub fn new(
+        image: impl Into<Box<[u8]>>,
+        manifest: SignedManifest,
+    ) -> Result<Self, FirmwareServerError> {
+        let image = image.into();
+
+        if u32::try_from(image.len()).is_err() {
+            return Err(FirmwareServerError::ImageTooLarge);
+        }
+
+        let unverified = manifest.manifest_unverified();
+        if unverified.delta.is_some() || !unverified.matches_image(&image) {
+            return Err(FirmwareServerError::ManifestMismatch);
+        }
+
+        Ok(Self {
+            image,
+            manifest,
+            deltas: Vec::new(),
+            max_chunk_size: DEFAULT_MAX_CHUNK_SIZE,
+        })
+    }
+
+    /// Set the largest chunk that will be served.
+    ///
+    /// A maximum of `0` is treated as `1`.
+    #[must_use]
+    pub fn with_max_chunk_size(mut self, max_chunk_size: u32) -> Self {
+        self.max_chunk_size = max_chunk_size.max(1);
+        self
+    }
+
+    /// Add a delta patch, which is offered to nodes running its base firmware.
+    ///
+    /// The manifest must announce the patch and describe the served image, the signature is
+    /// not checked. A patch for the same base replaces the previous one.
+    ///
+    /// # Errors
+    /// Returns [`FirmwareServerError::ImageTooLarge`] if the patch can't be transferred, and
+    /// [`FirmwareServerError::DeltaMismatch`] if the manifest describes a different patch or image.
+    pub fn with_delta(
+        mut self,
+        patch: impl Into<Box<[u8]>>,
+        manifest: SignedManifest,
+    ) -> Result<Self, FirmwareServerError> {
+        let patch = patch.into();
+        let size = u32::try_from(patch.len()).map_err(|_| FirmwareServerError::ImageTooLarge)?;
+
+        let unverified = manifest.manifest_unverified();
+        let full = self.manifest.manifest_unverified();
+        let Some(delta) = unverified.delta else {
+            return Err(FirmwareServerError::DeltaMismatch);
+        };
+
+        if unverified.version != full.version
+            || unverified.size != full.size
+            || unverified.sha256 != full.sha256
+            || delta.size != size
+            || delta.sha256 != sha256(&patch)
+        {
+            return Err(FirmwareServerError::DeltaMismatch);
+        }
+
+        self.deltas.retain(|existing| existing.base != delta.base);
+        self.deltas.push(DeltaPatch {
+            base: delta.base,
+            patch,
+            manifest,
+        });
+
+        Ok(self)
+    }
+
+    /// Returns the largest chunk that will be served.
+    #[must_use]
+    pub const fn max_chunk_size(&self) -> u32 {
+        self.max_chunk_size
+    }
+
+    /// Returns the version of the served firmware.
+    #[must_use]
+    pub const fn version(&self) -> Version {
+        self.manifest.manifest_unverified().version
+    }
+
+    /// Returns the signed manifest of the served firmware.
+    #[must_use]
+    pub const fn manifest(&self) -> &SignedManifest {
+        &self.manifest
+    }
+
+    /// Returns the served firmware image.
+    #[must_use]
+    pub const fn image(&self) -> &[u8] {
+        &self.image
+    }
+
+    /// Returns the versions for which a delta patch is available.
+    pub fn delta_bases(&self) -> impl Iterator<Item = Version> + '_ {
+        self.deltas.iter().map(|patch| patch.base)
+    }
+
+    /// Answer an update check from a node running the firmware `running`.
+    ///
+    /// Returns [`UpdateAvailable`](Response::UpdateAvailable) if the served firmware is newer,
+    /// and [`FirmwareUpToDate`](Response::FirmwareUpToDate) otherwise. Nodes running an older
+    /// firmware than the [`min_version`](crate::manifest::FirmwareManifest::min_version) of the
+    /// manifest can't install the update, so they're told that their firmware is up to date,
+    /// too. If the node accepts
+    /// `delta` patches and one is available for its firmware, the patch is announced instead
+    /// of the full image.
+    #[must_use]
+    pub fn check_update(&self, running: Version, delta: bool) -> Response {
+        if running >= self.version() || running < self.manifest.manifest_unverified().min_version {
+            return Response::FirmwareUpToDate;
+        }
+
+        let manifest = self
+            .find_delta(running)
+            .filter(|_| delta)
+            .map_or(&self.manifest, |patch| &patch.manifest);
+
+        Response::UpdateAvailable(manifest.clone())
+    }
+
+    /// Answer a request for the chunk starting at `offset`.
+    ///
+    /// The chunk is taken from the delta patch from the `base` firmware if one is given, and
+    /// from the full image otherwise. It is at most `max_size` bytes long, and never longer than
+    /// the server's maximum. It's compressed using `compression`, unless that doesn't make it
+    /// smaller.
+    /// Returns [`UpdateEnd`](Response::UpdateEnd) once the whole download has been served, and
+    /// [`InvalidRequest`](Response::InvalidRequest) if the offset is past its end, the requested
+    /// size is `0`, or there is no patch from `base`.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)] // All sizes were checked when adding them.
+    pub fn chunk(
+        &self,
+        offset: u32,
+        max_size: u32,
+        compression: Compression,
+        base: Option<Version>,
+    ) -> Response {
+        let payload = match base {
+            Some(base) => match self.find_delta(base) {
+                Some(delta) => &delta.patch,
+                None => return Response::InvalidRequest,
+            },
+            None => &self.image,
+        };
+        let size = payload.len() as u32;
+
+        if offset == size {
+            return Response::UpdateEnd;
+        }
+
+        if offset > size || max_size == 0 {
+            return Response::InvalidRequest;
+        }
+
+        let len = max_size.min(self.max_chunk_size).min(size - offset);
+        let start = offset as usize;
+        let data = &payload[start..start + len as usize];
+
+        Response::compressed_update_part(offset, size, data, compression)
+    }
+
+    /// Answer an [`UpdateCheck`](Request::UpdateCheck) or a
+    /// [`NextUpdateChunk`](Request::NextUpdateChunk) request.
+    ///
+    /// Returns [`None`] for other requests.
+    #[must_use]
+    pub fn respond(&self, request: &Request) -> Option<Response> {
+        match request {
+            Request::UpdateCheck {
+                firmware, delta, ..
+            } => Some(self.check_update(*firmware, *delta)),
+            Request::NextUpdateChunk {
+                offset,
+                max_size,
+                compression,
+                base,
+            } => Some(self.chunk(*offset, *max_size, *compression, *base)),
+            _ => None,
+        }
+    }
+
+    /// Find the delta patch from the `base` firmware.
+    fn find_delta(&self, base: Version) -> Option<&DeltaPatch> {
+        self.deltas.iter().find(|patch| patch.base == base)
+    }
+}
+
+impl Display for FirmwareServerError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::ImageTooLarge => write!(f, "firmware image is too large"),
+            Self::ManifestMismatch => write!(f, "firmware image doesn't match its manifest"),
+            Self::DeltaMismatch => write!(f, "delta patch doesn't match its manifest"),
+        }
+    }
+}
+
+impl core::error::Error for FirmwareServerError {}
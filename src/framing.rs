@@ -0,0 +1,322 @@
+//! Length-prefixed framing of messages on a byte stream.
+//!
+//! Every [`Message`] sent over a stream (such as a TCP socket) is preceded by a
+//! header containing the length of the serialized message as a big-endian
+//! [`u32`]:
+//!
+//! ```text
+//! +----------------+----------------------------+
+//! | length (4 B)   | serialized message         |
+//! +----------------+----------------------------+
+//! ```
+//!
+//! The [`FrameCodec`] takes care of both directions. Incoming bytes can be
+//! pushed into it as they arrive, regardless of how the stream has split them,
+//! and complete messages can then be popped out one by one.
+
+use crate::{
+    error::{DecodeError, EncodeError},
+    Message,
+};
+use alloc::vec::Vec;
+use core::fmt::Display;
+#[cfg(feature = "std")]
+use std::io::{self, Read, Write};
+
+/// Size of the frame header in bytes.
+pub const HEADER_SIZE: usize = size_of::<u32>();
+
+/// Default maximum size of a frame's payload *(excluding the header)*.
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 64 * 1024;
+
+/// Encoder and decoder for length-prefixed message frames.
+///
+/// ```rust
+/// use pwmp_msg::{framing::FrameCodec, request::Request, Message};
+///
+/// let mut codec = FrameCodec::new();
+/// let mut stream = Vec::new();
+///
+/// codec.encode(&Message::new_request(Request::Ping, 1), &mut stream).unwrap();
+/// codec.encode(&Message::new_request(Request::GetSettings, 2), &mut stream).unwrap();
+///
+/// // Feed the stream in pieces, the way a socket might deliver it.
+/// let (first, second) = stream.split_at(3);
+/// codec.push(first);
+/// assert_eq!(codec.decode(), Ok(None));
+///
+/// codec.push(second);
+/// assert_eq!(codec.decode().unwrap().unwrap().id(), 1);
+/// assert_eq!(codec.decode().unwrap().unwrap().id(), 2);
+/// assert_eq!(codec.decode(), Ok(None));
+/// ```
+#[derive(Debug, Clone)]
+pub struct FrameCodec {
+    /// Largest accepted payload size.
+    max_frame_size: usize,
+
+    /// Received bytes that haven't been decoded yet.
+    buffer: Vec<u8>,
+}
+
+/// Errors that can occur while encoding or decoding frames.
+#[derive(Debug)]
+pub enum FrameError {
+    /// The frame's payload is larger than the configured maximum.
+    ///
+    /// When this is returned while decoding, the stream can no longer be trusted
+    /// and the connection should be closed.
+    TooLarge {
+        /// Size of the rejected payload.
+        size: usize,
+
+        /// Configured maximum payload size.
+        max: usize,
+    },
+
+    /// The message could not be serialized.
+    Encode(EncodeError),
+
+    /// The frame's payload does not contain a valid message.
+    Decode(DecodeError),
+
+    /// The stream was closed before a complete frame was received.
+    UnexpectedEof,
+
+    /// An I/O error occurred on the underlying stream.
+    #[cfg(feature = "std")]
+    Io(io::Error),
+}
+
+impl FrameCodec {
+    /// Create a new codec with the [default](DEFAULT_MAX_FRAME_SIZE) maximum frame size.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self::with_max_frame_size(DEFAULT_MAX_FRAME_SIZE)
+    }
+
+    /// Create a new codec that accepts payloads of at most `max_frame_size` bytes.
+    ///
+    /// Values larger than [`u32::MAX`] are clamped, since such frames cannot be represented.
+    #[must_use]
+    pub const fn with_max_frame_size(max_frame_size: usize) -> Self {
+        let max_frame_size = if max_frame_size > u32::MAX as usize {
+            u32::MAX as usize
+        } else {
+            max_frame_size
+        };
+
+        Self {
+            max_frame_size,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Returns the maximum accepted payload size.
+    #[must_use]
+    pub const fn max_frame_size(&self) -> usize {
+        self.max_frame_size
+    }
+
+    /// Returns the number of buffered bytes that haven't been decoded yet.
+    #[must_use]
+    pub const fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Serialize a message and append it, including the header, to `dst`.
+    ///
+    /// # Errors
+    /// Returns [`FrameError::TooLarge`] if the serialized message exceeds the maximum
+    /// frame size, or [`FrameError::Encode`] if it could not be serialized.
+    /// Nothing is appended in either case.
+    #[allow(clippy::missing_panics_doc)]
+    pub fn encode(&self, message: &Message, dst: &mut Vec<u8>) -> Result<(), FrameError> {
+        let start = dst.len();
+        dst.extend_from_slice(&[0; HEADER_SIZE]);
+
+        let size = match message.serialize_append(dst) {
+            Ok(size) => size,
+            Err(err) => {
+                dst.truncate(start);
+                return Err(err.into());
+            }
+        };
+
+        if let Err(err) = self.check_size(size) {
+            dst.truncate(start);
+            return Err(err);
+        }
+
+        // `check_size()` guarantees that the length fits.
+        let header = u32::try_from(size).unwrap().to_be_bytes();
+        dst[start..start + HEADER_SIZE].copy_from_slice(&header);
+
+        Ok(())
+    }
+
+    /// Append received bytes to the internal buffer.
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Try to decode the next message from the buffered bytes.
+    ///
+    /// Returns `Ok(None)` if the buffer does not contain a complete frame yet.
+    ///
+    /// # Errors
+    /// Returns [`FrameError::TooLarge`] as soon as a header announcing an oversized payload
+    /// is received, and [`FrameError::Decode`] if a complete frame could not be deserialized.
+    /// A malformed frame is discarded, so decoding can continue with the next one.
+    pub fn decode(&mut self) -> Result<Option<Message>, FrameError> {
+        let Some(size) = self.peek_size() else {
+            return Ok(None);
+        };
+        self.check_size(size)?;
+
+        let end = HEADER_SIZE + size;
+        if self.buffer.len() < end {
+            return Ok(None);
+        }
+
+        let message = Message::deserialize(&self.buffer[HEADER_SIZE..end]);
+        self.buffer.drain(..end);
+
+        Ok(Some(message?))
+    }
+
+    /// Serialize a message and write it as one frame to `writer`.
+    ///
+    /// # Errors
+    /// Same as [`encode()`](Self::encode), plus [`FrameError::Io`] if writing fails.
+    #[cfg(feature = "std")]
+    pub fn write_frame<W: Write>(
+        &self,
+        writer: &mut W,
+        message: &Message,
+    ) -> Result<(), FrameError> {
+        let mut frame = Vec::new();
+        self.encode(message, &mut frame)?;
+        writer.write_all(&frame)?;
+
+        Ok(())
+    }
+
+    /// Read from `reader` until a complete message has been received.
+    ///
+    /// Bytes that were read past the end of the frame are kept for the next call.
+    ///
+    /// # Errors
+    /// Same as [`decode()`](Self::decode), plus [`FrameError::Io`] if reading fails and
+    /// [`FrameError::UnexpectedEof`] if the stream ends before a complete frame is received.
+    #[cfg(feature = "std")]
+    pub fn read_frame<R: Read>(&mut self, reader: &mut R) -> Result<Message, FrameError> {
+        let mut chunk = [0; 512];
+
+        loop {
+            if let Some(message) = self.decode()? {
+                return Ok(message);
+            }
+
+            match reader.read(&mut chunk) {
+                Ok(0) => return Err(FrameError::UnexpectedEof),
+                Ok(n) => self.push(&chunk[..n]),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => (),
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
+    /// Returns the payload size announced by the buffered header, if it has been fully received.
+    fn peek_size(&self) -> Option<usize> {
+        let header = self.buffer.first_chunk::<HEADER_SIZE>()?;
+        usize::try_from(u32::from_be_bytes(*header)).ok()
+    }
+
+    /// Check whether the given payload size is allowed.
+    const fn check_size(&self, size: usize) -> Result<(), FrameError> {
+        if size > self.max_frame_size {
+            return Err(FrameError::TooLarge {
+                size,
+                max: self.max_frame_size,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for FrameCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PartialEq for FrameError {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                Self::TooLarge { size, max },
+                Self::TooLarge {
+                    size: other_size,
+                    max: other_max,
+                },
+            ) => size == other_size && max == other_max,
+            (Self::Encode(err), Self::Encode(other_err)) => err == other_err,
+            (Self::Decode(err), Self::Decode(other_err)) => err == other_err,
+            (Self::UnexpectedEof, Self::UnexpectedEof) => true,
+            #[cfg(feature = "std")]
+            (Self::Io(err), Self::Io(other_err)) => err.kind() == other_err.kind(),
+            _ => false,
+        }
+    }
+}
+
+impl Display for FrameError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::TooLarge { size, max } => {
+                write!(
+                    f,
+                    "frame of {size} bytes exceeds the maximum of {max} bytes"
+                )
+            }
+            Self::Encode(err) => write!(f, "message could not be serialized: {err}"),
+            Self::Decode(err) => write!(f, "frame does not contain a valid message: {err}"),
+            Self::UnexpectedEof => write!(f, "stream ended before a complete frame was received"),
+            #[cfg(feature = "std")]
+            Self::Io(err) => write!(f, "I/O error: {err}"),
+        }
+    }
+}
+
+impl core::error::Error for FrameError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::Encode(err) => Some(err),
+            Self::Decode(err) => Some(err),
+            #[cfg(feature = "std")]
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<EncodeError> for FrameError {
+    fn from(value: EncodeError) -> Self {
+        Self::Encode(value)
+    }
+}
+
+impl From<DecodeError> for FrameError {
+    fn from(value: DecodeError) -> Self {
+        Self::Decode(value)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for FrameError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
@@ -0,0 +1,186 @@
+//! Generation of message IDs and detection of duplicated messages.
+//!
+//! Like the [`tracker`](crate::tracker), the [`DedupCache`] is sans-IO and takes the current
+//! time as a [`Duration`] measured from an arbitrary, but fixed point in time.
+
+use crate::MsgId;
+#[cfg(feature = "alloc")]
+use alloc::collections::VecDeque;
+#[cfg(feature = "alloc")]
+use core::time::Duration;
+
+/// Generates message IDs for a single connection.
+///
+/// IDs are handed out sequentially and wrap around to `0` after [`MsgId::MAX`].
+///
+/// ```rust
+/// use pwmp_msg::id::IdGenerator;
+///
+/// let mut ids = IdGenerator::starting_at(u32::MAX - 1);
+///
+/// assert_eq!(ids.next_id(), u32::MAX - 1);
+/// assert_eq!(ids.next_id(), u32::MAX);
+/// assert_eq!(ids.next_id(), 0);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct IdGenerator {
+    /// The ID that will be returned next.
+    next: MsgId,
+}
+
+/// A bounded cache of recently seen message IDs, used to detect duplicated or replayed messages.
+///
+/// An ID is remembered for the configured time window, or until it's pushed out by newer IDs
+/// when the cache is full. This means that IDs reused after a wraparound of the sender's
+/// [`IdGenerator`] are not reported as duplicates, as long as the window is reasonably short.
+///
+/// ```rust
+/// use pwmp_msg::id::DedupCache;
+/// use std::time::Duration;
+///
+/// let mut cache = DedupCache::new(64, Duration::from_secs(30));
+///
+/// assert!(cache.insert(1, Duration::from_secs(0)));
+/// assert!(!cache.insert(1, Duration::from_secs(10)));
+///
+/// // Forgotten after the window has passed.
+/// assert!(cache.insert(1, Duration::from_secs(31)));
+/// ```
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone)]
+pub struct DedupCache {
+    /// Maximum number of remembered IDs.
+    capacity: usize,
+
+    /// How long an ID is remembered.
+    window: Duration,
+
+    /// Remembered IDs with the time they were seen, ordered from the oldest one.
+    entries: VecDeque<(MsgId, Duration)>,
+}
+
+impl IdGenerator {
+    /// Create a new generator that starts at `0`.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self::starting_at(0)
+    }
+
+    /// Create a new generator that starts at the given ID.
+    ///
+    /// Starting at a random ID makes it less likely that IDs from a previous connection
+    /// are mistaken for new ones.
+    #[must_use]
+    pub const fn starting_at(first: MsgId) -> Self {
+        Self { next: first }
+    }
+
+    /// Create a new generator that starts at a random ID.
+    ///
+    /// The randomness comes from the standard library's hash map seeds, which is fine
+    /// for avoiding collisions, but not suitable for anything security related.
+    #[cfg(feature = "std")]
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn random() -> Self {
+        use std::hash::{BuildHasher, RandomState};
+
+        Self::starting_at(RandomState::new().hash_one(0u8) as MsgId)
+    }
+
+    /// Returns the next ID and advances the generator.
+    pub const fn next_id(&mut self) -> MsgId {
+        let id = self.next;
+        self.next = self.next.wrapping_add(1);
+
+        id
+    }
+
+    /// Returns the ID that will be returned by the next call to [`next_id()`](Self::next_id).
+    #[must_use]
+    pub const fn peek(&self) -> MsgId {
+        self.next
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl DedupCache {
+    /// Create a new cache that remembers up to `capacity` IDs for the duration of `window`.
+    #[must_use]
+    pub fn new(capacity: usize, window: Duration) -> Self {
+        Self {
+            capacity,
+            window,
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Returns the maximum number of remembered IDs.
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns how long an ID is remembered.
+    #[must_use]
+    pub const fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Returns the number of currently remembered IDs.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether no IDs are remembered.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Record an ID seen at `now`.
+    ///
+    /// Returns `true` if the ID is new, or `false` if it's a duplicate of a recently seen message.
+    /// Duplicates don't extend the time for which the ID is remembered.
+    pub fn insert(&mut self, id: MsgId, now: Duration) -> bool {
+        self.evict_expired(now);
+
+        if self.contains(id) {
+            return false;
+        }
+
+        if self.capacity == 0 {
+            return true;
+        }
+
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+
+        self.entries.push_back((id, now));
+        true
+    }
+
+    /// Returns whether the ID is currently remembered.
+    #[must_use]
+    pub fn contains(&self, id: MsgId) -> bool {
+        self.entries.iter().any(|(seen, _)| *seen == id)
+    }
+
+    /// Forget all IDs that were seen longer than the window ago.
+    pub fn evict_expired(&mut self, now: Duration) {
+        while let Some((_, seen_at)) = self.entries.front() {
+            if now.saturating_sub(*seen_at) <= self.window {
+                break;
+            }
+
+            self.entries.pop_front();
+        }
+    }
+
+    /// Forget all IDs.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
@@ -1,16 +1,64 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 #![deny(missing_docs, clippy::missing_docs_in_private_items)]
 
 //! Pixel-Weather Messaging Protocol core library.
 //!
 //! This library contains the definitions of all possible messages in the PWMP.
+//!
+//! # Features
+//! - `std` *(default)*: Enables I/O helpers based on `std::io` and the `units` module, which
+//!   needs the floating-point functions of `std`. Implies `alloc`.
+//! - `alloc`: Enables the message types, which need a global allocator.
+//!
+//! Without any features, only the fixed-size types ([`mac::Mac`], [`version::Version`],
+//! [`settings::NodeSettings`], ...), the chunk decompressor, the [`battery`] estimation and
+//! the borrowed [`measurement::MeasurementsRef`] are available.
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
 
+#[cfg(feature = "alloc")]
+use alloc::{boxed::Box, vec::Vec};
+#[cfg(feature = "alloc")]
+use error::{DecodeError, EncodeError};
+#[cfg(feature = "alloc")]
 use serde::{Deserialize, Serialize};
 
 pub mod aliases;
+#[cfg(feature = "alloc")]
+pub mod assembler;
+pub mod battery;
+#[cfg(feature = "alloc")]
+mod codec;
+pub mod compression;
+#[cfg(feature = "alloc")]
+pub mod delta;
+pub mod diagnostics;
+pub mod error;
+#[cfg(feature = "alloc")]
+pub mod firmware_server;
+#[cfg(feature = "alloc")]
+pub mod framing;
+pub mod id;
 pub mod mac;
+#[cfg(feature = "alloc")]
+pub mod manifest;
+pub mod measurement;
+pub mod ota;
+pub mod protocol;
+#[cfg(feature = "alloc")]
 pub mod request;
+#[cfg(feature = "alloc")]
 pub mod response;
+#[cfg(feature = "alloc")]
+pub mod rollout;
+#[cfg(feature = "alloc")]
+pub mod session;
 pub mod settings;
+#[cfg(feature = "alloc")]
+pub mod tracker;
+#[cfg(feature = "std")]
+pub mod units;
 pub mod version;
 
 /// Message ID type.
@@ -18,6 +66,7 @@ pub type MsgId = u32;
 
 /// A Message object.
 /// Can either be a request or a response.
+#[cfg(feature = "alloc")]
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Message {
     /// Unique ID of this message.
@@ -28,23 +77,60 @@ pub struct Message {
     /// - If the client requests too many OTA update chunks, this might be problematic.
     ///
     /// The server and client should keep a short-term cache of the sent/received IDs
-    /// to determinte if the same message hasn't been duplicated.
+    /// to determinte if the same message hasn't been duplicated. The [`id`] module provides
+    /// an [`IdGenerator`](id::IdGenerator) that handles wraparound, and a [`DedupCache`](id::DedupCache)
+    /// for detecting duplicates.
+    ///
+    /// Responses may also carry the ID of the request they answer, see [`reply_to()`](Self::reply_to).
     id: MsgId,
 
     /// Actual content of the message, which can be either a request or a response.
     content: MessageContent,
 }
 
+/// Borrowed counterpart of [`Message`].
+///
+/// Strings and firmware update chunks reference the buffer the message was decoded from,
+/// so decoding does not allocate *(except for firmware manifests)*. Both types have the same wire format.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MessageRef<'a> {
+    /// Unique ID of this message. See [`Message`] for details.
+    id: MsgId,
+
+    /// Actual content of the message, which can be either a request or a response.
+    #[serde(borrow)]
+    content: MessageContentRef<'a>,
+}
+
 /// A wrapper for the contents of the message.
+#[cfg(feature = "alloc")]
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 enum MessageContent {
     /// Client (node) has requested the server to perform an operation.
     Request(request::Request),
 
     /// Server has responded to a client's request.
-    Response(response::Response),
+    ///
+    /// The ID of the answered request, if known, is stored after the response, so that
+    /// peers of older protocol revisions can still decode the response itself. Responses of
+    /// such peers end before it, and are decoded with no ID.
+    Response(response::Response, Option<MsgId>),
 }
 
+/// Borrowed counterpart of [`MessageContent`].
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+enum MessageContentRef<'a> {
+    /// Client (node) has requested the server to perform an operation.
+    #[serde(borrow)]
+    Request(request::RequestRef<'a>),
+
+    /// Server has responded to a client's request.
+    Response(#[serde(borrow)] response::ResponseRef<'a>, Option<MsgId>),
+}
+
+#[cfg(feature = "alloc")]
 impl Message {
     /// Wrap a request and assign the given ID to the message.
     ///
@@ -84,7 +170,27 @@ impl Message {
     pub const fn new_response(res: response::Response, id: MsgId) -> Self {
         Self {
             id,
-            content: MessageContent::Response(res),
+            content: MessageContent::Response(res, None),
+        }
+    }
+
+    /// Wrap a response to the request with ID `request_id`, and assign the given ID to the message.
+    ///
+    /// # Example
+    /// ```rust
+    /// use pwmp_msg::{Message, request::Request, response::Response};
+    ///
+    /// let request = Message::new_request(Request::Ping, 7);
+    /// let reply = Message::new_reply(Response::Pong, 1, request.id());
+    ///
+    /// assert_eq!(reply.id(), 1);
+    /// assert_eq!(reply.reply_to(), Some(7));
+    /// ```
+    #[must_use]
+    pub const fn new_reply(res: response::Response, id: MsgId, request_id: MsgId) -> Self {
+        Self {
+            id,
+            content: MessageContent::Response(res, Some(request_id)),
         }
     }
 
@@ -113,7 +219,76 @@ impl Message {
     /// This will panic if the message could not be serialized.
     #[must_use]
     pub fn serialize(self) -> Box<[u8]> {
-        postcard::to_stdvec(&self).unwrap().into_boxed_slice()
+        postcard::to_allocvec(&self).unwrap().into_boxed_slice()
+    }
+
+    /// Serialize the message into the given buffer.
+    ///
+    /// Returns the part of the buffer that contains the serialized message.
+    ///
+    /// # Example
+    /// ```rust
+    /// use pwmp_msg::{Message, request::Request};
+    ///
+    /// let message = Message::new_request(Request::Ping, 1);
+    /// let mut buffer = [0; 64];
+    ///
+    /// let bytes = message.serialize_into(&mut buffer).unwrap();
+    /// assert_eq!(Message::deserialize(bytes), Ok(message));
+    /// ```
+    ///
+    /// # Errors
+    /// Returns [`EncodeError::BufferFull`] if the buffer is too small for the message.
+    pub fn serialize_into<'a>(&self, buf: &'a mut [u8]) -> Result<&'a mut [u8], EncodeError> {
+        codec::to_slice(self, buf)
+    }
+
+    /// Serialize the message and append it to the given vector.
+    ///
+    /// Returns the number of appended bytes.
+    ///
+    /// # Example
+    /// ```rust
+    /// use pwmp_msg::{Message, response::Response};
+    ///
+    /// let message = Message::new_response(Response::Pong, 1);
+    /// let mut buffer = vec![0xAA, 0xBB];
+    ///
+    /// let written = message.serialize_append(&mut buffer).unwrap();
+    /// assert_eq!(buffer.len(), 2 + written);
+    /// assert_eq!(Message::deserialize(&buffer[2..]), Ok(message));
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an [`EncodeError`] if the message could not be serialized.
+    /// The vector is left unchanged in this case.
+    pub fn serialize_append(&self, vec: &mut Vec<u8>) -> Result<usize, EncodeError> {
+        codec::append(self, vec)
+    }
+
+    /// Serialize the message into the given writer.
+    ///
+    /// Returns the number of written bytes. Bytes are passed to the writer as they are produced,
+    /// so unbuffered writers (such as sockets) should be wrapped in a [`BufWriter`](std::io::BufWriter).
+    /// The writer is flushed afterwards.
+    ///
+    /// # Example
+    /// ```rust
+    /// use pwmp_msg::{Message, request::Request};
+    ///
+    /// let message = Message::new_request(Request::GetSettings, 1);
+    /// let mut output = Vec::new();
+    ///
+    /// let written = message.serialize_to_writer(&mut output).unwrap();
+    /// assert_eq!(written, output.len());
+    /// assert_eq!(Message::deserialize(&output), Ok(message));
+    /// ```
+    ///
+    /// # Errors
+    /// Returns [`EncodeError::Io`] if writing to the writer fails.
+    #[cfg(feature = "std")]
+    pub fn serialize_to_writer<W: std::io::Write>(&self, writer: W) -> Result<usize, EncodeError> {
+        codec::to_writer(self, writer)
     }
 
     /// Deserialize a message from raw bytes.
@@ -144,9 +319,51 @@ impl Message {
     ///
     /// assert_eq!(message, original_message);
     /// ```
+    ///
+    /// # Errors
+    /// Returns a [`DecodeError`] describing what went wrong and where, if the bytes do not contain a valid message.
+    /// Values that are out of range for their type, such as a humidity of 250 %, are
+    /// [`InvalidValue`](DecodeError::InvalidValue) errors. Servers answer requests that can't be
+    /// decoded with [`Response::InvalidRequest`], replying to the ID from [`peek_id()`](Self::peek_id).
+    ///
+    /// ```rust
+    /// use pwmp_msg::{error::DecodeError, Message};
+    ///
+    /// // Message ID 1, `Request` variant, unknown request tag.
+    /// let bytes = [1, 0, 120];
+    ///
+    /// assert_eq!(
+    ///     Message::deserialize(&bytes),
+    ///     Err(DecodeError::InvalidValue { offset: 2 })
+    /// );
+    /// ```
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, DecodeError> {
+        codec::message_from_bytes(bytes, |message: &Self| {
+            message.response().is_some() && message.reply_to().is_none()
+        })
+    }
+
+    /// Read only the ID of a serialized message, even if the rest of it can't be decoded.
+    /// Returns `None` if the bytes don't start with a valid ID.
+    ///
+    /// ```rust
+    /// use pwmp_msg::{error::DecodeError, response::Response, Message};
+    ///
+    /// // Message ID 7, `Request` variant, unknown request tag.
+    /// let bytes = [7, 0, 120];
+    /// assert_eq!(
+    ///     Message::deserialize(&bytes),
+    ///     Err(DecodeError::InvalidValue { offset: 2 })
+    /// );
+    ///
+    /// let id = Message::peek_id(&bytes).unwrap();
+    /// let reply = Message::new_reply(Response::InvalidRequest, 1, id);
+    ///
+    /// assert_eq!(reply.reply_to(), Some(7));
+    /// ```
     #[must_use]
-    pub fn deserialize(bytes: &[u8]) -> Option<Self> {
-        postcard::from_bytes(bytes).ok()
+    pub fn peek_id(bytes: &[u8]) -> Option<MsgId> {
+        postcard::take_from_bytes(bytes).ok().map(|(id, _)| id)
     }
 
     /// Returns a reference to the contained [`Request`].
@@ -206,7 +423,7 @@ impl Message {
     /// ```
     #[must_use]
     pub const fn response(&self) -> Option<&response::Response> {
-        if let MessageContent::Response(res) = &self.content {
+        if let MessageContent::Response(res, _) = &self.content {
             Some(res)
         } else {
             None
@@ -268,7 +485,7 @@ impl Message {
     /// ```
     #[must_use]
     pub fn take_response(self) -> Option<response::Response> {
-        if let MessageContent::Response(res) = self.content {
+        if let MessageContent::Response(res, _) = self.content {
             Some(res)
         } else {
             None
@@ -300,4 +517,152 @@ impl Message {
     pub const fn id(&self) -> MsgId {
         self.id
     }
+
+    /// Returns the ID of the request this message answers.
+    ///
+    /// This is `None` for requests, and for responses created with [`new_response()`](Self::new_response).
+    ///
+    /// ```rust
+    /// use pwmp_msg::{Message, request::Request, response::Response};
+    ///
+    /// assert_eq!(Message::new_request(Request::Ping, 1).reply_to(), None);
+    /// assert_eq!(Message::new_response(Response::Pong, 2).reply_to(), None);
+    /// assert_eq!(Message::new_reply(Response::Pong, 2, 1).reply_to(), Some(1));
+    /// ```
+    #[must_use]
+    pub const fn reply_to(&self) -> Option<MsgId> {
+        if let MessageContent::Response(_, reply_to) = &self.content {
+            *reply_to
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a> MessageRef<'a> {
+    /// Deserialize a message from raw bytes without copying strings and update chunks.
+    ///
+    /// # Example
+    /// ```rust
+    /// use pwmp_msg::{Message, MessageRef, response::{Response, ResponseRef}};
+    ///
+    /// let chunk = vec![0xAB; 1024].into_boxed_slice();
+    /// let response = Response::update_part(0, 1024, chunk);
+    /// let bytes = Message::new_response(response, 1).serialize();
+    ///
+    /// let message = MessageRef::deserialize(&bytes).unwrap();
+    /// let Some(ResponseRef::UpdatePart { data, .. }) = message.response() else {
+    ///     unreachable!();
+    /// };
+    ///
+    /// assert_eq!(data.len(), 1024);
+    /// assert!(bytes.as_ptr_range().contains(&data.as_ptr()));
+    /// ```
+    ///
+    /// # Errors
+    /// Returns a [`DecodeError`] describing what went wrong and where, if the bytes do not contain a valid message.
+    pub fn deserialize(bytes: &'a [u8]) -> Result<Self, DecodeError> {
+        codec::message_from_bytes(bytes, |message: &Self| {
+            message.response().is_some() && message.reply_to().is_none()
+        })
+    }
+
+    /// Serialize the message into the given buffer.
+    ///
+    /// Returns the part of the buffer that contains the serialized message.
+    /// See [`Message::serialize_into()`].
+    ///
+    /// # Errors
+    /// Returns [`EncodeError::BufferFull`] if the buffer is too small for the message.
+    pub fn serialize_into<'b>(&self, buf: &'b mut [u8]) -> Result<&'b mut [u8], EncodeError> {
+        codec::to_slice(self, buf)
+    }
+
+    /// Returns a reference to the contained [`RequestRef`](request::RequestRef).
+    /// If the message contains a response instead, `None` is returned.
+    #[must_use]
+    pub const fn request(&self) -> Option<&request::RequestRef<'a>> {
+        if let MessageContentRef::Request(req) = &self.content {
+            Some(req)
+        } else {
+            None
+        }
+    }
+
+    /// Returns a reference to the contained [`ResponseRef`](response::ResponseRef).
+    /// If the message contains a request instead, `None` is returned.
+    #[must_use]
+    pub const fn response(&self) -> Option<&response::ResponseRef<'a>> {
+        if let MessageContentRef::Response(res, _) = &self.content {
+            Some(res)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the message ID.
+    #[must_use]
+    pub const fn id(&self) -> MsgId {
+        self.id
+    }
+
+    /// Returns the ID of the request this message answers. See [`Message::reply_to()`].
+    #[must_use]
+    pub const fn reply_to(&self) -> Option<MsgId> {
+        if let MessageContentRef::Response(_, reply_to) = &self.content {
+            *reply_to
+        } else {
+            None
+        }
+    }
+
+    /// Copy the borrowed data and convert this into an owned [`Message`].
+    ///
+    /// ```rust
+    /// use pwmp_msg::{Message, MessageRef, request::Request};
+    ///
+    /// let message = Message::new_request(Request::SendNotification("Hello".into()), 1);
+    /// let bytes = message.clone().serialize();
+    ///
+    /// assert_eq!(MessageRef::deserialize(&bytes).unwrap().into_owned(), message);
+    /// ```
+    #[must_use]
+    pub fn into_owned(self) -> Message {
+        Message::from(self)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<MessageRef<'_>> for Message {
+    fn from(value: MessageRef<'_>) -> Self {
+        let content = match value.content {
+            MessageContentRef::Request(req) => MessageContent::Request(req.into()),
+            MessageContentRef::Response(res, reply_to) => {
+                MessageContent::Response(res.into(), reply_to)
+            }
+        };
+
+        Self {
+            id: value.id,
+            content,
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a> From<&'a Message> for MessageRef<'a> {
+    fn from(value: &'a Message) -> Self {
+        let content = match &value.content {
+            MessageContent::Request(req) => MessageContentRef::Request(req.into()),
+            MessageContent::Response(res, reply_to) => {
+                MessageContentRef::Response(res.into(), *reply_to)
+            }
+        };
+
+        Self {
+            id: value.id,
+            content,
+        }
+    }
 }
@@ -3,13 +3,13 @@
 //! Defines a very basic MAC Address data type used for storing and representing
 //! MAC adresses.
 
-use serde::{Deserialize, Serialize};
-use std::{
+use core::{
     fmt::Display,
     num::ParseIntError,
     ops::{Index, IndexMut},
     str::FromStr,
 };
+use serde::{Deserialize, Serialize};
 
 /// Maximum length of a MAC address when represented as a string of characters
 /// in the following format: `AA:BB:CC:DD:EE:FF`.
@@ -68,7 +68,7 @@ impl FromStr for Mac {
 }
 
 impl Display for Mac {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(
             f,
             "{:02X}:{:02X}:{:02X}:{:02X}:{:02X}:{:02X}",
@@ -107,6 +107,18 @@ impl IndexMut<usize> for Mac {
     }
 }
 
+impl From<Mac> for [u8; 6] {
+    fn from(value: Mac) -> Self {
+        [value.0, value.1, value.2, value.3, value.4, value.5]
+    }
+}
+
+impl From<[u8; 6]> for Mac {
+    fn from(value: [u8; 6]) -> Self {
+        Self::new(value[0], value[1], value[2], value[3], value[4], value[5])
+    }
+}
+
 impl From<ParseIntError> for MacParseError {
     fn from(_: ParseIntError) -> Self {
         Self
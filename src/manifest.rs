@@ -0,0 +1,283 @@
+//! Signed firmware manifests.
+//!
+//! A [`FirmwareManifest`] describes a firmware image. The release tooling signs it with an
+//! Ed25519 [`SigningKey`], and the server announces the resulting [`SignedManifest`] in
+//! [`UpdateAvailable`](crate::response::Response::UpdateAvailable). Nodes only know the
+//! matching [`VerifyingKey`], so a server can't push firmware that wasn't signed by the release
+//! tooling.
+//!
+//! A manifest can also announce a [delta patch](crate::delta) against the node's running
+//! firmware, described by a [`DeltaManifest`]. The patch is downloaded instead of the image.
+//!
+//! ```rust
+//! use pwmp_msg::{
+//!     manifest::{FirmwareManifest, SigningKey},
+//!     ota::sha256,
+//!     version::Version,
+//! };
+//!
+//! let image = b"firmware image";
+//!
+//! // Release tooling
+//! let key = SigningKey::from_bytes(&[7; 32]);
+//! let signed = FirmwareManifest {
+//!     version: Version::new(1, 1, 0),
+//!     size: image.len() as u32,
+//!     sha256: sha256(image),
+//!     min_version: Version::new(1, 0, 0),
+//!     target: "esp32s3".into(),
+//!     delta: None,
+//! }
+//! .sign(&key);
+//!
+//! // Node, before requesting any chunks
+//! let manifest = signed.verify(&key.verifying_key()).unwrap();
+//! manifest.check_compatibility(Version::new(1, 0, 3), "esp32s3").unwrap();
+//!
+//! // Node, before flashing the downloaded image
+//! assert!(manifest.matches_image(image));
+//! ```
+
+use crate::{
+    ota::{self, ImageVerifier, Sha256Digest},
+    version::Version,
+};
+use alloc::{boxed::Box, vec::Vec};
+use core::{
+    fmt::Display,
+    hash::{Hash, Hasher},
+};
+pub use ed25519_dalek::{Signature, SigningKey, VerifyingKey};
+use serde::{Deserialize, Serialize};
+
+/// Prefix of the signed data, which prevents signatures from being reused in other contexts.
+const SIGNING_CONTEXT: &[u8] = b"pwmp-firmware-manifest";
+
+/// Description of a firmware image.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FirmwareManifest {
+    /// Version of the firmware.
+    pub version: Version,
+
+    /// Size of the firmware image.
+    pub size: u32,
+
+    /// SHA-256 digest of the firmware image.
+    pub sha256: Sha256Digest,
+
+    /// Oldest firmware version that is allowed to update to this one.
+    pub min_version: Version,
+
+    /// Hardware target the firmware was built for.
+    pub target: Box<str>,
+
+    /// The delta patch that is downloaded instead of the image, if any.
+    pub delta: Option<DeltaManifest>,
+}
+
+/// Description of a delta patch, which turns a base firmware image into the announced one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DeltaManifest {
+    /// Version of the firmware the patch must be applied to.
+    pub base: Version,
+
+    /// SHA-256 digest of the base firmware image.
+    pub base_sha256: Sha256Digest,
+
+    /// Size of the patch.
+    pub size: u32,
+
+    /// SHA-256 digest of the patch.
+    pub sha256: Sha256Digest,
+}
+
+/// A [`FirmwareManifest`] together with its Ed25519 signature.
+///
+/// The manifest can only be accessed after its signature has been [verified](Self::verify),
+/// except through [`manifest_unverified()`](Self::manifest_unverified).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedManifest {
+    /// The signed manifest.
+    manifest: FirmwareManifest,
+
+    /// Signature of the manifest.
+    signature: Signature,
+}
+
+/// Reasons why a firmware manifest is not accepted.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ManifestError {
+    /// The signature doesn't match the manifest and the trusted key.
+    InvalidSignature,
+
+    /// The running firmware is too old to update to this firmware.
+    UnsupportedVersion {
+        /// Version of the running firmware.
+        running: Version,
+
+        /// Oldest firmware version that is allowed to update.
+        min_version: Version,
+    },
+
+    /// The firmware was built for a different hardware target.
+    WrongTarget {
+        /// Target the firmware was built for.
+        expected: Box<str>,
+    },
+
+    /// The delta patch must be applied to a different firmware than the running one.
+    WrongBase {
+        /// Version of the firmware the patch must be applied to.
+        expected: Version,
+    },
+}
+
+impl FirmwareManifest {
+    /// Sign the manifest.
+    #[must_use]
+    pub fn sign(self, key: &SigningKey) -> SignedManifest {
+        use ed25519_dalek::Signer;
+
+        let signature = key.sign(&self.signed_bytes());
+        SignedManifest {
+            manifest: self,
+            signature,
+        }
+    }
+
+    /// Check whether a node running `running` on the hardware `target` may install this firmware.
+    ///
+    /// # Errors
+    /// Returns [`ManifestError::UnsupportedVersion`] if the running firmware is older than
+    /// [`min_version`](Self::min_version), [`ManifestError::WrongTarget`] if the hardware
+    /// target doesn't match, and [`ManifestError::WrongBase`] if a delta patch is announced for
+    /// a different firmware than the running one.
+    pub fn check_compatibility(&self, running: Version, target: &str) -> Result<(), ManifestError> {
+        if running < self.min_version {
+            return Err(ManifestError::UnsupportedVersion {
+                running,
+                min_version: self.min_version,
+            });
+        }
+
+        if *self.target != *target {
+            return Err(ManifestError::WrongTarget {
+                expected: self.target.clone(),
+            });
+        }
+
+        if let Some(delta) = &self.delta {
+            if delta.base != running {
+                return Err(ManifestError::WrongBase {
+                    expected: delta.base,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the size of the download, which is the size of the delta patch if one is announced,
+    /// and the size of the image otherwise.
+    #[must_use]
+    pub const fn download_size(&self) -> u32 {
+        match &self.delta {
+            Some(delta) => delta.size,
+            None => self.size,
+        }
+    }
+
+    /// Create a verifier that checks the downloaded chunks against this manifest.
+    ///
+    /// If a delta patch is announced, the chunks are checked against the patch.
+    #[must_use]
+    pub fn verifier(&self) -> ImageVerifier {
+        match &self.delta {
+            Some(delta) => ImageVerifier::new(delta.size, delta.sha256),
+            None => ImageVerifier::new(self.size, self.sha256),
+        }
+    }
+
+    /// Returns whether a complete image matches the size and digest of this manifest.
+    ///
+    /// This always checks the firmware image, even if a delta patch is announced.
+    #[must_use]
+    pub fn matches_image(&self, image: &[u8]) -> bool {
+        u32::try_from(image.len()) == Ok(self.size) && ota::sha256(image) == self.sha256
+    }
+
+    /// Returns the data covered by the signature.
+    fn signed_bytes(&self) -> Vec<u8> {
+        postcard::to_extend(self, Vec::from(SIGNING_CONTEXT))
+            .expect("serializing a manifest cannot fail")
+    }
+}
+
+impl SignedManifest {
+    /// Combine a manifest with a signature that was created separately.
+    ///
+    /// This is useful for servers that store the signature next to the image.
+    #[must_use]
+    pub const fn from_parts(manifest: FirmwareManifest, signature: Signature) -> Self {
+        Self {
+            manifest,
+            signature,
+        }
+    }
+
+    /// Check the signature against the trusted key, and return the manifest if it's valid.
+    ///
+    /// # Errors
+    /// Returns [`ManifestError::InvalidSignature`] if the manifest wasn't signed by the key,
+    /// or has been modified since.
+    pub fn verify(&self, key: &VerifyingKey) -> Result<&FirmwareManifest, ManifestError> {
+        key.verify_strict(&self.manifest.signed_bytes(), &self.signature)
+            .map_err(|_| ManifestError::InvalidSignature)?;
+
+        Ok(&self.manifest)
+    }
+
+    /// Returns the manifest without checking its signature.
+    ///
+    /// The contents must not be trusted. This is meant for servers and logging.
+    #[must_use]
+    pub const fn manifest_unverified(&self) -> &FirmwareManifest {
+        &self.manifest
+    }
+
+    /// Returns the signature of the manifest.
+    #[must_use]
+    pub const fn signature(&self) -> &Signature {
+        &self.signature
+    }
+}
+
+impl Hash for SignedManifest {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.manifest.hash(state);
+        self.signature.to_bytes().hash(state);
+    }
+}
+
+impl Display for ManifestError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::InvalidSignature => write!(f, "invalid manifest signature"),
+            Self::UnsupportedVersion {
+                running,
+                min_version,
+            } => write!(
+                f,
+                "running firmware {running} is older than the minimum version {min_version}"
+            ),
+            Self::WrongTarget { expected } => {
+                write!(f, "firmware was built for a different target ({expected})")
+            }
+            Self::WrongBase { expected } => {
+                write!(f, "delta patch must be applied to firmware {expected}")
+            }
+        }
+    }
+}
+
+impl core::error::Error for ManifestError {}
@@ -0,0 +1,815 @@
+//! Measurements of nodes with any number of sensors.
+//!
+//! A [`PostMeasurements`](crate::request::Request::PostMeasurements) request carries a list of
+//! [`Reading`]s. Each reading names the [`SensorKind`] it comes from, and optionally the index
+//! of the sensor, so a node can report several sensors of the same kind. The fixed
+//! [`PostResults`](crate::request::Request::PostResults) request can be converted into
+//! readings with [`Request::measurements()`](crate::request::Request::measurements).
+//!
+//! # Wire format
+//! Readings are packed into a byte string, which can be read without allocating
+//! *(see [`MeasurementsRef`])*. Each reading consists of:
+//! - A byte with the [code](SensorKind::code) of the sensor kind in the lower 7 bits.
+//!   The highest bit is set if the sensor index follows.
+//! - The sensor index, if present.
+//! - The value, as a little-endian 32-bit float.
+//!
+//! Codes that are not known to this version of the library are decoded as
+//! [`SensorKind::Other`], so new kinds of sensors don't require a new protocol revision.
+//!
+//! # Batches
+//! Nodes that can't reach the server buffer their measurements, and upload them later with
+//! [`PostMeasurementBatch`](crate::request::Request::PostMeasurementBatch). A
+//! [`MeasurementBatch`] contains [`Sample`]s, which are measurements with the
+//! [time](SampleTime) they were taken at. Each sample is packed as:
+//! - A byte that is `0` for a [timestamp](SampleTime::Timestamp) and `1` for an
+//!   [age](SampleTime::Age), followed by the value as an LEB128-encoded unsigned integer.
+//! - The size of the packed readings as an LEB128-encoded unsigned integer, followed by
+//!   the readings.
+//!
+//! ```rust
+//! use pwmp_msg::measurement::{Measurements, Reading, SensorKind};
+//!
+//! let measurements: Measurements = [
+//!     Reading::new(SensorKind::Temperature, 21.5),
+//!     Reading::new(SensorKind::Temperature, 4.25).with_index(1),
+//!     Reading::new(SensorKind::Co2, 612.0),
+//! ]
+//! .into_iter()
+//! .collect();
+//!
+//! assert_eq!(measurements.len(), 3);
+//! assert_eq!(
+//!     measurements.first(SensorKind::Co2),
+//!     Some(Reading::new(SensorKind::Co2, 612.0))
+//! );
+//! ```
+
+use crate::aliases::ValueError;
+#[cfg(feature = "alloc")]
+use crate::codec;
+#[cfg(feature = "alloc")]
+use alloc::{boxed::Box, vec::Vec};
+use core::{fmt::Display, ops::RangeInclusive};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Bits of the kind byte that contain the code of the sensor kind.
+const KIND_MASK: u8 = 0x7F;
+
+/// Bit of the kind byte that is set if the sensor index follows.
+const INDEX_FLAG: u8 = 0x80;
+
+/// Tag of a [`SampleTime::Timestamp`].
+const TIME_TIMESTAMP: u8 = 0;
+
+/// Tag of a [`SampleTime::Age`].
+const TIME_AGE: u8 = 1;
+
+/// Kind of sensor a [`Reading`] comes from.
+///
+/// The documentation of each kind names the unit of its values.
+/// New kinds are only ever added with a new code, so the codes of existing kinds never change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SensorKind {
+    /// Air temperature in *°C*.
+    Temperature,
+
+    /// Relative humidity in *%*.
+    Humidity,
+
+    /// Air pressure in *hPa*.
+    AirPressure,
+
+    /// Carbon dioxide concentration in *ppm*.
+    Co2,
+
+    /// Concentration of particulate matter up to 2.5 µm in *µg/m³*.
+    Pm2_5,
+
+    /// Concentration of particulate matter up to 10 µm in *µg/m³*.
+    Pm10,
+
+    /// Total volatile organic compounds in *ppb*.
+    Voc,
+
+    /// Illuminance in *lx*.
+    Illuminance,
+
+    /// UV index.
+    UvIndex,
+
+    /// Wind speed in *m/s*.
+    WindSpeed,
+
+    /// Wind direction in degrees, clockwise from north.
+    WindDirection,
+
+    /// Rainfall since the previous measurement in *mm*.
+    Rainfall,
+
+    /// A kind of sensor that is not known to this version of the library.
+    ///
+    /// This can only be created with [`from_code`](Self::from_code), so it never has the code
+    /// of a known kind, or a code that doesn't fit into 7 bits.
+    Other(UnknownKind),
+}
+
+/// Code of a [`SensorKind::Other`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UnknownKind(u8);
+
+/// A single value measured by a sensor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Reading {
+    /// Kind of the sensor.
+    pub kind: SensorKind,
+
+    /// Index of the sensor among the node's sensors of the same kind, if it has several.
+    pub index: Option<u8>,
+
+    /// The measured value, in the unit of the [sensor kind](SensorKind).
+    pub value: f32,
+}
+
+/// Readings of a node, borrowed from the buffer they were decoded from.
+///
+/// See the [module documentation](self) for the wire format.
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub struct MeasurementsRef<'a>(&'a [u8]);
+
+/// Readings of a node.
+///
+/// This has the same wire format as [`MeasurementsRef`].
+#[cfg(feature = "alloc")]
+#[derive(Clone, PartialEq, Eq, Default)]
+pub struct Measurements(Box<[u8]>);
+
+/// Iterator over the readings of [`MeasurementsRef`] or [`Measurements`].
+#[derive(Debug, Clone)]
+pub struct Readings<'a>(&'a [u8]);
+
+/// Time at which a [`Sample`] was measured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SampleTime {
+    /// Unix time in seconds, for nodes with a synchronized clock.
+    Timestamp(u64),
+
+    /// Seconds before the request was sent, for nodes without a synchronized clock.
+    Age(u32),
+}
+
+/// Measurements taken at the same time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sample<'a> {
+    /// When the measurements were taken.
+    pub time: SampleTime,
+
+    /// The readings of the sample.
+    pub measurements: MeasurementsRef<'a>,
+}
+
+/// Buffered samples of a node, borrowed from the buffer they were decoded from.
+///
+/// See the [module documentation](self#batches) for the wire format.
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+pub struct MeasurementBatchRef<'a>(&'a [u8]);
+
+/// Buffered samples of a node.
+///
+/// This has the same wire format as [`MeasurementBatchRef`].
+///
+/// ```rust
+/// use pwmp_msg::measurement::{
+///     MeasurementBatch, Measurements, Reading, Sample, SampleTime, SensorKind,
+/// };
+///
+/// let older: Measurements = [Reading::new(SensorKind::Temperature, 18.25)].into_iter().collect();
+/// let newer: Measurements = [Reading::new(SensorKind::Temperature, 19.0)].into_iter().collect();
+///
+/// let batch: MeasurementBatch = [
+///     Sample::new(SampleTime::Age(1800), &older),
+///     Sample::new(SampleTime::Age(900), &newer),
+/// ]
+/// .into_iter()
+/// .collect();
+///
+/// let received_at = 1_700_000_000;
+/// let times: Vec<_> = batch
+///     .iter()
+///     .map(|sample| sample.time.to_timestamp(received_at))
+///     .collect();
+/// assert_eq!(times, [1_699_998_200, 1_699_999_100]);
+/// ```
+#[cfg(feature = "alloc")]
+#[derive(Clone, PartialEq, Eq, Default)]
+pub struct MeasurementBatch(Box<[u8]>);
+
+/// Iterator over the samples of [`MeasurementBatchRef`] or [`MeasurementBatch`].
+#[derive(Debug, Clone)]
+pub struct Samples<'a>(&'a [u8]);
+
+impl SensorKind {
+    /// Returns the kind with the given code, or [`None`] if the code doesn't fit into 7 bits.
+    ///
+    /// ```rust
+    /// use pwmp_msg::measurement::SensorKind;
+    ///
+    /// assert_eq!(SensorKind::from_code(3), Some(SensorKind::Co2));
+    /// assert_eq!(SensorKind::from_code(100).unwrap().code(), 100);
+    /// assert_eq!(SensorKind::from_code(200), None);
+    /// ```
+    #[must_use]
+    pub const fn from_code(code: u8) -> Option<Self> {
+        Some(match code {
+            0 => Self::Temperature,
+            1 => Self::Humidity,
+            2 => Self::AirPressure,
+            3 => Self::Co2,
+            4 => Self::Pm2_5,
+            5 => Self::Pm10,
+            6 => Self::Voc,
+            7 => Self::Illuminance,
+            8 => Self::UvIndex,
+            9 => Self::WindSpeed,
+            10 => Self::WindDirection,
+            11 => Self::Rainfall,
+            code if code <= KIND_MASK => Self::Other(UnknownKind(code)),
+            _ => return None,
+        })
+    }
+
+    /// Returns the code of the kind, which is used on the wire.
+    #[must_use]
+    pub const fn code(self) -> u8 {
+        match self {
+            Self::Temperature => 0,
+            Self::Humidity => 1,
+            Self::AirPressure => 2,
+            Self::Co2 => 3,
+            Self::Pm2_5 => 4,
+            Self::Pm10 => 5,
+            Self::Voc => 6,
+            Self::Illuminance => 7,
+            Self::UvIndex => 8,
+            Self::WindSpeed => 9,
+            Self::WindDirection => 10,
+            Self::Rainfall => 11,
+            Self::Other(kind) => kind.code(),
+        }
+    }
+
+    /// Returns the unit of the values of this kind, or an empty string if they have none.
+    #[must_use]
+    pub const fn unit(self) -> &'static str {
+        match self {
+            Self::Temperature => "°C",
+            Self::Humidity => "%",
+            Self::AirPressure => "hPa",
+            Self::Co2 => "ppm",
+            Self::Pm2_5 | Self::Pm10 => "µg/m³",
+            Self::Voc => "ppb",
+            Self::Illuminance => "lx",
+            Self::WindSpeed => "m/s",
+            Self::WindDirection => "°",
+            Self::Rainfall => "mm",
+            Self::UvIndex | Self::Other(..) => "",
+        }
+    }
+
+    /// Returns the range of physically plausible values of this kind, or [`None`] if any finite
+    /// value is accepted.
+    ///
+    /// The ranges of temperature, humidity and air pressure match the types of
+    /// [`PostResults`](crate::request::Request::PostResults).
+    ///
+    /// ```rust
+    /// use pwmp_msg::measurement::SensorKind;
+    ///
+    /// assert_eq!(SensorKind::Humidity.range(), Some(0.0..=100.0));
+    /// assert_eq!(SensorKind::from_code(42).unwrap().range(), None);
+    /// ```
+    #[must_use]
+    pub const fn range(self) -> Option<RangeInclusive<f32>> {
+        Some(match self {
+            Self::Temperature => -327.68..=327.67,
+            Self::Humidity => 0.0..=100.0,
+            Self::AirPressure => 300.0..=1100.0,
+            Self::WindDirection => 0.0..=360.0,
+            Self::Co2
+            | Self::Pm2_5
+            | Self::Pm10
+            | Self::Voc
+            | Self::Illuminance
+            | Self::UvIndex
+            | Self::WindSpeed
+            | Self::Rainfall => 0.0..=f32::MAX,
+            Self::Other(..) => return None,
+        })
+    }
+}
+
+impl UnknownKind {
+    /// Returns the code of the kind.
+    #[must_use]
+    pub const fn code(self) -> u8 {
+        self.0
+    }
+}
+
+impl Reading {
+    /// Create a reading of the node's only sensor of the given kind.
+    #[must_use]
+    pub const fn new(kind: SensorKind, value: f32) -> Self {
+        Self {
+            kind,
+            index: None,
+            value,
+        }
+    }
+
+    /// Set the index of the sensor.
+    #[must_use]
+    pub const fn with_index(mut self, index: u8) -> Self {
+        self.index = Some(index);
+        self
+    }
+
+    /// Check that the value is finite and within the [range](SensorKind::range) of its kind.
+    ///
+    /// # Errors
+    /// Returns [`ValueError::NotFinite`] or [`ValueError::OutOfRange`] for invalid values.
+    pub fn validate(&self) -> Result<(), ValueError> {
+        if !self.value.is_finite() {
+            return Err(ValueError::NotFinite);
+        }
+
+        match self.kind.range() {
+            Some(range) if !range.contains(&self.value) => Err(ValueError::OutOfRange),
+            _ => Ok(()),
+        }
+    }
+
+    /// Append the wire format of the reading to `buf`.
+    #[cfg(feature = "alloc")]
+    fn encode(&self, buf: &mut Vec<u8>) {
+        match self.index {
+            Some(index) => buf.extend_from_slice(&[self.kind.code() | INDEX_FLAG, index]),
+            None => buf.push(self.kind.code()),
+        }
+
+        buf.extend_from_slice(&self.value.to_le_bytes());
+    }
+
+    /// Decode the reading at the start of `bytes`.
+    ///
+    /// Returns the reading and the remaining bytes, or [`None`] if the reading is truncated.
+    fn decode(bytes: &[u8]) -> Option<(Self, &[u8])> {
+        let (&kind, rest) = bytes.split_first()?;
+        let (index, rest) = if kind & INDEX_FLAG == 0 {
+            (None, rest)
+        } else {
+            let (&index, rest) = rest.split_first()?;
+            (Some(index), rest)
+        };
+        let (value, rest) = rest.split_first_chunk::<4>()?;
+
+        let reading = Self {
+            kind: SensorKind::from_code(kind & KIND_MASK)?,
+            index,
+            value: f32::from_le_bytes(*value),
+        };
+
+        Some((reading, rest))
+    }
+}
+
+impl<'a> MeasurementsRef<'a> {
+    /// Wrap packed readings.
+    ///
+    /// Returns [`None`] if the last reading is truncated.
+    #[must_use]
+    pub fn from_bytes(bytes: &'a [u8]) -> Option<Self> {
+        let mut readings = Readings(bytes);
+        while !readings.0.is_empty() {
+            readings.next()?;
+        }
+
+        Some(Self(bytes))
+    }
+
+    /// Returns the packed readings.
+    #[must_use]
+    pub const fn as_bytes(&self) -> &'a [u8] {
+        self.0
+    }
+
+    /// Returns an iterator over the readings.
+    #[must_use]
+    pub const fn iter(&self) -> Readings<'a> {
+        Readings(self.0)
+    }
+
+    /// Returns the number of readings.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Returns whether there are no readings.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the first reading of the given kind.
+    #[must_use]
+    pub fn first(&self, kind: SensorKind) -> Option<Reading> {
+        self.iter().find(|reading| reading.kind == kind)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Measurements {
+    /// Returns the packed readings.
+    #[must_use]
+    pub const fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Returns an iterator over the readings.
+    #[must_use]
+    pub fn iter(&self) -> Readings<'_> {
+        Readings(&self.0)
+    }
+
+    /// Returns the number of readings.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Returns whether there are no readings.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the first reading of the given kind.
+    #[must_use]
+    pub fn first(&self, kind: SensorKind) -> Option<Reading> {
+        MeasurementsRef::from(self).first(kind)
+    }
+}
+
+impl SampleTime {
+    /// Returns the Unix time of the sample, given the Unix time `now` at which the request
+    /// was received.
+    #[must_use]
+    pub const fn to_timestamp(self, now: u64) -> u64 {
+        match self {
+            Self::Timestamp(timestamp) => timestamp,
+            Self::Age(age) => now.saturating_sub(age as u64),
+        }
+    }
+}
+
+impl<'a> Sample<'a> {
+    /// Create a sample of measurements taken at the given time.
+    pub fn new(time: SampleTime, measurements: impl Into<MeasurementsRef<'a>>) -> Self {
+        Self {
+            time,
+            measurements: measurements.into(),
+        }
+    }
+
+    /// Append the wire format of the sample to `buf`.
+    #[cfg(feature = "alloc")]
+    fn encode(&self, buf: &mut Vec<u8>) {
+        let (tag, value) = match self.time {
+            SampleTime::Timestamp(timestamp) => (TIME_TIMESTAMP, timestamp),
+            SampleTime::Age(age) => (TIME_AGE, age.into()),
+        };
+        let readings = self.measurements.as_bytes();
+
+        buf.push(tag);
+        codec::append_varint(value, buf);
+        codec::append_varint(readings.len() as u64, buf);
+        buf.extend_from_slice(readings);
+    }
+
+    /// Decode the sample at the start of `bytes`.
+    ///
+    /// Returns the sample and the remaining bytes, or [`None`] if the sample is invalid.
+    fn decode(bytes: &'a [u8]) -> Option<(Self, &'a [u8])> {
+        let (&tag, rest) = bytes.split_first()?;
+        let (value, rest) = postcard::take_from_bytes::<u64>(rest).ok()?;
+        let time = match tag {
+            TIME_TIMESTAMP => SampleTime::Timestamp(value),
+            TIME_AGE => SampleTime::Age(u32::try_from(value).ok()?),
+            _ => return None,
+        };
+
+        let (len, rest) = postcard::take_from_bytes::<u64>(rest).ok()?;
+        let len = usize::try_from(len).ok().filter(|len| *len <= rest.len())?;
+        let (readings, rest) = rest.split_at(len);
+
+        let sample = Self {
+            time,
+            measurements: MeasurementsRef::from_bytes(readings)?,
+        };
+
+        Some((sample, rest))
+    }
+}
+
+impl<'a> MeasurementBatchRef<'a> {
+    /// Wrap packed samples.
+    ///
+    /// Returns [`None`] if a sample is invalid or truncated.
+    #[must_use]
+    pub fn from_bytes(bytes: &'a [u8]) -> Option<Self> {
+        let mut samples = Samples(bytes);
+        while !samples.0.is_empty() {
+            samples.next()?;
+        }
+
+        Some(Self(bytes))
+    }
+
+    /// Returns the packed samples.
+    #[must_use]
+    pub const fn as_bytes(&self) -> &'a [u8] {
+        self.0
+    }
+
+    /// Returns an iterator over the samples.
+    #[must_use]
+    pub const fn iter(&self) -> Samples<'a> {
+        Samples(self.0)
+    }
+
+    /// Returns the number of samples.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Returns whether there are no samples.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl MeasurementBatch {
+    /// Returns the packed samples.
+    #[must_use]
+    pub const fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Returns an iterator over the samples.
+    #[must_use]
+    pub fn iter(&self) -> Samples<'_> {
+        Samples(&self.0)
+    }
+
+    /// Returns the number of samples.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Returns whether there are no samples.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Iterator for Readings<'_> {
+    type Item = Reading;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (reading, rest) = Reading::decode(self.0)?;
+        self.0 = rest;
+
+        Some(reading)
+    }
+}
+
+impl<'a> IntoIterator for MeasurementsRef<'a> {
+    type Item = Reading;
+    type IntoIter = Readings<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> Iterator for Samples<'a> {
+    type Item = Sample<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (sample, rest) = Sample::decode(self.0)?;
+        self.0 = rest;
+
+        Some(sample)
+    }
+}
+
+impl<'a> IntoIterator for MeasurementBatchRef<'a> {
+    type Item = Sample<'a>;
+    type IntoIter = Samples<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a> IntoIterator for &'a MeasurementBatch {
+    type Item = Sample<'a>;
+    type IntoIter = Samples<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a> IntoIterator for &'a Measurements {
+    type Item = Reading;
+    type IntoIter = Readings<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl FromIterator<Reading> for Measurements {
+    fn from_iter<T: IntoIterator<Item = Reading>>(iter: T) -> Self {
+        let mut buf = Vec::new();
+        for reading in iter {
+            reading.encode(&mut buf);
+        }
+
+        Self(buf.into_boxed_slice())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a> FromIterator<Sample<'a>> for MeasurementBatch {
+    fn from_iter<T: IntoIterator<Item = Sample<'a>>>(iter: T) -> Self {
+        let mut buf = Vec::new();
+        for sample in iter {
+            sample.encode(&mut buf);
+        }
+
+        Self(buf.into_boxed_slice())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a> From<&'a MeasurementBatch> for MeasurementBatchRef<'a> {
+    fn from(value: &'a MeasurementBatch) -> Self {
+        Self(&value.0)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<MeasurementBatchRef<'_>> for MeasurementBatch {
+    fn from(value: MeasurementBatchRef<'_>) -> Self {
+        Self(value.0.into())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a> From<&'a Measurements> for MeasurementsRef<'a> {
+    fn from(value: &'a Measurements) -> Self {
+        Self(&value.0)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<MeasurementsRef<'_>> for Measurements {
+    fn from(value: MeasurementsRef<'_>) -> Self {
+        Self(value.0.into())
+    }
+}
+
+impl core::fmt::Debug for MeasurementsRef<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl core::fmt::Debug for Measurements {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl core::fmt::Debug for MeasurementBatchRef<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl core::fmt::Debug for MeasurementBatch {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl Serialize for MeasurementsRef<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for MeasurementsRef<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = <&'a [u8]>::deserialize(deserializer)?;
+
+        Self::from_bytes(bytes).ok_or_else(|| D::Error::custom("truncated sensor reading"))
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Serialize for Measurements {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'de> Deserialize<'de> for Measurements {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = Box::<[u8]>::deserialize(deserializer)?;
+
+        if MeasurementsRef::from_bytes(&bytes).is_none() {
+            return Err(D::Error::custom("truncated sensor reading"));
+        }
+
+        Ok(Self(bytes))
+    }
+}
+
+impl Serialize for MeasurementBatchRef<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for MeasurementBatchRef<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = <&'a [u8]>::deserialize(deserializer)?;
+
+        Self::from_bytes(bytes).ok_or_else(|| D::Error::custom("invalid measurement sample"))
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Serialize for MeasurementBatch {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'de> Deserialize<'de> for MeasurementBatch {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = Box::<[u8]>::deserialize(deserializer)?;
+
+        if MeasurementBatchRef::from_bytes(&bytes).is_none() {
+            return Err(D::Error::custom("invalid measurement sample"));
+        }
+
+        Ok(Self(bytes))
+    }
+}
+
+impl Display for SensorKind {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Temperature => write!(f, "temperature"),
+            Self::Humidity => write!(f, "humidity"),
+            Self::AirPressure => write!(f, "air pressure"),
+            Self::Co2 => write!(f, "CO2"),
+            Self::Pm2_5 => write!(f, "PM2.5"),
+            Self::Pm10 => write!(f, "PM10"),
+            Self::Voc => write!(f, "VOC"),
+            Self::Illuminance => write!(f, "illuminance"),
+            Self::UvIndex => write!(f, "UV index"),
+            Self::WindSpeed => write!(f, "wind speed"),
+            Self::WindDirection => write!(f, "wind direction"),
+            Self::Rainfall => write!(f, "rainfall"),
+            Self::Other(kind) => write!(f, "sensor kind {}", kind.code()),
+        }
+    }
+}
@@ -0,0 +1,249 @@
+//! Integrity checks for firmware updates.
+//!
+//! The server announces the size and SHA-256 digest of the image in
+//! [`UpdateAvailable`](crate::response::Response::UpdateAvailable), and every
+//! [`UpdatePart`](crate::response::Response::UpdatePart) carries a CRC32 of its data.
+//! The [`ImageVerifier`] checks both on the node's side.
+//!
+//! After installing an update, nodes report the outcome with
+//! [`ReportFirmwareUpdate`](crate::request::Request::ReportFirmwareUpdate), including a
+//! [`RollbackReason`] if the new firmware was rejected.
+
+use core::fmt::Display;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// A SHA-256 digest.
+pub type Sha256Digest = [u8; 32];
+
+/// Verifies a firmware image as its chunks arrive.
+///
+/// Chunks must be passed in order, since the digest is computed incrementally. A node that
+/// resumes a download after a reboot has to pass the chunks it has already stored first.
+///
+/// ```rust
+/// use pwmp_msg::ota::{crc32, sha256, ImageVerifier};
+///
+/// let image = b"firmware image";
+/// let mut verifier = ImageVerifier::new(image.len() as u32, sha256(image));
+///
+/// for (i, chunk) in image.chunks(4).enumerate() {
+///     verifier.verify_chunk(i as u32 * 4, crc32(chunk), chunk).unwrap();
+/// }
+///
+/// assert!(verifier.is_complete());
+/// assert_eq!(verifier.finish(), Ok(()));
+/// ```
+#[derive(Debug, Clone)]
+pub struct ImageVerifier {
+    /// Announced size of the image.
+    size: u32,
+
+    /// Announced digest of the image.
+    sha256: Sha256Digest,
+
+    /// Digest of the data received so far.
+    hasher: Sha256,
+
+    /// Number of bytes received so far.
+    received: u32,
+}
+
+/// Errors that can occur while verifying a firmware image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VerifyError {
+    /// A chunk doesn't start where the previous one ended.
+    UnexpectedOffset {
+        /// Offset at which the next chunk was expected to start.
+        expected: u32,
+
+        /// Offset at which the chunk actually starts.
+        actual: u32,
+    },
+
+    /// The data of the chunk at the given offset doesn't match its CRC32.
+    ChecksumMismatch {
+        /// Offset of the corrupted chunk.
+        offset: u32,
+    },
+
+    /// A chunk extends past the announced size of the image.
+    Overflow {
+        /// Announced size of the image.
+        size: u32,
+    },
+
+    /// The image was finished before all of its bytes were received.
+    Incomplete {
+        /// Number of bytes received.
+        received: u32,
+
+        /// Announced size of the image.
+        size: u32,
+    },
+
+    /// The digest of the received image doesn't match the announced one.
+    DigestMismatch,
+}
+
+/// Reason why a node has rolled back to its previous firmware.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RollbackReason {
+    /// The new firmware kept rebooting before it could confirm itself.
+    BootLoop,
+
+    /// The new firmware booted, but its self-test failed.
+    SelfTestFailed,
+
+    /// The watchdog has reset the node while running the new firmware.
+    WatchdogReset,
+
+    /// The new firmware has panicked.
+    Panic,
+
+    /// The bootloader has refused to boot the new image, for example because it's corrupted.
+    InvalidImage,
+
+    /// The new firmware could not reach the server.
+    ConnectionFailed,
+
+    /// Any other reason. The diagnostic message of the report should contain the details.
+    Other,
+}
+
+/// Computes the CRC32 checksum *(IEEE)* of a chunk's data.
+#[must_use]
+pub fn crc32(data: &[u8]) -> u32 {
+    crc32fast::hash(data)
+}
+
+/// Computes the SHA-256 digest of an image.
+#[must_use]
+pub fn sha256(data: &[u8]) -> Sha256Digest {
+    Sha256::digest(data).into()
+}
+
+impl ImageVerifier {
+    /// Create a new verifier for an image with the announced size and digest.
+    #[must_use]
+    pub fn new(size: u32, sha256: Sha256Digest) -> Self {
+        Self {
+            size,
+            sha256,
+            hasher: Sha256::new(),
+            received: 0,
+        }
+    }
+
+    /// Returns the announced size of the image.
+    #[must_use]
+    pub const fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Returns the number of bytes received so far.
+    #[must_use]
+    pub const fn received(&self) -> u32 {
+        self.received
+    }
+
+    /// Returns whether all bytes of the image have been received.
+    #[must_use]
+    pub const fn is_complete(&self) -> bool {
+        self.received == self.size
+    }
+
+    /// Check a chunk that starts at `offset` against its CRC32, and add it to the digest.
+    ///
+    /// # Errors
+    /// Returns [`VerifyError::UnexpectedOffset`] if the chunk doesn't continue the image,
+    /// [`VerifyError::Overflow`] if it extends past the announced size, and
+    /// [`VerifyError::ChecksumMismatch`] if its data is corrupted.
+    /// The chunk is not added to the digest in these cases.
+    pub fn verify_chunk(
+        &mut self,
+        offset: u32,
+        crc32: u32,
+        data: &[u8],
+    ) -> Result<(), VerifyError> {
+        self.check_chunk(offset, crc32, data)?;
+        self.add_checked_chunk(data);
+
+        Ok(())
+    }
+
+    /// Check a chunk that starts at `offset` against its CRC32, without adding it to the digest.
+    ///
+    /// Use this to store the chunk before [accepting](Self::verify_chunk) it, so a chunk that
+    /// could not be stored is requested again.
+    ///
+    /// # Errors
+    /// Returns the same errors as [`verify_chunk()`](Self::verify_chunk).
+    pub fn check_chunk(&self, offset: u32, crc32: u32, data: &[u8]) -> Result<(), VerifyError> {
+        if offset != self.received {
+            return Err(VerifyError::UnexpectedOffset {
+                expected: self.received,
+                actual: offset,
+            });
+        }
+
+        u32::try_from(data.len())
+            .ok()
+            .and_then(|len| offset.checked_add(len))
+            .filter(|end| *end <= self.size)
+            .ok_or(VerifyError::Overflow { size: self.size })?;
+
+        if self::crc32(data) != crc32 {
+            return Err(VerifyError::ChecksumMismatch { offset });
+        }
+
+        Ok(())
+    }
+
+    /// Add a chunk that has passed [`check_chunk()`](Self::check_chunk) to the digest.
+    #[allow(clippy::cast_possible_truncation)] // Checked not to exceed the size.
+    pub(crate) fn add_checked_chunk(&mut self, data: &[u8]) {
+        self.hasher.update(data);
+        self.received += data.len() as u32;
+    }
+
+    /// Check that the whole image was received and matches the announced digest.
+    ///
+    /// # Errors
+    /// Returns [`VerifyError::Incomplete`] if some bytes are missing, and
+    /// [`VerifyError::DigestMismatch`] if the image is corrupted.
+    pub fn finish(self) -> Result<(), VerifyError> {
+        if !self.is_complete() {
+            return Err(VerifyError::Incomplete {
+                received: self.received,
+                size: self.size,
+            });
+        }
+
+        if Sha256Digest::from(self.hasher.finalize()) != self.sha256 {
+            return Err(VerifyError::DigestMismatch);
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for VerifyError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::UnexpectedOffset { expected, actual } => {
+                write!(f, "expected chunk at offset {expected}, got {actual}")
+            }
+            Self::ChecksumMismatch { offset } => {
+                write!(f, "checksum mismatch in chunk at offset {offset}")
+            }
+            Self::Overflow { size } => write!(f, "chunk exceeds the image size of {size} bytes"),
+            Self::Incomplete { received, size } => {
+                write!(f, "received only {received} of {size} bytes")
+            }
+            Self::DigestMismatch => write!(f, "image digest mismatch"),
+        }
+    }
+}
+
+impl core::error::Error for VerifyError {}
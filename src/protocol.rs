@@ -0,0 +1,115 @@
+//! Protocol revision negotiation.
+//!
+//! Every change to the wire format of [`Request`](crate::request::Request)s or
+//! [`Response`](crate::response::Response)s bumps the [`PROTOCOL_REVISION`]. Nodes announce
+//! the revision they speak in their [`Handshake`](crate::request::Request::Handshake), and the
+//! server either confirms it or rejects the node. Both sides use [`RevisionRange::negotiate()`]
+//! to make that decision.
+
+use serde::{Deserialize, Serialize};
+
+/// Revision number of the protocol.
+pub type ProtocolRevision = u16;
+
+/// Revision of the protocol implemented by this version of the library.
+pub const PROTOCOL_REVISION: ProtocolRevision = 1;
+
+/// An inclusive range of supported protocol revisions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RevisionRange {
+    /// Oldest supported revision.
+    min: ProtocolRevision,
+
+    /// Newest supported revision.
+    max: ProtocolRevision,
+}
+
+/// Reason why the server has rejected a node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RejectReason {
+    /// The node's MAC address is unknown or not allowed to communicate with the server.
+    Unauthorized,
+
+    /// The server does not support the node's protocol revision.
+    UnsupportedProtocol {
+        /// Oldest revision supported by the server.
+        min: ProtocolRevision,
+
+        /// Newest revision supported by the server.
+        max: ProtocolRevision,
+    },
+}
+
+impl RevisionRange {
+    /// The revisions supported by this version of the library.
+    pub const CURRENT: Self = Self::new(PROTOCOL_REVISION, PROTOCOL_REVISION);
+
+    /// Create a new range of supported revisions.
+    ///
+    /// If `min` is greater than `max`, they're swapped.
+    #[must_use]
+    pub const fn new(min: ProtocolRevision, max: ProtocolRevision) -> Self {
+        if min <= max {
+            Self { min, max }
+        } else {
+            Self { min: max, max: min }
+        }
+    }
+
+    /// Returns the oldest supported revision.
+    #[must_use]
+    pub const fn min(&self) -> ProtocolRevision {
+        self.min
+    }
+
+    /// Returns the newest supported revision.
+    #[must_use]
+    pub const fn max(&self) -> ProtocolRevision {
+        self.max
+    }
+
+    /// Returns whether the given revision is supported.
+    #[must_use]
+    pub const fn contains(&self, revision: ProtocolRevision) -> bool {
+        revision >= self.min && revision <= self.max
+    }
+
+    /// Decide which revision to use for a session with a peer that speaks `revision`.
+    ///
+    /// The server calls this with the revision announced in the node's handshake, and the node
+    /// calls it with the revision confirmed by the server.
+    ///
+    /// ```rust
+    /// use pwmp_msg::protocol::{RejectReason, RevisionRange};
+    ///
+    /// let server = RevisionRange::new(2, 4);
+    ///
+    /// assert_eq!(server.negotiate(3), Ok(3));
+    /// assert_eq!(
+    ///     server.negotiate(1),
+    ///     Err(RejectReason::UnsupportedProtocol { min: 2, max: 4 })
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    /// Returns [`RejectReason::UnsupportedProtocol`] if the revision is outside of this range.
+    pub const fn negotiate(
+        &self,
+        revision: ProtocolRevision,
+    ) -> Result<ProtocolRevision, RejectReason> {
+        if self.contains(revision) {
+            Ok(revision)
+        } else {
+            Err(RejectReason::UnsupportedProtocol {
+                min: self.min,
+                max: self.max,
+            })
+        }
+    }
+}
+
+impl Default for RevisionRange {
+    fn default() -> Self {
+        Self::CURRENT
+    }
+}
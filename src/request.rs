@@ -1,12 +1,26 @@
-//! Contains the definition if a request message.
+//! Contains the definition if a request message, and its borrowed counterpart.
 
 use crate::{
-    aliases::{AirPressure, BatteryVoltage, Humidity, Rssi, Temperature},
+    aliases::{AirPressure, BatteryVoltage, Humidity, Rssi, Temperature, ValueError},
+    compression::Compression,
+    diagnostics::Diagnostics,
     mac::Mac,
+    measurement::{
+        MeasurementBatch, MeasurementBatchRef, Measurements, MeasurementsRef, Reading, SensorKind,
+    },
+    ota::RollbackReason,
+    protocol::ProtocolRevision,
+    response::Response,
+    rollout::{ReleaseChannel, ReleaseChannelRef},
     version::Version,
 };
+use alloc::boxed::Box;
+use core::fmt::Display;
 use serde::{Deserialize, Serialize};
 
+/// Maximum length of a WiFi SSID in bytes.
+const MAX_SSID_LEN: usize = 32;
+
 /// A request message used by nodes to ask the PWMP server to perform an operation.
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub enum Request {
@@ -14,31 +28,55 @@ pub enum Request {
     Ping,
 
     /// Ask to server to authorize the node using it's MAC address.
+    ///
+    /// The server answers with [`Response::HandshakeAccepted`] or [`Response::Reject`].
+    ///
+    /// **Starting with protocol revision 1, the wire format of this variant and of its responses
+    /// must stay the same in all protocol revisions.** Nodes from before revision 1 only sent
+    /// their MAC address, and can't be decoded.
     Handshake {
         #[allow(clippy::doc_markdown)]
         /// The node's MAC address. This address should be that of the WiFi interface.
         mac: Mac,
+        /// Protocol revision spoken by the node.
+        protocol: ProtocolRevision,
+        /// Version of the firmware running on the node.
+        firmware: Version,
     },
 
     /// Post measurement results to the database.
     PostResults {
-        /// Temperature
+        /// Temperature, with a precision of 0.01 °C
         temperature: Temperature,
-        /// Humidity
+        /// Relative humidity, from 0 % to 100 %
         humidity: Humidity,
-        /// Air pressure *(if supported by the node)*
+        /// Air pressure, from 300 hPa to 1100 hPa *(if supported by the node)*
         air_pressure: Option<AirPressure>,
     },
 
+    /// Post the readings of any number of sensors to the database.
+    ///
+    /// This replaces [`PostResults`](Self::PostResults) for nodes with other sensors, or with
+    /// several sensors of the same kind. See the [`measurement`](crate::measurement) module.
+    PostMeasurements(Measurements),
+
+    /// Post buffered measurements, each with the time it was taken at.
+    ///
+    /// Nodes send this after they couldn't reach the server for a while. Unlike
+    /// [`PostResults`](Self::PostResults), any number of batches may be posted in a session.
+    PostMeasurementBatch(MeasurementBatch),
+
     /// Post node statistics to the database.
     PostStats {
-        /// Node's battery voltage
+        /// Node's battery voltage, with a precision of 1 mV
         battery: BatteryVoltage,
-        /// ESSID of the wireless network
+        /// ESSID of the wireless network, at most 32 bytes long
         wifi_ssid: Box<str>,
         /// RSSI *(signal quality)* of the connection to the wireless network in dBm *(decibel meters)*.
-        /// Higher values (closer to 0) mean a better quality.
+        /// Higher values (closer to 0) mean a better quality, positive values are invalid.
         wifi_rssi: Rssi,
+        /// Uptime, reset reason, memory usage and connection details of the node
+        diagnostics: Diagnostics,
     },
 
     /// Store a notification in the database. These can be read by other applications.
@@ -51,21 +89,482 @@ pub enum Request {
 
     /// Check for a firmware update.
     /// This will also cache the update on the server.
-    UpdateCheck(Version),
+    ///
+    /// The server only offers releases of the node's channel and board, see the [`rollout`](crate::rollout) module.
+    UpdateCheck {
+        /// Version of the firmware running on the node.
+        firmware: Version,
+        /// Release channel followed by the node.
+        channel: ReleaseChannel,
+        /// Hardware/board identifier of the node.
+        board: Box<str>,
+        /// Whether the node can apply a [delta patch](crate::delta) against its running firmware.
+        delta: bool,
+    },
 
-    /// Request a part of a firmware upgrade.
-    /// The parameter is the maximum chunk size that shall be received.
+    /// Request a part of a firmware upgrade, starting at the given byte offset of the image.
+    ///
+    /// Since the offset is explicit, a node can resume an interrupted download (for example after
+    /// a reconnect or a reboot), and the server doesn't need to remember how far each node got.
     ///
     /// The size type is intentionally [`u32`] to help prevent out-of-memory denial of service attacks.
     /// While it's still possible to make a OOM attack, the server has a connection limit and a rate limit to help mitigate this.
     ///
     /// **The client must request an update check first before sending this request.**
-    NextUpdateChunk(u32),
+    NextUpdateChunk {
+        /// Offset of the first requested byte within the firmware image.
+        offset: u32,
+        /// The maximum chunk size that shall be received, before compression.
+        max_size: u32,
+        /// Compression the node can decode. The server may still send an uncompressed chunk.
+        compression: Compression,
+        /// Base firmware version of the announced [delta patch](crate::manifest::DeltaManifest), or `None` if the full image is downloaded.
+        ///
+        /// Offsets and sizes refer to the patch if this is set.
+        base: Option<Version>,
+    },
 
     /// Report back about the updated firmware version.
-    /// The parameter means whether this new firmware is working, or was bad, and the node has rolled back to a previous version.
-    ReportFirmwareUpdate(bool),
+    ///
+    /// This is sent in the first session after the node has booted a new firmware, or has rolled back from it.
+    ReportFirmwareUpdate {
+        /// Version of the firmware that was running before the update.
+        previous: Version,
+        /// Version of the installed update.
+        new: Version,
+        /// Why the node has rolled back to the previous firmware, or `None` if the new firmware is working.
+        rollback: Option<RollbackReason>,
+        /// An optional short diagnostic message, such as the last panic message or reset cause.
+        diagnostic: Option<Box<str>>,
+    },
 
     /// Tell the server that the session is over and the node will disconnect.
     Bye,
 }
+
+impl Request {
+    /// Returns whether the server answers this request.
+    ///
+    /// ```rust
+    /// use pwmp_msg::request::Request;
+    ///
+    /// assert!(Request::Ping.expects_response());
+    /// assert!(!Request::Bye.expects_response());
+    /// ```
+    #[must_use]
+    pub const fn expects_response(&self) -> bool {
+        !matches!(self, Self::Bye)
+    }
+
+    /// Returns whether the given response is a valid answer to this request.
+    ///
+    /// [Erroneous](Response::is_error) responses are valid answers to any request
+    /// that [expects a response](Self::expects_response).
+    ///
+    /// ```rust
+    /// use pwmp_msg::{request::Request, response::Response};
+    ///
+    /// assert!(Request::Ping.accepts(&Response::Pong));
+    /// assert!(Request::Ping.accepts(&Response::InternalServerError));
+    /// assert!(!Request::Ping.accepts(&Response::Ok));
+    /// ```
+    #[must_use]
+    pub const fn accepts(&self, response: &Response) -> bool {
+        if !self.expects_response() {
+            return false;
+        }
+
+        if response.is_error() {
+            return true;
+        }
+
+        match self {
+            Self::Ping => matches!(response, Response::Pong),
+            Self::Handshake { .. } => matches!(response, Response::HandshakeAccepted { .. }),
+            Self::PostResults { .. }
+            | Self::PostMeasurements(..)
+            | Self::PostMeasurementBatch(..)
+            | Self::PostStats { .. }
+            | Self::SendNotification(..)
+            | Self::ReportFirmwareUpdate { .. } => matches!(response, Response::Ok),
+            Self::GetSettings => matches!(response, Response::Settings(..)),
+            Self::UpdateCheck { .. } => matches!(
+                response,
+                Response::FirmwareUpToDate | Response::UpdateAvailable(..)
+            ),
+            Self::NextUpdateChunk { .. } => {
+                matches!(response, Response::UpdatePart { .. } | Response::UpdateEnd)
+            }
+            Self::Bye => false,
+        }
+    }
+
+    /// Returns the measurement results of a [`PostResults`](Self::PostResults) or
+    /// [`PostMeasurements`](Self::PostMeasurements) request as a list of readings.
+    ///
+    /// Returns [`None`] for other requests.
+    ///
+    /// ```rust
+    /// use pwmp_msg::{
+    ///     aliases::{Humidity, Temperature},
+    ///     measurement::SensorKind,
+    ///     request::Request,
+    /// };
+    ///
+    /// let request = Request::PostResults {
+    ///     temperature: Temperature::from_centi_celsius(2150),
+    ///     humidity: Humidity::new(40).unwrap(),
+    ///     air_pressure: None,
+    /// };
+    /// let measurements = request.measurements().unwrap();
+    ///
+    /// assert_eq!(measurements.len(), 2);
+    /// assert_eq!(measurements.first(SensorKind::Humidity).unwrap().value, 40.0);
+    /// ```
+    #[must_use]
+    pub fn measurements(&self) -> Option<Measurements> {
+        RequestRef::from(self).measurements()
+    }
+
+    /// Check that all measured values and strings of the request are valid.
+    ///
+    /// Values with a fixed type are already checked when they are constructed or deserialized, so
+    /// an out-of-range [`Humidity`], [`AirPressure`] or [`Rssi`] makes
+    /// [`Message::deserialize()`](crate::Message::deserialize) fail with
+    /// [`DecodeError::InvalidValue`](crate::error::DecodeError::InvalidValue) instead. This also checks each [`Reading`] of [`PostMeasurements`](Self::PostMeasurements) and
+    /// [`PostMeasurementBatch`](Self::PostMeasurementBatch), the length of the SSID, and the
+    /// [diagnostics](Diagnostics) of [`PostStats`](Self::PostStats).
+    /// Servers answer invalid requests with [`Response::InvalidRequest`].
+    ///
+    /// # Errors
+    /// Returns the first field that is invalid.
+    ///
+    /// ```rust
+    /// use pwmp_msg::{
+    ///     aliases::ValueError,
+    ///     measurement::{Reading, SensorKind},
+    ///     request::{InvalidField, Request},
+    /// };
+    ///
+    /// let request = Request::PostMeasurements(
+    ///     [Reading::new(SensorKind::Humidity, 250.0)]
+    ///         .into_iter()
+    ///         .collect(),
+    /// );
+    ///
+    /// assert_eq!(
+    ///     request.validate(),
+    ///     Err(InvalidField {
+    ///         field: "measurements",
+    ///         error: ValueError::OutOfRange
+    ///     })
+    /// );
+    /// ```
+    pub fn validate(&self) -> Result<(), InvalidField> {
+        RequestRef::from(self).validate()
+    }
+}
+
+impl RequestRef<'_> {
+    /// Returns the measurement results of a [`PostResults`](Self::PostResults) or
+    /// [`PostMeasurements`](Self::PostMeasurements) request as a list of readings.
+    ///
+    /// Returns [`None`] for other requests.
+    #[must_use]
+    pub fn measurements(&self) -> Option<Measurements> {
+        match *self {
+            Self::PostResults {
+                temperature,
+                humidity,
+                air_pressure,
+            } => {
+                let readings = [
+                    Some(Reading::new(SensorKind::Temperature, temperature.celsius())),
+                    Some(Reading::new(SensorKind::Humidity, humidity.into())),
+                    air_pressure
+                        .map(|pressure| Reading::new(SensorKind::AirPressure, pressure.into())),
+                ];
+
+                Some(readings.into_iter().flatten().collect())
+            }
+            Self::PostMeasurements(measurements) => Some(measurements.into()),
+            _ => None,
+        }
+    }
+
+    /// Check that all measured values and strings of the request are valid.
+    /// See [`Request::validate()`].
+    ///
+    /// # Errors
+    /// Returns the first field that is invalid.
+    pub fn validate(&self) -> Result<(), InvalidField> {
+        match *self {
+            Self::PostMeasurements(measurements) => validate_readings("measurements", measurements),
+            Self::PostMeasurementBatch(batch) => batch
+                .iter()
+                .try_for_each(|sample| validate_readings("measurement_batch", sample.measurements)),
+            Self::PostStats {
+                wifi_ssid,
+                diagnostics,
+                ..
+            } => {
+                if wifi_ssid.len() > MAX_SSID_LEN {
+                    return Err(InvalidField::out_of_range("wifi_ssid"));
+                }
+
+                if diagnostics.wifi_channel == Some(0) {
+                    return Err(InvalidField::out_of_range("wifi_channel"));
+                }
+
+                if matches!(
+                    (diagnostics.free_heap, diagnostics.min_free_heap),
+                    (Some(free), Some(min)) if min > free
+                ) {
+                    return Err(InvalidField::out_of_range("min_free_heap"));
+                }
+
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// A field of a request that failed [validation](Request::validate).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InvalidField {
+    /// Name of the field.
+    pub field: &'static str,
+
+    /// Why the value of the field is invalid.
+    pub error: ValueError,
+}
+
+impl InvalidField {
+    /// Create an error for a field with a value that is out of range.
+    const fn out_of_range(field: &'static str) -> Self {
+        Self {
+            field,
+            error: ValueError::OutOfRange,
+        }
+    }
+}
+
+/// Check all readings, and report invalid ones as the given field.
+fn validate_readings(
+    field: &'static str,
+    readings: MeasurementsRef<'_>,
+) -> Result<(), InvalidField> {
+    readings
+        .iter()
+        .try_for_each(|reading| reading.validate())
+        .map_err(|error| InvalidField { field, error })
+}
+
+/// Borrowed counterpart of [`Request`], which references strings in the buffer it was decoded from.
+///
+/// Both types have the same wire format. See [`Request`] for the meaning of each variant.
+#[allow(missing_docs, clippy::missing_docs_in_private_items)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+pub enum RequestRef<'a> {
+    Ping,
+    Handshake {
+        mac: Mac,
+        protocol: ProtocolRevision,
+        firmware: Version,
+    },
+    PostResults {
+        temperature: Temperature,
+        humidity: Humidity,
+        air_pressure: Option<AirPressure>,
+    },
+    PostMeasurements(MeasurementsRef<'a>),
+    PostMeasurementBatch(MeasurementBatchRef<'a>),
+    PostStats {
+        battery: BatteryVoltage,
+        wifi_ssid: &'a str,
+        wifi_rssi: Rssi,
+        diagnostics: Diagnostics,
+    },
+    SendNotification(&'a str),
+    GetSettings,
+    UpdateCheck {
+        firmware: Version,
+        channel: ReleaseChannelRef<'a>,
+        board: &'a str,
+        delta: bool,
+    },
+    NextUpdateChunk {
+        offset: u32,
+        max_size: u32,
+        compression: Compression,
+        base: Option<Version>,
+    },
+    ReportFirmwareUpdate {
+        previous: Version,
+        new: Version,
+        rollback: Option<RollbackReason>,
+        diagnostic: Option<&'a str>,
+    },
+    Bye,
+}
+
+impl From<RequestRef<'_>> for Request {
+    fn from(value: RequestRef<'_>) -> Self {
+        match value {
+            RequestRef::Ping => Self::Ping,
+            RequestRef::Handshake {
+                mac,
+                protocol,
+                firmware,
+            } => Self::Handshake {
+                mac,
+                protocol,
+                firmware,
+            },
+            RequestRef::PostResults {
+                temperature,
+                humidity,
+                air_pressure,
+            } => Self::PostResults {
+                temperature,
+                humidity,
+                air_pressure,
+            },
+            RequestRef::PostMeasurements(measurements) => {
+                Self::PostMeasurements(measurements.into())
+            }
+            RequestRef::PostMeasurementBatch(batch) => Self::PostMeasurementBatch(batch.into()),
+            RequestRef::PostStats {
+                battery,
+                wifi_ssid,
+                wifi_rssi,
+                diagnostics,
+            } => Self::PostStats {
+                battery,
+                wifi_ssid: wifi_ssid.into(),
+                wifi_rssi,
+                diagnostics,
+            },
+            RequestRef::SendNotification(text) => Self::SendNotification(text.into()),
+            RequestRef::GetSettings => Self::GetSettings,
+            RequestRef::UpdateCheck {
+                firmware,
+                channel,
+                board,
+                delta,
+            } => Self::UpdateCheck {
+                firmware,
+                channel: channel.into(),
+                board: board.into(),
+                delta,
+            },
+            RequestRef::NextUpdateChunk {
+                offset,
+                max_size,
+                compression,
+                base,
+            } => Self::NextUpdateChunk {
+                offset,
+                max_size,
+                compression,
+                base,
+            },
+            RequestRef::ReportFirmwareUpdate {
+                previous,
+                new,
+                rollback,
+                diagnostic,
+            } => Self::ReportFirmwareUpdate {
+                previous,
+                new,
+                rollback,
+                diagnostic: diagnostic.map(Into::into),
+            },
+            RequestRef::Bye => Self::Bye,
+        }
+    }
+}
+
+impl<'a> From<&'a Request> for RequestRef<'a> {
+    fn from(value: &'a Request) -> Self {
+        match value {
+            Request::Ping => Self::Ping,
+            Request::Handshake {
+                mac,
+                protocol,
+                firmware,
+            } => Self::Handshake {
+                mac: *mac,
+                protocol: *protocol,
+                firmware: *firmware,
+            },
+            Request::PostResults {
+                temperature,
+                humidity,
+                air_pressure,
+            } => Self::PostResults {
+                temperature: *temperature,
+                humidity: *humidity,
+                air_pressure: *air_pressure,
+            },
+            Request::PostMeasurements(measurements) => Self::PostMeasurements(measurements.into()),
+            Request::PostMeasurementBatch(batch) => Self::PostMeasurementBatch(batch.into()),
+            Request::PostStats {
+                battery,
+                wifi_ssid,
+                wifi_rssi,
+                diagnostics,
+            } => Self::PostStats {
+                battery: *battery,
+                wifi_ssid,
+                wifi_rssi: *wifi_rssi,
+                diagnostics: *diagnostics,
+            },
+            Request::SendNotification(text) => Self::SendNotification(text),
+            Request::GetSettings => Self::GetSettings,
+            Request::UpdateCheck {
+                firmware,
+                channel,
+                board,
+                delta,
+            } => Self::UpdateCheck {
+                firmware: *firmware,
+                channel: channel.into(),
+                board,
+                delta: *delta,
+            },
+            Request::NextUpdateChunk {
+                offset,
+                max_size,
+                compression,
+                base,
+            } => Self::NextUpdateChunk {
+                offset: *offset,
+                max_size: *max_size,
+                compression: *compression,
+                base: *base,
+            },
+            Request::ReportFirmwareUpdate {
+                previous,
+                new,
+                rollback,
+                diagnostic,
+            } => Self::ReportFirmwareUpdate {
+                previous: *previous,
+                new: *new,
+                rollback: *rollback,
+                diagnostic: diagnostic.as_deref(),
+            },
+            Request::Bye => Self::Bye,
+        }
+    }
+}
+
+impl Display for InvalidField {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "invalid {}: {}", self.field, self.error)
+    }
+}
+
+impl core::error::Error for InvalidField {}
@@ -1,6 +1,13 @@
-//! Contains the definition of a response message, used to respond to requests.
+//! Contains the definition of a response message, used to respond to requests, and its borrowed counterpart.
 
-use crate::{settings::NodeSettings, version::Version};
+use crate::{
+    compression::{self, Compression},
+    manifest::SignedManifest,
+    ota,
+    protocol::{ProtocolRevision, RejectReason},
+    settings::NodeSettings,
+};
+use alloc::boxed::Box;
 use derive_more::Debug;
 use serde::{Deserialize, Serialize};
 
@@ -13,8 +20,8 @@ pub enum Response {
     /// Indicate a successfully processed request. This is usually used as a response to `Request::Post*` messages.
     Ok,
 
-    /// The server has rejected authentication. The node/client will be disconnected.
-    Reject,
+    /// The server has rejected the handshake. The node/client will be disconnected.
+    Reject(RejectReason),
 
     /// The client made an invalid request.
     InvalidRequest,
@@ -32,16 +39,38 @@ pub enum Response {
     FirmwareUpToDate,
 
     /// Firmware update available.
-    UpdateAvailable(Version),
+    ///
+    /// The node must [verify](SignedManifest::verify) the manifest before requesting any chunks.
+    /// Its size and digest allow the node to check whether the image fits before erasing any flash,
+    /// and to [verify](crate::ota::ImageVerifier) the image once it's downloaded.
+    UpdateAvailable(SignedManifest),
 
     /// Part of a firmware update.
-    UpdatePart(#[debug(skip)] Box<[u8]>),
+    UpdatePart {
+        /// Offset of the first byte of `data` within the firmware image.
+        offset: u32,
+        /// Size of the whole firmware image.
+        total_size: u32,
+        /// CRC32 checksum of the decompressed `data`, see [`ota::crc32()`].
+        crc32: u32,
+        /// Compression of `data`.
+        compression: Compression,
+        /// The image's bytes, starting at `offset`.
+        #[debug(skip)]
+        data: Box<[u8]>,
+    },
 
     /// End of firmware update chunks.
     UpdateEnd,
 
     /// Node settings.
     Settings(Option<NodeSettings>),
+
+    /// The server has accepted the handshake.
+    HandshakeAccepted {
+        /// Protocol revision that will be used for the rest of the session.
+        protocol: ProtocolRevision,
+    },
 }
 
 impl Response {
@@ -57,11 +86,169 @@ impl Response {
     pub const fn is_error(&self) -> bool {
         matches!(
             self,
-            Self::Reject
+            Self::Reject(..)
                 | Self::InvalidRequest
                 | Self::RateLimitExceeded
                 | Self::InternalServerError
                 | Self::Stalling
         )
     }
+
+    /// Create an uncompressed [`UpdatePart`](Self::UpdatePart) with the checksum of `data` computed.
+    ///
+    /// ```rust
+    /// use pwmp_msg::{ota, response::Response};
+    ///
+    /// let Response::UpdatePart { crc32, data, .. } = Response::update_part(0, 3, vec![1, 2, 3].into())
+    /// else {
+    ///     unreachable!();
+    /// };
+    /// assert_eq!(crc32, ota::crc32(&data));
+    /// ```
+    #[must_use]
+    pub fn update_part(offset: u32, total_size: u32, data: Box<[u8]>) -> Self {
+        Self::UpdatePart {
+            offset,
+            total_size,
+            crc32: ota::crc32(&data),
+            compression: Compression::None,
+            data,
+        }
+    }
+
+    /// Create an [`UpdatePart`](Self::UpdatePart) with `data` compressed using `compression`.
+    ///
+    /// If the compressed data isn't smaller, it's sent uncompressed instead.
+    ///
+    /// ```rust
+    /// use pwmp_msg::{compression::Compression, response::Response};
+    ///
+    /// let part = Response::compressed_update_part(0, 4096, &[0; 4096], Compression::Lzss);
+    /// assert!(matches!(
+    ///     part,
+    ///     Response::UpdatePart { compression: Compression::Lzss, data, .. } if data.len() < 4096
+    /// ));
+    /// ```
+    #[must_use]
+    pub fn compressed_update_part(
+        offset: u32,
+        total_size: u32,
+        data: &[u8],
+        compression: Compression,
+    ) -> Self {
+        let compressed = match compression {
+            Compression::None => None,
+            Compression::Lzss => Some(compression::compress(data)),
+        }
+        .filter(|compressed| compressed.len() < data.len());
+
+        let Some(compressed) = compressed else {
+            return Self::update_part(offset, total_size, data.into());
+        };
+
+        Self::UpdatePart {
+            offset,
+            total_size,
+            crc32: ota::crc32(data),
+            compression,
+            data: compressed.into_boxed_slice(),
+        }
+    }
+}
+
+/// Borrowed counterpart of [`Response`], which references update chunks in the buffer it was decoded from.
+///
+/// Both types have the same wire format. See [`Response`] for the meaning of each variant.
+/// Firmware manifests are rare and small, so they're still decoded into owned values.
+#[allow(missing_docs, clippy::missing_docs_in_private_items)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Hash)]
+pub enum ResponseRef<'a> {
+    Pong,
+    Ok,
+    Reject(RejectReason),
+    InvalidRequest,
+    RateLimitExceeded,
+    InternalServerError,
+    Stalling,
+    FirmwareUpToDate,
+    UpdateAvailable(SignedManifest),
+    UpdatePart {
+        offset: u32,
+        total_size: u32,
+        crc32: u32,
+        compression: Compression,
+        #[debug(skip)]
+        data: &'a [u8],
+    },
+    UpdateEnd,
+    Settings(Option<NodeSettings>),
+    HandshakeAccepted {
+        protocol: ProtocolRevision,
+    },
+}
+
+impl From<ResponseRef<'_>> for Response {
+    fn from(value: ResponseRef<'_>) -> Self {
+        match value {
+            ResponseRef::Pong => Self::Pong,
+            ResponseRef::Ok => Self::Ok,
+            ResponseRef::Reject(reason) => Self::Reject(reason),
+            ResponseRef::InvalidRequest => Self::InvalidRequest,
+            ResponseRef::RateLimitExceeded => Self::RateLimitExceeded,
+            ResponseRef::InternalServerError => Self::InternalServerError,
+            ResponseRef::Stalling => Self::Stalling,
+            ResponseRef::FirmwareUpToDate => Self::FirmwareUpToDate,
+            ResponseRef::UpdateAvailable(manifest) => Self::UpdateAvailable(manifest),
+            ResponseRef::UpdatePart {
+                offset,
+                total_size,
+                crc32,
+                compression,
+                data,
+            } => Self::UpdatePart {
+                offset,
+                total_size,
+                crc32,
+                compression,
+                data: data.into(),
+            },
+            ResponseRef::UpdateEnd => Self::UpdateEnd,
+            ResponseRef::Settings(settings) => Self::Settings(settings),
+            ResponseRef::HandshakeAccepted { protocol } => Self::HandshakeAccepted { protocol },
+        }
+    }
+}
+
+impl<'a> From<&'a Response> for ResponseRef<'a> {
+    fn from(value: &'a Response) -> Self {
+        match value {
+            Response::Pong => Self::Pong,
+            Response::Ok => Self::Ok,
+            Response::Reject(reason) => Self::Reject(*reason),
+            Response::InvalidRequest => Self::InvalidRequest,
+            Response::RateLimitExceeded => Self::RateLimitExceeded,
+            Response::InternalServerError => Self::InternalServerError,
+            Response::Stalling => Self::Stalling,
+            Response::FirmwareUpToDate => Self::FirmwareUpToDate,
+            Response::UpdateAvailable(manifest) => Self::UpdateAvailable(manifest.clone()),
+            Response::UpdatePart {
+                offset,
+                total_size,
+                crc32,
+                compression,
+                data,
+            } => Self::UpdatePart {
+                offset: *offset,
+                total_size: *total_size,
+                crc32: *crc32,
+                compression: *compression,
+                data,
+            },
+            Response::UpdateEnd => Self::UpdateEnd,
+            Response::Settings(settings) => Self::Settings(*settings),
+            Response::HandshakeAccepted { protocol } => Self::HandshakeAccepted {
+                protocol: *protocol,
+            },
+        }
+    }
 }
@@ -0,0 +1,399 @@
+//! Release channels and staged rollouts.
+//!
+//! Nodes announce the [`ReleaseChannel`] they follow and their board in every
+//! [`UpdateCheck`](crate::request::Request::UpdateCheck). The server describes each candidate
+//! firmware with a [`Release`], and uses [`Release::check()`] to decide whether it should be
+//! offered to a node.
+//!
+//! Releases can be rolled out to a percentage of the nodes first. Which nodes are included is
+//! decided by [`rollout_bucket()`], which is derived from the node's MAC address and the release
+//! version. Raising the percentage of a release therefore only ever adds nodes to it.
+//!
+//! ```rust
+//! use pwmp_msg::{
+//!     mac::Mac,
+//!     request::Request,
+//!     rollout::{Ineligible, NodeInfo, Release, ReleaseChannel},
+//!     version::Version,
+//! };
+//!
+//! let release = Release {
+//!     version: Version::new(1, 2, 0),
+//!     channel: ReleaseChannel::Beta,
+//!     board: "pws-v2".into(),
+//!     min_version: Version::new(1, 0, 0),
+//!     rollout: 100,
+//! };
+//!
+//! let check = Request::UpdateCheck {
+//!     firmware: Version::new(1, 1, 0),
+//!     channel: ReleaseChannel::Stable,
+//!     board: "pws-v2".into(),
+//!     delta: false,
+//! };
+//! let node = NodeInfo::from_update_check(Mac::new(0, 1, 2, 3, 4, 5), &check).unwrap();
+//!
+//! // Stable nodes don't receive beta releases.
+//! assert_eq!(release.check(&node), Err(Ineligible::WrongChannel));
+//! ```
+
+use crate::{mac::Mac, request::RequestRef, version::Version};
+use alloc::boxed::Box;
+use core::fmt::Display;
+use serde::{Deserialize, Deserializer, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Prefix of the hashed data, which keeps rollout buckets independent of other hashes.
+const ROLLOUT_CONTEXT: &[u8] = b"pwmp-rollout";
+
+/// A release channel followed by a node.
+///
+/// Nodes also receive releases of the channels that are more stable than their own:
+/// a node on [`Nightly`](Self::Nightly) receives beta and stable releases too.
+/// Custom channels only receive their own releases.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize)]
+pub enum ReleaseChannel {
+    /// Releases that are considered stable.
+    #[default]
+    Stable,
+
+    /// Pre-releases for test nodes.
+    Beta,
+
+    /// Automated builds of the latest development version.
+    Nightly,
+
+    /// A channel with a custom name, for example for a single deployment.
+    ///
+    /// Custom channels with the name of a built-in channel, such as `beta`, are treated as that
+    /// channel, and are decoded and converted into it.
+    Custom(Box<str>),
+}
+
+/// Borrowed counterpart of [`ReleaseChannel`].
+#[allow(missing_docs, clippy::missing_docs_in_private_items)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize)]
+pub enum ReleaseChannelRef<'a> {
+    #[default]
+    Stable,
+    Beta,
+    Nightly,
+    Custom(&'a str),
+}
+
+/// Wire format of [`ReleaseChannel`] and [`ReleaseChannelRef`], before custom names are
+/// normalized.
+#[derive(Deserialize)]
+#[serde(rename = "ReleaseChannel")]
+enum WireChannel<S> {
+    /// See [`ReleaseChannel::Stable`].
+    Stable,
+
+    /// See [`ReleaseChannel::Beta`].
+    Beta,
+
+    /// See [`ReleaseChannel::Nightly`].
+    Nightly,
+
+    /// See [`ReleaseChannel::Custom`].
+    Custom(S),
+}
+
+/// Describes a candidate firmware release.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Release {
+    /// Version of the released firmware.
+    pub version: Version,
+
+    /// Channel of the release.
+    pub channel: ReleaseChannel,
+
+    /// Board the firmware was built for.
+    pub board: Box<str>,
+
+    /// Oldest firmware version that is allowed to update to this release.
+    pub min_version: Version,
+
+    /// Percentage of the nodes that receive the release, from `0` to `100`.
+    ///
+    /// Values above `100` are treated as `100`.
+    pub rollout: u8,
+}
+
+/// Information about a node that checks for an update.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeInfo<'a> {
+    /// The node's MAC address.
+    pub mac: Mac,
+
+    /// Version of the firmware running on the node.
+    pub firmware: Version,
+
+    /// Channel followed by the node.
+    pub channel: ReleaseChannelRef<'a>,
+
+    /// The node's board.
+    pub board: &'a str,
+}
+
+/// Reasons why a release is not offered to a node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Ineligible {
+    /// The node doesn't follow the channel of the release.
+    WrongChannel,
+
+    /// The release was built for a different board.
+    WrongBoard,
+
+    /// The node already runs this release, or a newer one.
+    UpToDate,
+
+    /// The node's firmware is too old to update to this release.
+    UnsupportedVersion {
+        /// Oldest firmware version that is allowed to update.
+        min_version: Version,
+    },
+
+    /// The node is not part of the staged rollout yet.
+    NotInRollout,
+}
+
+impl<'a> ReleaseChannelRef<'a> {
+    /// Parse a channel name.
+    ///
+    /// Names other than `stable`, `beta` and `nightly` are custom channels.
+    ///
+    /// ```rust
+    /// use pwmp_msg::rollout::ReleaseChannelRef;
+    ///
+    /// assert_eq!(ReleaseChannelRef::from_name("beta"), ReleaseChannelRef::Beta);
+    /// assert_eq!(
+    ///     ReleaseChannelRef::from_name("greenhouse"),
+    ///     ReleaseChannelRef::Custom("greenhouse")
+    /// );
+    /// ```
+    #[must_use]
+    pub fn from_name(name: &'a str) -> Self {
+        match name {
+            "stable" => Self::Stable,
+            "beta" => Self::Beta,
+            "nightly" => Self::Nightly,
+            _ => Self::Custom(name),
+        }
+    }
+
+    /// Returns the name of the channel.
+    #[must_use]
+    pub const fn name(&self) -> &'a str {
+        match self {
+            Self::Stable => "stable",
+            Self::Beta => "beta",
+            Self::Nightly => "nightly",
+            Self::Custom(name) => name,
+        }
+    }
+
+    /// Returns whether a node following this channel receives releases of the `release` channel.
+    ///
+    /// ```rust
+    /// use pwmp_msg::rollout::ReleaseChannelRef;
+    ///
+    /// assert!(ReleaseChannelRef::Beta.receives(ReleaseChannelRef::Stable));
+    /// assert!(!ReleaseChannelRef::Stable.receives(ReleaseChannelRef::Beta));
+    /// assert!(!ReleaseChannelRef::Custom("lab").receives(ReleaseChannelRef::Stable));
+    /// ```
+    #[must_use]
+    pub fn receives(&self, release: ReleaseChannelRef<'_>) -> bool {
+        match (self.rank(), release.rank()) {
+            (Some(node), Some(release)) => release <= node,
+            (None, None) => self.name() == release.name(),
+            _ => false,
+        }
+    }
+
+    /// Returns the position of a built-in channel, ordered from the most stable one.
+    ///
+    /// Custom channels with the name of a built-in channel have its position, too.
+    fn rank(&self) -> Option<u8> {
+        match self.name() {
+            "stable" => Some(0),
+            "beta" => Some(1),
+            "nightly" => Some(2),
+            _ => None,
+        }
+    }
+}
+
+impl Release {
+    /// Decide whether this release should be offered to the node.
+    ///
+    /// # Errors
+    /// Returns the first reason why the node must not receive this release.
+    pub fn check(&self, node: &NodeInfo<'_>) -> Result<(), Ineligible> {
+        if !node.channel.receives((&self.channel).into()) {
+            return Err(Ineligible::WrongChannel);
+        }
+
+        if *self.board != *node.board {
+            return Err(Ineligible::WrongBoard);
+        }
+
+        if node.firmware >= self.version {
+            return Err(Ineligible::UpToDate);
+        }
+
+        if node.firmware < self.min_version {
+            return Err(Ineligible::UnsupportedVersion {
+                min_version: self.min_version,
+            });
+        }
+
+        if rollout_bucket(node.mac, self.version) >= self.rollout {
+            return Err(Ineligible::NotInRollout);
+        }
+
+        Ok(())
+    }
+
+    /// Returns whether this release should be offered to the node. See [`check()`](Self::check).
+    #[must_use]
+    pub fn applies_to(&self, node: &NodeInfo<'_>) -> bool {
+        self.check(node).is_ok()
+    }
+}
+
+impl<'a> NodeInfo<'a> {
+    /// Collect the information from an [`UpdateCheck`](crate::request::Request::UpdateCheck)
+    /// sent by the node with the given MAC address.
+    ///
+    /// Returns [`None`] for other requests.
+    #[must_use]
+    pub fn from_update_check(mac: Mac, request: impl Into<RequestRef<'a>>) -> Option<Self> {
+        let RequestRef::UpdateCheck {
+            firmware,
+            channel,
+            board,
+            ..
+        } = request.into()
+        else {
+            return None;
+        };
+
+        Some(Self {
+            mac,
+            firmware,
+            channel,
+            board,
+        })
+    }
+}
+
+/// Returns the rollout bucket of a node for a release, from `0` to `99`.
+///
+/// A node receives a release that is rolled out to `n` percent of the nodes if its bucket is
+/// less than `n`. Buckets are derived from the MAC address and the release version, so
+/// different releases are rolled out to different nodes first.
+///
+/// ```rust
+/// use pwmp_msg::{mac::Mac, rollout::rollout_bucket, version::Version};
+///
+/// let mac = Mac::new(0, 1, 2, 3, 4, 5);
+/// let version = Version::new(1, 2, 0);
+///
+/// assert!(rollout_bucket(mac, version) < 100);
+/// assert_eq!(rollout_bucket(mac, version), rollout_bucket(mac, version));
+/// ```
+#[must_use]
+#[allow(clippy::cast_possible_truncation)] // The remainder is less than 100.
+pub fn rollout_bucket(mac: Mac, version: Version) -> u8 {
+    let octets: [u8; 6] = mac.into();
+    let (major, middle, minor) = version.into();
+
+    let digest = Sha256::new()
+        .chain_update(ROLLOUT_CONTEXT)
+        .chain_update(octets)
+        .chain_update([major, middle, minor])
+        .finalize();
+    let value = u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]);
+
+    (value % 100) as u8
+}
+
+impl<'a> From<&'a ReleaseChannel> for ReleaseChannelRef<'a> {
+    fn from(value: &'a ReleaseChannel) -> Self {
+        match value {
+            ReleaseChannel::Stable => Self::Stable,
+            ReleaseChannel::Beta => Self::Beta,
+            ReleaseChannel::Nightly => Self::Nightly,
+            ReleaseChannel::Custom(name) => Self::from_name(name),
+        }
+    }
+}
+
+impl From<ReleaseChannelRef<'_>> for ReleaseChannel {
+    fn from(value: ReleaseChannelRef<'_>) -> Self {
+        match value {
+            ReleaseChannelRef::Stable => Self::Stable,
+            ReleaseChannelRef::Beta => Self::Beta,
+            ReleaseChannelRef::Nightly => Self::Nightly,
+            ReleaseChannelRef::Custom(name) => match ReleaseChannelRef::from_name(name) {
+                ReleaseChannelRef::Custom(name) => Self::Custom(name.into()),
+                channel => channel.into(),
+            },
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ReleaseChannel {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match WireChannel::<Box<str>>::deserialize(deserializer)? {
+            WireChannel::Stable => Self::Stable,
+            WireChannel::Beta => Self::Beta,
+            WireChannel::Nightly => Self::Nightly,
+            WireChannel::Custom(name) => match ReleaseChannelRef::from_name(&name) {
+                ReleaseChannelRef::Custom(..) => Self::Custom(name),
+                channel => channel.into(),
+            },
+        })
+    }
+}
+
+impl<'de: 'a, 'a> Deserialize<'de> for ReleaseChannelRef<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match WireChannel::<&'a str>::deserialize(deserializer)? {
+            WireChannel::Stable => Self::Stable,
+            WireChannel::Beta => Self::Beta,
+            WireChannel::Nightly => Self::Nightly,
+            WireChannel::Custom(name) => Self::from_name(name),
+        })
+    }
+}
+
+impl Display for ReleaseChannel {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        ReleaseChannelRef::from(self).fmt(f)
+    }
+}
+
+impl Display for ReleaseChannelRef<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl Display for Ineligible {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::WrongChannel => write!(f, "node doesn't follow the release channel"),
+            Self::WrongBoard => write!(f, "release was built for a different board"),
+            Self::UpToDate => write!(f, "node is already up to date"),
+            Self::UnsupportedVersion { min_version } => write!(
+                f,
+                "node firmware is older than the minimum version {min_version}"
+            ),
+            Self::NotInRollout => write!(f, "node is not part of the staged rollout"),
+        }
+    }
+}
+
+impl core::error::Error for Ineligible {}
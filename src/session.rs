@@ -0,0 +1,344 @@
+//! Sans-IO state machines that enforce the rules of a PWMP session.
+//!
+//! A session follows these rules:
+//! - The first request must be a [`Handshake`](Request::Handshake), and only one is allowed.
+//! - Requests and responses strictly alternate. A new request may only be sent once the
+//!   previous one has been [answered](Request::accepts).
+//! - Only **one** [`PostResults`](Request::PostResults) or
+//!   [`PostMeasurements`](Request::PostMeasurements) request is allowed once the server has
+//!   answered one with [`Ok`](Response::Ok). Until then, rejected results may be corrected and
+//!   posted again. Buffered measurements
+//!   may be posted with any number of [`PostMeasurementBatch`](Request::PostMeasurementBatch)
+//!   requests.
+//! - [`NextUpdateChunk`](Request::NextUpdateChunk) is only allowed after the server has
+//!   answered an [`UpdateCheck`](Request::UpdateCheck) with
+//!   [`UpdateAvailable`](Response::UpdateAvailable), and until it has sent
+//!   [`UpdateEnd`](Response::UpdateEnd).
+//! - The session ends with [`Bye`](Request::Bye), or when the server sends
+//!   [`Reject`](Response::Reject) or [`Stalling`](Response::Stalling).
+//!   No more messages are allowed afterwards.
+//!
+//! The [`ServerSession`] and [`NodeSession`] types implement the same rules from both
+//! perspectives. They don't perform any I/O, they're only fed with the messages that
+//! are sent and received.
+
+use crate::{request::Request, response::Response};
+use core::fmt::Display;
+
+/// Tracks a session on the server's side.
+///
+/// ```rust
+/// use pwmp_msg::{
+///     mac::Mac,
+///     protocol::PROTOCOL_REVISION,
+///     request::Request,
+///     response::Response,
+///     session::{ProtocolViolation, ServerSession},
+///     version::Version,
+/// };
+///
+/// let mut session = ServerSession::new();
+///
+/// assert_eq!(
+///     session.receive_request(&Request::GetSettings),
+///     Err(ProtocolViolation::NotAuthenticated)
+/// );
+///
+/// let handshake = Request::Handshake {
+///     mac: Mac::new(0, 1, 2, 3, 4, 5),
+///     protocol: PROTOCOL_REVISION,
+///     firmware: Version::new(1, 0, 0),
+/// };
+/// session.receive_request(&handshake).unwrap();
+/// session
+///     .send_response(&Response::HandshakeAccepted { protocol: PROTOCOL_REVISION })
+///     .unwrap();
+///
+/// session.receive_request(&Request::Bye).unwrap();
+/// assert!(session.is_closed());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ServerSession(Session);
+
+/// Tracks a session on the node's side.
+///
+/// ```rust
+/// use pwmp_msg::{
+///     request::Request,
+///     response::Response,
+///     rollout::ReleaseChannel,
+///     session::{NodeSession, ProtocolViolation},
+///     version::Version,
+/// };
+///
+/// let mut session = NodeSession::new();
+/// let update_check = Request::UpdateCheck {
+///     firmware: Version::new(1, 0, 0),
+///     channel: ReleaseChannel::Stable,
+///     board: "pws-v2".into(),
+///     delta: false,
+/// };
+///
+/// // Must not be sent before the handshake.
+/// assert_eq!(
+///     session.send_request(&update_check),
+///     Err(ProtocolViolation::NotAuthenticated)
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct NodeSession(Session);
+
+/// A message that is not allowed in the current state of the session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProtocolViolation {
+    /// A request other than [`Handshake`](Request::Handshake) was sent before the handshake was accepted.
+    NotAuthenticated,
+
+    /// A second [`Handshake`](Request::Handshake) was sent.
+    DuplicateHandshake,
+
+    /// A second [`PostResults`](Request::PostResults) or
+    /// [`PostMeasurements`](Request::PostMeasurements) was sent after the server has accepted
+    /// the first one.
+    DuplicateResults,
+
+    /// A chunk of a firmware update was requested without an update being available.
+    UpdateNotAvailable,
+
+    /// A request was sent before the previous one was answered.
+    RequestPending,
+
+    /// A response was sent although no request is waiting for one.
+    UnexpectedResponse,
+
+    /// The response is not a valid answer to the pending request.
+    InvalidResponse,
+
+    /// A message was sent after the session has ended.
+    SessionClosed,
+}
+
+/// State shared by both perspectives of a session.
+#[derive(Debug, Clone, Default)]
+struct Session {
+    /// Current phase of the session.
+    phase: Phase,
+
+    /// The request that is waiting for a response.
+    pending: Option<Request>,
+
+    /// Whether the server has accepted the node's results.
+    results_posted: bool,
+
+    /// Progress of the firmware update.
+    update: UpdateState,
+}
+
+/// Phase of a session.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Phase {
+    /// The handshake hasn't been accepted yet.
+    #[default]
+    Unauthenticated,
+
+    /// The handshake was accepted.
+    Established,
+
+    /// The session has ended.
+    Closed,
+}
+
+/// Progress of a firmware update within a session.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum UpdateState {
+    /// No update is being transferred.
+    #[default]
+    Idle,
+
+    /// The server has announced an update, chunks may be requested.
+    Available,
+}
+
+impl ServerSession {
+    /// Create a new session for a node that has just connected.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check and record a request received from the node.
+    ///
+    /// # Errors
+    /// Returns the violated rule if the request is not allowed at this point.
+    /// The state of the session is not changed in this case.
+    pub fn receive_request(&mut self, request: &Request) -> Result<(), ProtocolViolation> {
+        self.0.request(request)
+    }
+
+    /// Check and record a response that is about to be sent to the node.
+    ///
+    /// # Errors
+    /// Returns the violated rule if the response is not allowed at this point.
+    /// The state of the session is not changed in this case.
+    pub fn send_response(&mut self, response: &Response) -> Result<(), ProtocolViolation> {
+        self.0.response(response)
+    }
+
+    /// Returns whether the handshake has been accepted and the session is still open.
+    #[must_use]
+    pub const fn is_established(&self) -> bool {
+        matches!(self.0.phase, Phase::Established)
+    }
+
+    /// Returns whether the session has ended.
+    #[must_use]
+    pub const fn is_closed(&self) -> bool {
+        matches!(self.0.phase, Phase::Closed)
+    }
+
+    /// Returns the request that is waiting for a response, if any.
+    #[must_use]
+    pub const fn pending(&self) -> Option<&Request> {
+        self.0.pending.as_ref()
+    }
+}
+
+impl NodeSession {
+    /// Create a new session for a connection that has just been established.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check and record a request that is about to be sent to the server.
+    ///
+    /// # Errors
+    /// Returns the violated rule if the request is not allowed at this point.
+    /// The state of the session is not changed in this case.
+    pub fn send_request(&mut self, request: &Request) -> Result<(), ProtocolViolation> {
+        self.0.request(request)
+    }
+
+    /// Check and record a response received from the server.
+    ///
+    /// # Errors
+    /// Returns the violated rule if the response is not allowed at this point.
+    /// The state of the session is not changed in this case.
+    pub fn receive_response(&mut self, response: &Response) -> Result<(), ProtocolViolation> {
+        self.0.response(response)
+    }
+
+    /// Returns whether the handshake has been accepted and the session is still open.
+    #[must_use]
+    pub const fn is_established(&self) -> bool {
+        matches!(self.0.phase, Phase::Established)
+    }
+
+    /// Returns whether the session has ended.
+    #[must_use]
+    pub const fn is_closed(&self) -> bool {
+        matches!(self.0.phase, Phase::Closed)
+    }
+
+    /// Returns the request that is waiting for a response, if any.
+    #[must_use]
+    pub const fn pending(&self) -> Option<&Request> {
+        self.0.pending.as_ref()
+    }
+}
+
+impl Session {
+    /// Check and record a request.
+    fn request(&mut self, request: &Request) -> Result<(), ProtocolViolation> {
+        if self.phase == Phase::Closed {
+            return Err(ProtocolViolation::SessionClosed);
+        }
+
+        if self.pending.is_some() {
+            return Err(ProtocolViolation::RequestPending);
+        }
+
+        match (self.phase, request) {
+            (Phase::Unauthenticated, Request::Handshake { .. }) => (),
+            (Phase::Unauthenticated, _) => return Err(ProtocolViolation::NotAuthenticated),
+            (_, Request::Handshake { .. }) => return Err(ProtocolViolation::DuplicateHandshake),
+            (_, Request::PostResults { .. } | Request::PostMeasurements(..))
+                if self.results_posted =>
+            {
+                return Err(ProtocolViolation::DuplicateResults);
+            }
+            (_, Request::NextUpdateChunk { .. }) if self.update != UpdateState::Available => {
+                return Err(ProtocolViolation::UpdateNotAvailable);
+            }
+            _ => (),
+        }
+
+        match request {
+            Request::UpdateCheck { .. } => self.update = UpdateState::Idle,
+            Request::Bye => self.phase = Phase::Closed,
+            _ => (),
+        }
+
+        if request.expects_response() {
+            self.pending = Some(request.clone());
+        }
+
+        Ok(())
+    }
+
+    /// Check and record a response.
+    fn response(&mut self, response: &Response) -> Result<(), ProtocolViolation> {
+        if self.phase == Phase::Closed {
+            return Err(ProtocolViolation::SessionClosed);
+        }
+
+        let Some(request) = &self.pending else {
+            return Err(ProtocolViolation::UnexpectedResponse);
+        };
+
+        if !request.accepts(response) {
+            return Err(ProtocolViolation::InvalidResponse);
+        }
+
+        // Rejected results may be corrected and posted again.
+        if matches!(
+            (request, response),
+            (
+                Request::PostResults { .. } | Request::PostMeasurements(..),
+                Response::Ok
+            )
+        ) {
+            self.results_posted = true;
+        }
+
+        match response {
+            Response::HandshakeAccepted { .. } => self.phase = Phase::Established,
+            Response::Reject(..) | Response::Stalling => self.phase = Phase::Closed,
+            Response::UpdateAvailable(..) => self.update = UpdateState::Available,
+            Response::UpdateEnd => self.update = UpdateState::Idle,
+            _ => (),
+        }
+
+        self.pending = None;
+        Ok(())
+    }
+}
+
+impl Display for ProtocolViolation {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let description = match self {
+            Self::NotAuthenticated => "request sent before the handshake",
+            Self::DuplicateHandshake => "duplicate handshake",
+            Self::DuplicateResults => "duplicate measurement results",
+            Self::UpdateNotAvailable => "update chunk requested without an available update",
+            Self::RequestPending => "request sent before the previous one was answered",
+            Self::UnexpectedResponse => "response sent without a pending request",
+            Self::InvalidResponse => "response does not answer the pending request",
+            Self::SessionClosed => "message sent after the session has ended",
+        };
+
+        write!(f, "{description}")
+    }
+}
+
+impl core::error::Error for ProtocolViolation {}
@@ -1,13 +1,15 @@
 //! Settings type for representing individual node settings.
 
+use core::time::Duration;
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
 
 /// Settings of a particular node.
 #[allow(clippy::struct_excessive_bools)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct NodeSettings {
     /// Whether to ignore low battery voltage.
+    ///
+    /// See [`BatteryProfile::should_warn()`](crate::battery::BatteryProfile::should_warn).
     pub battery_ignore: bool,
 
     /// Whether Over-the-Air (remote) updates should be allowed.
@@ -17,6 +19,8 @@ pub struct NodeSettings {
     pub sleep_time: u16,
 
     /// Whether to enable software-based battery over-discharge protection.
+    ///
+    /// See [`BatteryProfile::should_protect()`](crate::battery::BatteryProfile::should_protect).
     pub sbop: bool,
 
     /// Whether the node is allowed to send notifications.
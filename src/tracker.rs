@@ -0,0 +1,224 @@
+//! Tracking of requests that are waiting for a response.
+//!
+//! The tracker is sans-IO: it doesn't read any clock by itself. Instead, every method that
+//! needs the current time takes it as a [`Duration`] measured from an arbitrary, but fixed
+//! point in time *(for example the start of the program)*.
+
+use crate::{request::Request, Message, MsgId};
+use alloc::vec::Vec;
+use core::{fmt::Display, time::Duration};
+
+/// Keeps track of sent requests and matches incoming responses to them.
+///
+/// ```rust
+/// use pwmp_msg::{request::Request, response::Response, tracker::PendingRequests, Message};
+/// use std::time::Duration;
+///
+/// let mut pending = PendingRequests::new(Duration::from_secs(5));
+/// let now = Duration::ZERO;
+///
+/// pending.register(&Message::new_request(Request::Ping, 1), now).unwrap();
+///
+/// let response = Message::new_reply(Response::Pong, 100, 1);
+/// assert_eq!(pending.resolve(&response), Ok(Request::Ping));
+/// assert!(pending.is_empty());
+/// ```
+#[derive(Debug, Clone)]
+pub struct PendingRequests {
+    /// How long to wait for a response.
+    timeout: Duration,
+
+    /// Requests waiting for a response, ordered from the oldest one.
+    entries: Vec<Pending>,
+}
+
+/// Errors that can occur while tracking requests.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrackError {
+    /// A response was passed where a request was expected.
+    NotARequest,
+
+    /// A request was passed where a response was expected.
+    NotAResponse,
+
+    /// A request with the same ID is already waiting for a response.
+    DuplicateId(MsgId),
+
+    /// The response references a request that is not waiting for a response.
+    UnknownRequest(MsgId),
+
+    /// A response was received while no request is waiting for one.
+    NoPendingRequest,
+
+    /// The response is not a valid answer to the request it references.
+    ///
+    /// The request is no longer tracked after this error.
+    InvalidResponse {
+        /// The request that was answered with an invalid response.
+        request: Request,
+    },
+}
+
+/// A request that is waiting for a response.
+#[derive(Debug, Clone)]
+struct Pending {
+    /// ID of the request's message.
+    id: MsgId,
+
+    /// The request itself.
+    request: Request,
+
+    /// Point in time after which the request is considered to have timed out.
+    deadline: Duration,
+}
+
+impl PendingRequests {
+    /// Create a new tracker that waits `timeout` for every response.
+    #[must_use]
+    pub const fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Returns how long the tracker waits for a response.
+    #[must_use]
+    pub const fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Returns the number of requests waiting for a response.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether no request is waiting for a response.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns whether the request with the given ID is waiting for a response.
+    #[must_use]
+    pub fn contains(&self, id: MsgId) -> bool {
+        self.entries.iter().any(|entry| entry.id == id)
+    }
+
+    /// Start tracking a request that was sent at `now`.
+    ///
+    /// Requests that [don't expect a response](Request::expects_response) are ignored.
+    ///
+    /// # Errors
+    /// Returns [`TrackError::NotARequest`] if the message contains a response, and
+    /// [`TrackError::DuplicateId`] if a request with the same ID is already being tracked.
+    pub fn register(&mut self, message: &Message, now: Duration) -> Result<(), TrackError> {
+        let request = message.request().ok_or(TrackError::NotARequest)?;
+
+        if self.contains(message.id()) {
+            return Err(TrackError::DuplicateId(message.id()));
+        }
+
+        if request.expects_response() {
+            self.entries.push(Pending {
+                id: message.id(),
+                request: request.clone(),
+                deadline: now.saturating_add(self.timeout),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Match a received response to the request it answers, and stop tracking that request.
+    ///
+    /// The request is looked up using the response's [`reply_to()`](Message::reply_to) ID.
+    /// If the response does not reference any request, it's assumed to answer the oldest one,
+    /// since requests and responses alternate within a session.
+    ///
+    /// Returns the answered request.
+    ///
+    /// # Errors
+    /// Returns [`TrackError::NotAResponse`] if the message contains a request,
+    /// [`TrackError::UnknownRequest`] or [`TrackError::NoPendingRequest`] if no matching request is
+    /// being tracked, and [`TrackError::InvalidResponse`] if the response is not a valid answer.
+    pub fn resolve(&mut self, message: &Message) -> Result<Request, TrackError> {
+        let response = message.response().ok_or(TrackError::NotAResponse)?;
+
+        let index = match message.reply_to() {
+            Some(id) => self
+                .entries
+                .iter()
+                .position(|entry| entry.id == id)
+                .ok_or(TrackError::UnknownRequest(id))?,
+            None if self.entries.is_empty() => return Err(TrackError::NoPendingRequest),
+            None => 0,
+        };
+
+        let Pending { request, .. } = self.entries.remove(index);
+        if !request.accepts(response) {
+            return Err(TrackError::InvalidResponse { request });
+        }
+
+        Ok(request)
+    }
+
+    /// Stop tracking all requests whose timeout has elapsed at `now`, and return them
+    /// together with their IDs.
+    ///
+    /// ```rust
+    /// use pwmp_msg::{request::Request, tracker::PendingRequests, Message};
+    /// use std::time::Duration;
+    ///
+    /// let mut pending = PendingRequests::new(Duration::from_secs(5));
+    /// pending.register(&Message::new_request(Request::Ping, 1), Duration::ZERO).unwrap();
+    ///
+    /// assert!(pending.expire(Duration::from_secs(5)).is_empty());
+    /// assert_eq!(pending.expire(Duration::from_secs(6)), vec![(1, Request::Ping)]);
+    /// ```
+    pub fn expire(&mut self, now: Duration) -> Vec<(MsgId, Request)> {
+        let mut expired = Vec::new();
+
+        self.entries.retain(|entry| {
+            if entry.deadline < now {
+                expired.push((entry.id, entry.request.clone()));
+                false
+            } else {
+                true
+            }
+        });
+
+        expired
+    }
+
+    /// Returns the earliest point in time at which a request times out, if any are tracked.
+    ///
+    /// This can be used to decide how long to wait for incoming data.
+    #[must_use]
+    pub fn next_deadline(&self) -> Option<Duration> {
+        self.entries.iter().map(|entry| entry.deadline).min()
+    }
+
+    /// Stop tracking all requests.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+impl Display for TrackError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::NotARequest => write!(f, "message does not contain a request"),
+            Self::NotAResponse => write!(f, "message does not contain a response"),
+            Self::DuplicateId(id) => write!(f, "request with ID {id} is already pending"),
+            Self::UnknownRequest(id) => write!(f, "no pending request with ID {id}"),
+            Self::NoPendingRequest => write!(f, "no request is waiting for a response"),
+            Self::InvalidResponse { request } => {
+                write!(f, "invalid response to request {request:?}")
+            }
+        }
+    }
+}
+
+impl core::error::Error for TrackError {}
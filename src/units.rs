@@ -0,0 +1,170 @@
+//! Unit conversions and derived meteorological quantities.
+//!
+//! The conversions extend the types of the [`aliases`](crate::aliases) module, which are used
+//! by [`PostResults`](crate::request::Request::PostResults), and are also available for plain
+//! values, such as the values of [readings](crate::measurement::Reading). Derived quantities are
+//! computed from the measured values in *°C* and *hPa*, like the values of the protocol.
+//!
+//! This module requires the `std` feature, since the floating-point functions it uses are not
+//! available in `core`.
+//!
+//! ```rust
+//! use pwmp_msg::{
+//!     aliases::{Humidity, Temperature},
+//!     units::dew_point,
+//! };
+//!
+//! let temperature = Temperature::from_centi_celsius(2000);
+//! let humidity = Humidity::new(50).unwrap();
+//!
+//! assert_eq!(temperature.fahrenheit(), 68.0);
+//! assert!((dew_point(temperature, humidity).unwrap() - 9.26).abs() < 0.01);
+//! ```
+
+use crate::aliases::{AirPressure, Humidity, Temperature};
+
+/// Hecto-Pascals per inch of mercury.
+const HPA_PER_INHG: f32 = 33.863_89;
+
+/// Hecto-Pascals per millimetre of mercury.
+const HPA_PER_MMHG: f32 = 1.333_224;
+
+/// Coefficient of the Magnus formula over water *(Sonntag, 1990)*.
+const MAGNUS_B: f32 = 17.62;
+
+/// Temperature coefficient of the Magnus formula over water in *°C* *(Sonntag, 1990)*.
+const MAGNUS_C: f32 = 243.12;
+
+/// Saturation vapour pressure at 0 °C in *hPa*.
+const MAGNUS_E0: f32 = 6.112;
+
+/// Specific gas constant of water vapour in *J/(kg·K)*.
+const WATER_VAPOUR_GAS_CONSTANT: f32 = 461.5;
+
+/// Offset between *°C* and *K*.
+const ZERO_CELSIUS: f32 = 273.15;
+
+/// Temperature lapse rate of the standard atmosphere in *K/m*.
+const LAPSE_RATE: f32 = 0.0065;
+
+/// Sea-level temperature of the standard atmosphere in *K*.
+const SEA_LEVEL_TEMPERATURE: f32 = 288.15;
+
+/// Exponent of the barometric formula, `g·M / (R·L)` of the standard atmosphere.
+const BAROMETRIC_EXPONENT: f32 = 5.255_88;
+
+impl Temperature {
+    /// Returns the temperature in degrees Fahrenheit.
+    #[must_use]
+    pub fn fahrenheit(self) -> f32 {
+        celsius_to_fahrenheit(self.celsius())
+    }
+}
+
+impl AirPressure {
+    /// Returns the air pressure in inches of mercury (*inHg*).
+    #[must_use]
+    pub fn inches_of_mercury(self) -> f32 {
+        hpa_to_inhg(self.into())
+    }
+
+    /// Returns the air pressure in millimetres of mercury (*mmHg*).
+    #[must_use]
+    pub fn millimeters_of_mercury(self) -> f32 {
+        hpa_to_mmhg(self.into())
+    }
+}
+
+/// Convert degrees Celsius to degrees Fahrenheit.
+#[must_use]
+pub const fn celsius_to_fahrenheit(celsius: f32) -> f32 {
+    celsius * 1.8 + 32.0
+}
+
+/// Convert degrees Fahrenheit to degrees Celsius.
+#[must_use]
+pub const fn fahrenheit_to_celsius(fahrenheit: f32) -> f32 {
+    (fahrenheit - 32.0) / 1.8
+}
+
+/// Convert hecto-Pascals to inches of mercury.
+#[must_use]
+pub const fn hpa_to_inhg(hpa: f32) -> f32 {
+    hpa / HPA_PER_INHG
+}
+
+/// Convert hecto-Pascals to millimetres of mercury.
+#[must_use]
+pub const fn hpa_to_mmhg(hpa: f32) -> f32 {
+    hpa / HPA_PER_MMHG
+}
+
+/// Returns the dew point in *°C*, using the Magnus formula.
+///
+/// Returns [`None`] at a humidity of 0 %, where there is no dew point.
+#[must_use]
+pub fn dew_point(temperature: Temperature, humidity: Humidity) -> Option<f32> {
+    if humidity.percent() == 0 {
+        return None;
+    }
+
+    let celsius = temperature.celsius();
+    let gamma = (f32::from(humidity) / 100.0).ln() + MAGNUS_B * celsius / (MAGNUS_C + celsius);
+
+    Some(MAGNUS_C * gamma / (MAGNUS_B - gamma))
+}
+
+/// Returns the heat index *("feels like" temperature)* in *°C*, using the algorithm of the
+/// US National Weather Service.
+///
+/// The heat index is meant for warm weather. In cool weather, it's close to the temperature.
+#[must_use]
+pub fn heat_index(temperature: Temperature, humidity: Humidity) -> f32 {
+    let t = temperature.fahrenheit();
+    let rh = f32::from(humidity);
+
+    // Steadman's simple formula is accurate enough below 80 °F.
+    let simple = 0.5 * (t + 61.0 + (t - 68.0) * 1.2 + rh * 0.094);
+    if (simple + t) / 2.0 < 80.0 {
+        return fahrenheit_to_celsius(simple);
+    }
+
+    // The Rothfusz regression.
+    let mut index = -42.379 + 2.049_015_2 * t + 10.143_331 * rh
+        - 0.224_755_4 * t * rh
+        - 6.837_83e-3 * t * t
+        - 5.481_717e-2 * rh * rh
+        + 1.228_74e-3 * t * t * rh
+        + 8.528_2e-4 * t * rh * rh
+        - 1.99e-6 * t * t * rh * rh;
+
+    if rh < 13.0 && (80.0..=112.0).contains(&t) {
+        index -= (13.0 - rh) / 4.0 * ((17.0 - (t - 95.0).abs()) / 17.0).sqrt();
+    } else if rh > 85.0 && (80.0..=87.0).contains(&t) {
+        index += (rh - 85.0) / 10.0 * (87.0 - t) / 5.0;
+    }
+
+    fahrenheit_to_celsius(index)
+}
+
+/// Returns the absolute humidity *(mass of water vapour per volume of air)* in *g/m³*.
+#[must_use]
+pub fn absolute_humidity(temperature: Temperature, humidity: Humidity) -> f32 {
+    let celsius = temperature.celsius();
+    let saturation = MAGNUS_E0 * (MAGNUS_B * celsius / (MAGNUS_C + celsius)).exp();
+    let vapour_pressure = saturation * f32::from(humidity) / 100.0;
+
+    // Pa / (J/(kg·K) · K) = kg/m³
+    vapour_pressure * 100.0 / (WATER_VAPOUR_GAS_CONSTANT * (celsius + ZERO_CELSIUS)) * 1000.0
+}
+
+/// Returns the pressure reduced to sea level in *hPa*, given the pressure measured at the node
+/// and the node's altitude above sea level in metres.
+///
+/// Uses the barometric formula of the standard atmosphere, which is valid below 11 km.
+#[must_use]
+pub fn sea_level_pressure(station: AirPressure, altitude: f32) -> f32 {
+    let ratio = 1.0 - LAPSE_RATE * altitude / SEA_LEVEL_TEMPERATURE;
+
+    f32::from(station) / ratio.powf(BAROMETRIC_EXPONENT)
+}
@@ -1,11 +1,22 @@
 //! Contains the definition of a simple version structure for storing
 //! and representing semantic version numbers (`X.Y.Z`).
 
+use core::{fmt::Display, str::FromStr};
 use serde::{Deserialize, Serialize};
-use std::{fmt::Display, str::FromStr};
 
 /// A structure that represents a semantic version (eg. `1.0.0`) with a major part (`1`), middle part (`0`) and a minor part (`0`).
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+///
+/// Versions are ordered by their major, middle and minor number, in this order.
+///
+/// ```rust
+/// use pwmp_msg::version::Version;
+///
+/// assert!(Version::new(1, 2, 6) < Version::new(1, 3, 0));
+/// assert!(Version::new(2, 0, 0) > Version::new(1, 9, 9));
+/// ```
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize,
+)]
 pub struct Version {
     /// Major number.
     major: u8,
@@ -140,7 +151,7 @@ impl From<(u8, u8, u8)> for Version {
 }
 
 impl Display for Version {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "{}.{}.{}", self.major, self.middle, self.minor)
     }
 }
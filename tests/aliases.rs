@@ -0,0 +1,206 @@
+use pwmp_msg::{
+    aliases::{AirPressure, BatteryVoltage, Humidity, Rssi, Temperature, ValueError},
+    diagnostics::Diagnostics,
+    error::DecodeError,
+    request::Request,
+    response::Response,
+    Message,
+};
+
+#[test]
+fn temperature_roundtrips_losslessly() {
+    for centi_celsius in i16::MIN..=i16::MAX {
+        let temperature = Temperature::from_centi_celsius(centi_celsius);
+
+        assert_eq!(
+            Temperature::try_from(f32::from(temperature)),
+            Ok(temperature)
+        );
+    }
+}
+
+#[test]
+fn battery_voltage_roundtrips_losslessly() {
+    for millivolts in u16::MIN..=u16::MAX {
+        let voltage = BatteryVoltage::from_millivolts(millivolts);
+
+        assert_eq!(BatteryVoltage::try_from(f32::from(voltage)), Ok(voltage));
+    }
+}
+
+#[test]
+fn rounds_to_nearest_step() {
+    assert_eq!(Temperature::try_from(21.234).unwrap().centi_celsius(), 2123);
+    assert_eq!(Temperature::try_from(21.236).unwrap().centi_celsius(), 2124);
+    assert_eq!(Temperature::try_from(-0.004).unwrap().centi_celsius(), 0);
+    assert_eq!(Temperature::try_from(-5.555).unwrap().centi_celsius(), -556);
+    assert_eq!(BatteryVoltage::try_from(3.7004).unwrap().millivolts(), 3700);
+    assert_eq!(BatteryVoltage::try_from(-0.0004).unwrap().millivolts(), 0);
+}
+
+#[test]
+fn rejects_invalid_values() {
+    for value in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+        assert_eq!(Temperature::try_from(value), Err(ValueError::NotFinite));
+        assert_eq!(BatteryVoltage::try_from(value), Err(ValueError::NotFinite));
+    }
+
+    assert_eq!(
+        Temperature::try_from(327.67).unwrap().centi_celsius(),
+        i16::MAX
+    );
+    assert_eq!(
+        Temperature::try_from(-327.68).unwrap().centi_celsius(),
+        i16::MIN
+    );
+    assert_eq!(Temperature::try_from(327.68), Err(ValueError::OutOfRange));
+    assert_eq!(Temperature::try_from(-400.0), Err(ValueError::OutOfRange));
+    assert_eq!(Temperature::try_from(f32::MAX), Err(ValueError::OutOfRange));
+    assert_eq!(
+        BatteryVoltage::try_from(65.536),
+        Err(ValueError::OutOfRange)
+    );
+    assert_eq!(
+        BatteryVoltage::try_from(-0.001),
+        Err(ValueError::OutOfRange)
+    );
+}
+
+#[test]
+fn checks_physical_ranges() {
+    assert_eq!(Humidity::new(0).unwrap().percent(), 0);
+    assert_eq!(Humidity::new(100), Ok(Humidity::MAX));
+    assert_eq!(Humidity::new(101), Err(ValueError::OutOfRange));
+    assert_eq!(Humidity::try_from(250u8), Err(ValueError::OutOfRange));
+    assert_eq!(Humidity::try_from(45.4).unwrap().percent(), 45);
+    assert_eq!(Humidity::try_from(-1.0), Err(ValueError::OutOfRange));
+    assert_eq!(Humidity::try_from(f32::NAN), Err(ValueError::NotFinite));
+
+    assert_eq!(AirPressure::new(300), Ok(AirPressure::MIN));
+    assert_eq!(AirPressure::new(1100), Ok(AirPressure::MAX));
+    assert_eq!(AirPressure::new(299), Err(ValueError::OutOfRange));
+    assert_eq!(AirPressure::new(1101), Err(ValueError::OutOfRange));
+    assert_eq!(AirPressure::try_from(1013.25).unwrap().hectopascals(), 1013);
+    assert_eq!(AirPressure::try_from(0.0), Err(ValueError::OutOfRange));
+
+    assert_eq!(Rssi::new(-128), Ok(Rssi::MIN));
+    assert_eq!(Rssi::new(0), Ok(Rssi::MAX));
+    assert_eq!(Rssi::new(-60).unwrap().dbm(), -60);
+    assert_eq!(Rssi::try_from(1i8), Err(ValueError::OutOfRange));
+}
+
+#[test]
+fn rejects_out_of_range_values_when_deserializing() {
+    let message = Message::new_request(
+        Request::PostResults {
+            temperature: Temperature::from_centi_celsius(0),
+            humidity: Humidity::new(40).unwrap(),
+            air_pressure: Some(AirPressure::new(1013).unwrap()),
+        },
+        1,
+    );
+    let bytes = message.clone().serialize();
+    let humidity = bytes.iter().position(|byte| *byte == 40).unwrap();
+    assert_eq!(Message::deserialize(&bytes), Ok(message));
+
+    let mut invalid = bytes.to_vec();
+    invalid[humidity] = 250;
+    assert_eq!(
+        Message::deserialize(&invalid),
+        Err(DecodeError::InvalidValue { offset: humidity })
+    );
+
+    // The air pressure follows the humidity and its `Some` tag, as a varint.
+    let mut invalid = bytes.to_vec();
+    invalid[humidity + 2..].copy_from_slice(&[0xE7, 0x0F]); // 2023 hPa
+    assert_eq!(
+        Message::deserialize(&invalid),
+        Err(DecodeError::InvalidValue {
+            offset: humidity + 3
+        })
+    );
+
+    let stats = Message::new_request(
+        Request::PostStats {
+            battery: BatteryVoltage::from_millivolts(3700),
+            wifi_ssid: "ssid".into(),
+            wifi_rssi: Rssi::new(-1).unwrap(),
+            diagnostics: Diagnostics::new(0),
+        },
+        1,
+    );
+    // The RSSI is followed by the uptime and 7 empty options of the diagnostics.
+    let mut invalid = stats.serialize().to_vec();
+    let rssi = invalid.len() - 9;
+    invalid[rssi] = 1; // +1 dBm
+    assert_eq!(
+        Message::deserialize(&invalid),
+        Err(DecodeError::InvalidValue { offset: rssi })
+    );
+}
+
+#[test]
+fn answers_out_of_range_values_with_invalid_request() {
+    let message = Message::new_request(
+        Request::PostResults {
+            temperature: Temperature::from_centi_celsius(0),
+            humidity: Humidity::new(40).unwrap(),
+            air_pressure: None,
+        },
+        300,
+    );
+    let mut invalid = message.serialize().to_vec();
+    let humidity = invalid.len() - 2;
+    invalid[humidity] = 250;
+
+    assert_eq!(
+        Message::deserialize(&invalid),
+        Err(DecodeError::InvalidValue { offset: humidity })
+    );
+    assert_eq!(Message::peek_id(&invalid), Some(300));
+
+    let reply = Message::new_reply(Response::InvalidRequest, 1, 300);
+    assert_eq!(reply.response(), Some(&Response::InvalidRequest));
+    assert_eq!(reply.reply_to(), Some(300));
+
+    assert_eq!(Message::peek_id(&[]), None);
+    assert_eq!(Message::peek_id(&[0x80]), None);
+}
+
+#[test]
+fn serializes_compactly() {
+    let results = |temperature| {
+        Message::new_request(
+            Request::PostResults {
+                temperature,
+                humidity: Humidity::new(40).unwrap(),
+                air_pressure: None,
+            },
+            1,
+        )
+        .serialize()
+        .len()
+    };
+
+    // Typical temperatures take 2 bytes instead of the 4 bytes of a float.
+    assert_eq!(
+        results(Temperature::from_centi_celsius(2150)),
+        results(Temperature::from_centi_celsius(0)) + 1
+    );
+    assert_eq!(
+        results(Temperature::try_from(-25.0).unwrap()),
+        results(Temperature::try_from(45.0).unwrap())
+    );
+}
+
+#[test]
+fn displays_units() {
+    assert_eq!(
+        Temperature::from_centi_celsius(-505).to_string(),
+        "-5.05 °C"
+    );
+    assert_eq!(BatteryVoltage::from_millivolts(3700).to_string(), "3.700 V");
+    assert_eq!(Humidity::new(45).unwrap().to_string(), "45 %");
+    assert_eq!(AirPressure::new(1013).unwrap().to_string(), "1013 hPa");
+    assert_eq!(Rssi::new(-60).unwrap().to_string(), "-60 dBm");
+}
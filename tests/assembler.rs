@@ -0,0 +1,487 @@
+use pwmp_msg::{
+    assembler::{AssembleError, FirmwareSink, IoSink, MemorySink, OtaAssembler},
+    compression::{Compression, DecompressError},
+    manifest::{FirmwareManifest, ManifestError, SigningKey},
+    ota::{sha256, VerifyError},
+    request::Request,
+    response::Response,
+    version::Version,
+    Message, MessageRef,
+};
+use std::{fmt::Debug, io::Cursor};
+
+const IMAGE: &[u8] = b"This firmware image is downloaded in several small chunks.";
+
+fn key() -> SigningKey {
+    SigningKey::from_bytes(&[3; 32])
+}
+
+fn announcement(image: &[u8]) -> Response {
+    Response::UpdateAvailable(
+        FirmwareManifest {
+            version: Version::new(1, 1, 0),
+            size: image.len() as u32,
+            sha256: sha256(image),
+            min_version: Version::new(1, 0, 0),
+            target: "esp32s3".into(),
+            delta: None,
+        }
+        .sign(&key()),
+    )
+}
+
+fn assembler<S: FirmwareSink>(sink: S, max_chunk_size: u32) -> OtaAssembler<S>
+where
+    S::Error: Debug,
+{
+    OtaAssembler::new(
+        &announcement(IMAGE),
+        &key().verifying_key(),
+        sink,
+        max_chunk_size,
+    )
+    .unwrap()
+}
+
+/// Answers a chunk request the way a server would.
+fn serve(request: &Request) -> Response {
+    let Request::NextUpdateChunk {
+        offset,
+        max_size,
+        compression,
+        ..
+    } = *request
+    else {
+        panic!("unexpected request: {request:?}");
+    };
+    let start = offset as usize;
+    let end = (start + max_size as usize).min(IMAGE.len());
+
+    if start == IMAGE.len() {
+        Response::UpdateEnd
+    } else {
+        Response::compressed_update_part(
+            offset,
+            IMAGE.len() as u32,
+            &IMAGE[start..end],
+            compression,
+        )
+    }
+}
+
+#[test]
+fn assembles_image_in_memory() {
+    let mut assembler = assembler(MemorySink::new(), 10);
+    assert_eq!(assembler.progress(), 0);
+
+    loop {
+        let response = serve(&assembler.next_request());
+        if response == Response::UpdateEnd {
+            break;
+        }
+
+        assembler.push_part(&response).unwrap();
+    }
+
+    assert!(assembler.is_complete());
+    assert_eq!(assembler.progress(), 100);
+    assert_eq!(
+        assembler.finish(&Response::UpdateEnd).unwrap().into_inner(),
+        IMAGE
+    );
+}
+
+#[test]
+fn accepts_borrowed_parts() {
+    let mut assembler = assembler(MemorySink::new(), IMAGE.len() as u32);
+    let bytes = Message::new_response(serve(&assembler.next_request()), 1).serialize();
+    let message = MessageRef::deserialize(&bytes).unwrap();
+
+    assembler
+        .push_part(message.response().unwrap().clone())
+        .unwrap();
+    assert_eq!(
+        assembler.finish(&Response::UpdateEnd).unwrap().as_slice(),
+        IMAGE
+    );
+}
+
+#[test]
+fn writes_into_seekable_writer() {
+    let mut assembler = assembler(IoSink::new(Cursor::new(Vec::new())), 20);
+    while !assembler.is_complete() {
+        assembler
+            .push_part(&serve(&assembler.next_request()))
+            .unwrap();
+    }
+
+    let sink = assembler.finish(&Response::UpdateEnd).unwrap();
+    assert_eq!(sink.into_inner().into_inner(), IMAGE);
+}
+
+#[test]
+fn tracks_progress() {
+    let mut sink = MemorySink::new();
+    let half = IMAGE.len() as u32 / 2;
+    let mut assembler = assembler(&mut sink, half);
+    assembler
+        .push_part(&serve(&assembler.next_request()))
+        .unwrap();
+
+    assert_eq!(assembler.received(), half);
+    assert_eq!(assembler.progress(), 50);
+    assert_eq!(
+        assembler.next_request(),
+        Request::NextUpdateChunk {
+            offset: half,
+            max_size: half,
+            compression: Compression::None,
+            base: None,
+        }
+    );
+}
+
+#[test]
+fn rejects_overflow_past_announced_size() {
+    let mut assembler = assembler(MemorySink::new(), IMAGE.len() as u32);
+    assembler
+        .push_part(&serve(&assembler.next_request()))
+        .unwrap();
+
+    let extra = Response::update_part(IMAGE.len() as u32, IMAGE.len() as u32, vec![0; 4].into());
+    assert_eq!(
+        assembler.push_part(&extra),
+        Err(AssembleError::Verify(VerifyError::Overflow {
+            size: IMAGE.len() as u32
+        }))
+    );
+}
+
+#[test]
+fn rejects_part_of_other_image() {
+    let mut assembler = assembler(MemorySink::new(), 8);
+    let part = Response::update_part(0, 4096, IMAGE[..8].into());
+
+    assert_eq!(
+        assembler.push_part(&part),
+        Err(AssembleError::SizeMismatch {
+            announced: IMAGE.len() as u32,
+            actual: 4096
+        })
+    );
+}
+
+#[test]
+fn rejects_corrupted_part() {
+    let mut assembler = assembler(MemorySink::new(), 8);
+    let Response::UpdatePart {
+        offset,
+        total_size,
+        crc32,
+        compression,
+        mut data,
+    } = serve(&assembler.next_request())
+    else {
+        unreachable!();
+    };
+    data[0] ^= 0xFF;
+    let corrupted = Response::UpdatePart {
+        offset,
+        total_size,
+        crc32,
+        compression,
+        data,
+    };
+
+    assert_eq!(
+        assembler.push_part(&corrupted),
+        Err(AssembleError::Verify(VerifyError::ChecksumMismatch {
+            offset: 0
+        }))
+    );
+}
+
+#[test]
+fn assembles_compressed_image() {
+    let image = b"weather weather weather weather weather weather weather weather".repeat(8);
+    let mut assembler = OtaAssembler::new(
+        &announcement(&image),
+        &key().verifying_key(),
+        MemorySink::new(),
+        128,
+    )
+    .unwrap()
+    .with_compression(Compression::Lzss);
+    let size = image.len() as u32;
+
+    while !assembler.is_complete() {
+        let Request::NextUpdateChunk {
+            offset,
+            max_size,
+            compression,
+            ..
+        } = assembler.next_request()
+        else {
+            unreachable!();
+        };
+        assert_eq!(compression, Compression::Lzss);
+
+        let start = offset as usize;
+        let end = (start + max_size as usize).min(image.len());
+        let part = Response::compressed_update_part(offset, size, &image[start..end], compression);
+        assert!(matches!(
+            &part,
+            Response::UpdatePart { compression: Compression::Lzss, data, .. } if data.len() < end - start
+        ));
+
+        assembler.push_part(&part).unwrap();
+    }
+
+    assert_eq!(
+        assembler.finish(&Response::UpdateEnd).unwrap().into_inner(),
+        image
+    );
+}
+
+#[test]
+fn rejects_malformed_compressed_part() {
+    let mut assembler = assembler(MemorySink::new(), 16);
+    let part = |data: &[u8]| Response::UpdatePart {
+        offset: 0,
+        total_size: IMAGE.len() as u32,
+        crc32: 0,
+        compression: Compression::Lzss,
+        data: data.into(),
+    };
+
+    // The decompressed size exceeds the image, so nothing is allocated.
+    assert_eq!(
+        assembler.push_part(&part(&u32::MAX.to_le_bytes())),
+        Err(AssembleError::Verify(VerifyError::Overflow {
+            size: IMAGE.len() as u32
+        }))
+    );
+    assert_eq!(
+        assembler.push_part(&part(&[8, 0, 0, 0, 0, b'a'])),
+        Err(AssembleError::Decompress(DecompressError::Truncated))
+    );
+    assert_eq!(assembler.received(), 0);
+}
+
+#[test]
+fn rejects_parts_larger_than_requested() {
+    let image = [0; 4096];
+    let mut assembler = OtaAssembler::new(
+        &announcement(&image),
+        &key().verifying_key(),
+        MemorySink::new(),
+        1024,
+    )
+    .unwrap()
+    .with_compression(Compression::Lzss);
+
+    // The server ignores the requested size, and sends the whole image in one chunk.
+    let part = Response::compressed_update_part(0, 4096, &image, Compression::Lzss);
+    assert_eq!(
+        assembler.push_part(&part),
+        Err(AssembleError::ChunkTooLarge {
+            requested: 1024,
+            actual: 4096
+        })
+    );
+
+    let part = Response::update_part(0, 4096, image[..1025].into());
+    assert_eq!(
+        assembler.push_part(&part),
+        Err(AssembleError::ChunkTooLarge {
+            requested: 1024,
+            actual: 1025
+        })
+    );
+    assert_eq!(assembler.received(), 0);
+
+    let part = Response::compressed_update_part(0, 4096, &image[..1024], Compression::Lzss);
+    assert_eq!(assembler.push_part(&part), Ok(()));
+    assert_eq!(assembler.received(), 1024);
+}
+
+#[test]
+fn finishes_on_update_end() {
+    let mut assembler = assembler(MemorySink::new(), IMAGE.len() as u32);
+    assembler
+        .push_part(&serve(&assembler.next_request()))
+        .unwrap();
+
+    assert_eq!(
+        assembler.finish(&Response::FirmwareUpToDate).unwrap_err(),
+        AssembleError::UnexpectedResponse
+    );
+}
+
+#[test]
+fn rejects_incomplete_image() {
+    let mut assembler = assembler(MemorySink::new(), 8);
+    assembler
+        .push_part(&serve(&assembler.next_request()))
+        .unwrap();
+
+    assert_eq!(
+        assembler.finish(&Response::UpdateEnd),
+        Err(AssembleError::Verify(VerifyError::Incomplete {
+            received: 8,
+            size: IMAGE.len() as u32
+        }))
+    );
+}
+
+#[test]
+fn rejects_unsigned_update() {
+    let foreign_key = SigningKey::from_bytes(&[4; 32]).verifying_key();
+
+    assert_eq!(
+        OtaAssembler::new(&announcement(IMAGE), &foreign_key, MemorySink::new(), 8).unwrap_err(),
+        AssembleError::Manifest(ManifestError::InvalidSignature)
+    );
+}
+
+#[test]
+fn rejects_unexpected_responses() {
+    assert_eq!(
+        OtaAssembler::new(&Response::Ok, &key().verifying_key(), MemorySink::new(), 8).unwrap_err(),
+        AssembleError::UnexpectedResponse
+    );
+
+    let mut assembler = assembler(MemorySink::new(), 8);
+    assert_eq!(
+        assembler.push_part(&Response::UpdateEnd),
+        Err(AssembleError::UnexpectedResponse)
+    );
+}
+
+#[test]
+fn reports_sink_errors() {
+    /// A flash partition that is too small for the test image.
+    #[derive(Debug)]
+    struct TinyPartition;
+
+    impl FirmwareSink for TinyPartition {
+        type Error = &'static str;
+
+        fn begin(&mut self, size: u32) -> Result<(), Self::Error> {
+            if size > 16 {
+                return Err("partition too small");
+            }
+
+            Ok(())
+        }
+
+        fn write(&mut self, _offset: u32, _data: &[u8]) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    assert_eq!(
+        OtaAssembler::new(
+            &announcement(IMAGE),
+            &key().verifying_key(),
+            TinyPartition,
+            8
+        )
+        .unwrap_err(),
+        AssembleError::Sink("partition too small")
+    );
+}
+
+#[test]
+fn retries_parts_that_could_not_be_written() {
+    /// A flash partition whose second write fails once.
+    #[derive(Debug, Default)]
+    struct FlakyPartition {
+        inner: MemorySink,
+        writes: usize,
+    }
+
+    impl FirmwareSink for FlakyPartition {
+        type Error = &'static str;
+
+        fn write(&mut self, offset: u32, data: &[u8]) -> Result<(), Self::Error> {
+            self.writes += 1;
+            if self.writes == 2 {
+                return Err("write failed");
+            }
+
+            self.inner.write(offset, data).map_err(|_| "unreachable")
+        }
+    }
+
+    let mut assembler = assembler(FlakyPartition::default(), 8);
+    assembler
+        .push_part(&serve(&assembler.next_request()))
+        .unwrap();
+
+    let request = assembler.next_request();
+    assert_eq!(
+        assembler.push_part(&serve(&request)),
+        Err(AssembleError::Sink("write failed"))
+    );
+    assert_eq!(assembler.received(), 8);
+    assert_eq!(assembler.next_request(), request);
+
+    loop {
+        let response = serve(&assembler.next_request());
+        if response == Response::UpdateEnd {
+            break;
+        }
+
+        assembler.push_part(&response).unwrap();
+    }
+
+    assert_eq!(
+        assembler
+            .finish(&Response::UpdateEnd)
+            .unwrap()
+            .inner
+            .as_slice(),
+        IMAGE
+    );
+}
+
+#[test]
+fn rejects_image_with_unwritten_part() {
+    /// A flash partition whose second write always fails.
+    #[derive(Debug, Default)]
+    struct BrokenPartition {
+        writes: usize,
+    }
+
+    impl FirmwareSink for BrokenPartition {
+        type Error = &'static str;
+
+        fn write(&mut self, _offset: u32, _data: &[u8]) -> Result<(), Self::Error> {
+            self.writes += 1;
+            if self.writes == 2 {
+                return Err("write failed");
+            }
+
+            Ok(())
+        }
+    }
+
+    let mut assembler = assembler(BrokenPartition::default(), 8);
+    for offset in (0..IMAGE.len()).step_by(8) {
+        let end = (offset + 8).min(IMAGE.len());
+        let part =
+            Response::update_part(offset as u32, IMAGE.len() as u32, IMAGE[offset..end].into());
+        let _ = assembler.push_part(&part);
+    }
+
+    // The parts after the failed one don't continue the image.
+    assert_eq!(assembler.received(), 8);
+    assert_eq!(
+        assembler.finish(&Response::UpdateEnd).unwrap_err(),
+        AssembleError::Verify(VerifyError::Incomplete {
+            received: 8,
+            size: IMAGE.len() as u32
+        })
+    );
+}
@@ -0,0 +1,113 @@
+use pwmp_msg::{
+    aliases::BatteryVoltage,
+    battery::{BatteryLevel, BatteryProfile, CurvePoint, DischargeCurve},
+    settings::NodeSettings,
+};
+
+fn volts(millivolts: u16) -> BatteryVoltage {
+    BatteryVoltage::from_millivolts(millivolts)
+}
+
+#[test]
+fn built_in_curves_are_valid() {
+    for curve in [
+        DischargeCurve::LI_ION,
+        DischargeCurve::LIFEPO4,
+        DischargeCurve::ALKALINE_2AA,
+    ] {
+        let points = curve.points();
+
+        assert_eq!(DischargeCurve::new(points), Some(curve));
+        assert_eq!(points.first().unwrap().percent, 0);
+        assert_eq!(points.last().unwrap().percent, 100);
+    }
+}
+
+#[test]
+fn interpolates_between_points() {
+    let curve = DischargeCurve::LI_ION;
+
+    assert_eq!(curve.percentage(volts(4200)), 100);
+    assert_eq!(curve.percentage(volts(3830)), 50);
+    assert_eq!(curve.percentage(volts(3850)), 55);
+    assert_eq!(curve.percentage(volts(3150)), 2);
+    assert_eq!(curve.percentage(volts(3000)), 0);
+
+    assert_eq!(DischargeCurve::LIFEPO4.percentage(volts(3285)), 55);
+    assert_eq!(DischargeCurve::ALKALINE_2AA.percentage(volts(2700)), 52);
+}
+
+#[test]
+fn clamps_outside_of_curve() {
+    assert_eq!(DischargeCurve::LI_ION.percentage(volts(4350)), 100);
+    assert_eq!(DischargeCurve::LI_ION.percentage(volts(2500)), 0);
+    assert_eq!(DischargeCurve::LI_ION.percentage(volts(0)), 0);
+    assert_eq!(
+        DischargeCurve::ALKALINE_2AA.percentage(volts(u16::MAX)),
+        100
+    );
+
+    let points = [CurvePoint::new(3000, 10), CurvePoint::new(3500, 90)];
+    let curve = DischargeCurve::new(&points).unwrap();
+    assert_eq!(curve.percentage(volts(2000)), 10);
+    assert_eq!(curve.percentage(volts(4000)), 90);
+
+    let single = [CurvePoint::new(3000, 50)];
+    let curve = DischargeCurve::new(&single).unwrap();
+    assert_eq!(curve.percentage(volts(2000)), 50);
+    assert_eq!(curve.percentage(volts(3000)), 50);
+    assert_eq!(curve.percentage(volts(4000)), 50);
+}
+
+#[test]
+fn rejects_invalid_curves() {
+    assert!(DischargeCurve::new(&[]).is_none());
+    assert!(DischargeCurve::new(&[CurvePoint::new(3000, 101)]).is_none());
+    // Voltages out of order.
+    assert!(DischargeCurve::new(&[CurvePoint::new(3500, 0), CurvePoint::new(3000, 100)]).is_none());
+    // The same voltage twice.
+    assert!(DischargeCurve::new(&[CurvePoint::new(3000, 0), CurvePoint::new(3000, 100)]).is_none());
+    // The charge decreases.
+    assert!(DischargeCurve::new(&[CurvePoint::new(3000, 50), CurvePoint::new(3500, 40)]).is_none());
+}
+
+#[test]
+fn classifies_levels() {
+    let profile = BatteryProfile::new(DischargeCurve::LI_ION);
+
+    assert_eq!(profile.level(volts(4000)), BatteryLevel::Normal);
+    assert_eq!(profile.level(volts(3710)), BatteryLevel::Normal);
+    assert_eq!(profile.level(volts(3700)), BatteryLevel::Low);
+    assert_eq!(profile.level(volts(3300)), BatteryLevel::Critical);
+    assert_eq!(profile.level(volts(2900)), BatteryLevel::Critical);
+
+    let profile = BatteryProfile {
+        low: 50,
+        critical: 30,
+        ..BatteryProfile::new(DischargeCurve::LIFEPO4)
+    };
+    assert_eq!(profile.level(volts(3300)), BatteryLevel::Normal);
+    assert_eq!(profile.level(volts(3270)), BatteryLevel::Low);
+    assert_eq!(profile.level(volts(3260)), BatteryLevel::Critical);
+}
+
+#[test]
+fn follows_node_settings() {
+    let profile = BatteryProfile::new(DischargeCurve::ALKALINE_2AA);
+    let settings = NodeSettings::default();
+    let (normal, low, critical) = (volts(3000), volts(2300), volts(2050));
+
+    assert!(!profile.should_warn(normal, &settings));
+    assert!(profile.should_warn(low, &settings));
+    assert!(profile.should_warn(critical, &settings));
+    assert!(!profile.should_protect(low, &settings));
+    assert!(profile.should_protect(critical, &settings));
+
+    let ignored = NodeSettings {
+        battery_ignore: true,
+        sbop: false,
+        ..settings
+    };
+    assert!(!profile.should_warn(critical, &ignored));
+    assert!(!profile.should_protect(critical, &ignored));
+}
@@ -0,0 +1,19 @@
+//! Fixtures shared by the integration tests.
+
+use pwmp_msg::{
+    manifest::{FirmwareManifest, SignedManifest, SigningKey},
+    version::Version,
+};
+
+/// A manifest of a 4 KiB image of the given version, signed with a fixed key.
+pub fn manifest(version: Version) -> SignedManifest {
+    FirmwareManifest {
+        version,
+        size: 4096,
+        sha256: [0xAB; 32],
+        min_version: Version::new(1, 0, 0),
+        target: "esp32".into(),
+        delta: None,
+    }
+    .sign(&SigningKey::from_bytes(&[1; 32]))
+}
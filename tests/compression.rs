@@ -0,0 +1,127 @@
+use pwmp_msg::compression::{
+    compress, decompress, decompressed_size, Compression, DecompressError, MAX_MATCH, WINDOW_SIZE,
+};
+
+/// Firmware-like data: repeated structures with some noise.
+fn sample(len: usize) -> Vec<u8> {
+    let mut state = 0x1234_5678u32;
+
+    (0..len)
+        .map(|i| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            if i % 7 == 0 {
+                (state >> 24) as u8
+            } else {
+                (i % 64) as u8
+            }
+        })
+        .collect()
+}
+
+fn roundtrip(data: &[u8]) -> Vec<u8> {
+    let compressed = compress(data);
+    let mut output = vec![0; data.len()];
+
+    assert_eq!(decompressed_size(&compressed), Ok(data.len() as u32));
+    assert_eq!(decompress(&compressed, &mut output), Ok(data.len()));
+    assert_eq!(output, data);
+
+    compressed
+}
+
+#[test]
+fn roundtrips() {
+    roundtrip(&[]);
+    roundtrip(b"a");
+    roundtrip(b"abcabcabcabc");
+    roundtrip(&[0; 10_000]);
+    roundtrip(&sample(65_536));
+}
+
+#[test]
+fn compresses_repetitive_data() {
+    let data = sample(16_384);
+
+    assert!(roundtrip(&data).len() < data.len() / 2);
+    assert!(roundtrip(&[0xFF; 4096]).len() < 4096 / MAX_MATCH * 3);
+}
+
+#[test]
+fn references_reach_the_whole_window() {
+    let block = sample(64);
+    let mut data = block.clone();
+    data.extend(std::iter::repeat_n(0xAA, WINDOW_SIZE - 64));
+    data.extend(&block);
+
+    let compressed = roundtrip(&data);
+    assert!(compressed.len() < data.len() / 4);
+}
+
+#[test]
+fn incompressible_data_grows_by_flag_bytes_only() {
+    let data: Vec<u8> = (0..=255).collect();
+
+    assert_eq!(compress(&data).len(), 4 + data.len() + data.len() / 8);
+}
+
+#[test]
+fn rejects_small_output() {
+    let compressed = compress(&[1; 100]);
+    let mut output = [0; 99];
+
+    assert_eq!(
+        decompress(&compressed, &mut output),
+        Err(DecompressError::OutputTooSmall { required: 100 })
+    );
+    assert_eq!(
+        Compression::None.decompress(&[1; 100], &mut output),
+        Err(DecompressError::OutputTooSmall { required: 100 })
+    );
+}
+
+#[test]
+fn rejects_corrupted_data() {
+    let mut output = [0; 16];
+
+    assert_eq!(
+        decompress(&[4, 0, 0], &mut output),
+        Err(DecompressError::Truncated)
+    );
+    assert_eq!(
+        decompress(&[4, 0, 0, 0, 0, b'a', b'b'], &mut output),
+        Err(DecompressError::Truncated)
+    );
+    // A reference before the start of the chunk.
+    assert_eq!(
+        decompress(&[4, 0, 0, 0, 0b10, b'a', 0x00, 0x10], &mut output),
+        Err(DecompressError::InvalidReference)
+    );
+    // A reference past the end of the chunk.
+    assert_eq!(
+        decompress(&[4, 0, 0, 0, 0b10, b'a', 0x00, 0x01], &mut output),
+        Err(DecompressError::InvalidReference)
+    );
+    assert_eq!(
+        decompress(&[1, 0, 0, 0, 0, b'a', b'b'], &mut output),
+        Err(DecompressError::TrailingData)
+    );
+}
+
+#[test]
+fn decodes_overlapping_references() {
+    // "a", then a reference of 6 bytes at distance 1.
+    let data = [7, 0, 0, 0, 0b10, b'a', 0x00, 0x03];
+    let mut output = [0; 7];
+
+    assert_eq!(decompress(&data, &mut output), Ok(7));
+    assert_eq!(&output, b"aaaaaaa");
+}
+
+#[test]
+fn uncompressed_chunks_are_copied() {
+    let mut output = [0; 8];
+
+    assert_eq!(Compression::None.decompressed_size(b"raw"), Ok(3));
+    assert_eq!(Compression::None.decompress(b"raw", &mut output), Ok(3));
+    assert_eq!(&output[..3], b"raw");
+}
@@ -0,0 +1,61 @@
+use pwmp_msg::{error::DecodeError, request::Request, Message};
+
+fn notification_bytes() -> Box<[u8]> {
+    Message::new_request(Request::SendNotification("Hi".into()), 1).serialize()
+}
+
+#[test]
+fn empty_buffer() {
+    assert_eq!(
+        Message::deserialize(&[]),
+        Err(DecodeError::UnexpectedEnd { offset: 0 })
+    );
+}
+
+#[test]
+fn truncated_string() {
+    // ID, `Request`, `SendNotification`, length, 'H'
+    let bytes = notification_bytes();
+    assert_eq!(bytes.len(), 6);
+
+    assert_eq!(
+        Message::deserialize(&bytes[..5]),
+        Err(DecodeError::UnexpectedEnd { offset: 4 })
+    );
+}
+
+#[test]
+fn unknown_content_tag() {
+    assert_eq!(
+        Message::deserialize(&[1, 2]),
+        Err(DecodeError::InvalidValue { offset: 1 })
+    );
+}
+
+#[test]
+fn invalid_utf8() {
+    let mut bytes = notification_bytes();
+    bytes[4] = 0xFF;
+
+    assert_eq!(
+        Message::deserialize(&bytes),
+        Err(DecodeError::InvalidUtf8 { offset: 4 })
+    );
+}
+
+#[test]
+fn invalid_option() {
+    // ID, `Response`, `Settings`, bad option tag
+    assert_eq!(
+        Message::deserialize(&[1, 1, 11, 7]),
+        Err(DecodeError::InvalidOption { offset: 3 })
+    );
+}
+
+#[test]
+fn invalid_varint() {
+    assert_eq!(
+        Message::deserialize(&[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]),
+        Err(DecodeError::InvalidVarint { offset: 4 })
+    );
+}
@@ -0,0 +1,217 @@
+use pwmp_msg::{
+    assembler::{FirmwareSink, MemorySink},
+    delta::{diff, BaseImage, DeltaApplier, DeltaError, IoBase},
+};
+use std::{convert::Infallible, io::Cursor};
+
+/// A firmware-like base image.
+fn base() -> Vec<u8> {
+    let mut state = 0x9E37_79B9u32;
+
+    (0..20_000)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            (state >> 24) as u8
+        })
+        .collect()
+}
+
+/// A new version of [`base()`], with some code changed, moved, inserted and removed.
+fn target() -> Vec<u8> {
+    let base = base();
+    let mut target = base[..5000].to_vec();
+    target.extend_from_slice(b"new feature: dew point calculation");
+    target.extend_from_slice(&base[12_000..16_000]);
+    target.extend_from_slice(&base[5000..11_000]);
+    target[7000] ^= 0xFF;
+    target.extend(base[16_000..].iter().map(|b| b.wrapping_add(1)));
+
+    target
+}
+
+fn apply<B: BaseImage>(
+    base: B,
+    patch: &[u8],
+    chunk_size: usize,
+) -> Result<Vec<u8>, DeltaError<B::Error, Infallible>> {
+    let mut applier = DeltaApplier::new(base, MemorySink::new());
+    applier.begin(patch.len() as u32)?;
+
+    for (i, chunk) in patch.chunks(chunk_size).enumerate() {
+        applier.write((i * chunk_size) as u32, chunk)?;
+    }
+    applier.finish()?;
+
+    Ok(applier.into_inner().1.into_inner())
+}
+
+#[test]
+fn roundtrips() {
+    let base = base();
+    let target = target();
+    let patch = diff(&base, &target);
+
+    for chunk_size in [1, 7, 512, patch.len()] {
+        assert_eq!(
+            apply(base.as_slice(), &patch, chunk_size),
+            Ok(target.clone())
+        );
+    }
+
+    assert_eq!(apply(base.as_slice(), &diff(&base, &[]), 64), Ok(vec![]));
+    assert_eq!(apply([].as_slice(), &diff(&[], &target), 64), Ok(target));
+}
+
+#[test]
+fn patch_is_smaller_than_image() {
+    let target = target();
+    let patch = diff(&base(), &target);
+
+    // Most of the image is copied, only the modified parts are inserted.
+    assert!(patch.len() < target.len() / 2, "{} bytes", patch.len());
+}
+
+#[test]
+fn reads_base_from_reader() {
+    let base = base();
+    let target = target();
+    let patch = diff(&base, &target);
+    let reader = IoBase::new(Cursor::new(base.clone()), base.len() as u32);
+
+    assert_eq!(apply(reader, &patch, 1000).unwrap(), target);
+}
+
+#[test]
+fn rejects_wrong_base() {
+    let base = base();
+    let patch = diff(&base, &target());
+
+    let mut modified = base.clone();
+    modified[100] ^= 1;
+    assert_eq!(
+        apply(modified.as_slice(), &patch, 64),
+        Err(DeltaError::BaseMismatch)
+    );
+    assert_eq!(apply(&base[1..], &patch, 64), Err(DeltaError::BaseMismatch));
+}
+
+#[test]
+fn rejects_corrupted_patches() {
+    let base = base();
+    let target = target();
+    let patch = diff(&base, &target);
+
+    let mut magic = patch.clone();
+    magic[0] = b'X';
+    assert_eq!(
+        apply(base.as_slice(), &magic, 64),
+        Err(DeltaError::InvalidMagic)
+    );
+
+    let mut tag = patch.clone();
+    tag[76] = 7;
+    assert_eq!(
+        apply(base.as_slice(), &tag, 64),
+        Err(DeltaError::InvalidOperation { tag: 7 })
+    );
+
+    // The digest of the new image.
+    let mut digest = patch.clone();
+    digest[44] ^= 1;
+    assert_eq!(
+        apply(base.as_slice(), &digest, 64),
+        Err(DeltaError::DigestMismatch)
+    );
+
+    let mut trailing = patch.clone();
+    trailing.extend_from_slice(&[1, 1, 0]);
+    assert_eq!(
+        apply(base.as_slice(), &trailing, 64),
+        Err(DeltaError::TrailingData)
+    );
+
+    assert_eq!(
+        apply(base.as_slice(), &patch[..patch.len() - 1], 64),
+        Err(DeltaError::Incomplete)
+    );
+}
+
+#[test]
+fn rejects_invalid_operations() {
+    let base = b"base image".as_slice();
+    let header = &diff(base, &[0; 16])[..76];
+
+    // A copy past the end of the base image.
+    let mut copy = header.to_vec();
+    copy.extend_from_slice(&[0, 5, 10]);
+    assert_eq!(apply(base, &copy, 64), Err(DeltaError::CopyOutOfBounds));
+
+    // An insert past the end of the new image.
+    let mut insert = header.to_vec();
+    insert.extend_from_slice(&[1, 17]);
+    insert.extend_from_slice(&[0; 17]);
+    assert_eq!(apply(base, &insert, 64), Err(DeltaError::Overflow));
+
+    // An argument that doesn't fit into 32 bits.
+    let mut varint = header.to_vec();
+    varint.extend_from_slice(&[1, 0xFF, 0xFF, 0xFF, 0xFF, 0x1F]);
+    assert_eq!(apply(base, &varint, 64), Err(DeltaError::InvalidVarint));
+}
+
+#[test]
+fn rejects_retry_after_failed_write() {
+    /// A sink whose first write fails once.
+    #[derive(Debug, Default)]
+    struct FlakySink {
+        writes: usize,
+    }
+
+    impl FirmwareSink for FlakySink {
+        type Error = &'static str;
+
+        fn write(&mut self, _offset: u32, _data: &[u8]) -> Result<(), Self::Error> {
+            self.writes += 1;
+            if self.writes == 1 {
+                return Err("write failed");
+            }
+
+            Ok(())
+        }
+    }
+
+    let base = base();
+    let patch = diff(&base, &target());
+    let mut applier = DeltaApplier::new(base.as_slice(), FlakySink::default());
+
+    applier.begin(patch.len() as u32).unwrap();
+    assert_eq!(
+        applier.write(0, &patch),
+        Err(DeltaError::Sink("write failed"))
+    );
+    assert_eq!(applier.write(0, &patch), Err(DeltaError::Aborted));
+
+    // Starting again applies the whole patch.
+    applier.begin(patch.len() as u32).unwrap();
+    applier.write(0, &patch).unwrap();
+    applier.finish().unwrap();
+}
+
+#[test]
+fn rejects_out_of_order_chunks() {
+    let base = base();
+    let patch = diff(&base, &target());
+    let mut applier = DeltaApplier::new(base.as_slice(), MemorySink::new());
+
+    applier.begin(patch.len() as u32).unwrap();
+    applier.write(0, &patch[..100]).unwrap();
+
+    assert_eq!(
+        applier.write(200, &patch[200..300]),
+        Err(DeltaError::UnexpectedOffset {
+            expected: 100,
+            actual: 200
+        })
+    );
+}
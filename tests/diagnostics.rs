@@ -0,0 +1,98 @@
+use pwmp_msg::{
+    aliases::{BatteryVoltage, Rssi, ValueError},
+    diagnostics::{Diagnostics, ResetReason, WakeReason},
+    mac::Mac,
+    request::{InvalidField, Request},
+    Message,
+};
+
+fn stats(diagnostics: Diagnostics) -> Request {
+    Request::PostStats {
+        battery: BatteryVoltage::from_millivolts(3700),
+        wifi_ssid: "Weather".into(),
+        wifi_rssi: Rssi::new(-60).unwrap(),
+        diagnostics,
+    }
+}
+
+fn full() -> Diagnostics {
+    Diagnostics {
+        reset_reason: Some(ResetReason::Wake(WakeReason::External)),
+        free_heap: Some(120_000),
+        min_free_heap: Some(96_000),
+        wifi_channel: Some(1),
+        bssid: Some(Mac::new(0x02, 0x00, 0x5E, 0x10, 0x20, 0x30)),
+        connect_time: Some(950),
+        failed_sessions: Some(0),
+        ..Diagnostics::new(u64::MAX)
+    }
+}
+
+#[test]
+fn roundtrips() {
+    for diagnostics in [Diagnostics::new(0), full()] {
+        let message = Message::new_request(stats(diagnostics), 1);
+
+        assert_eq!(
+            Message::deserialize(&message.clone().serialize()),
+            Ok(message)
+        );
+    }
+}
+
+#[test]
+fn omitted_values_are_cheap() {
+    let len = |diagnostics| {
+        Message::new_request(stats(diagnostics), 1)
+            .serialize()
+            .len()
+    };
+
+    // A small uptime and one byte for each omitted value.
+    assert_eq!(len(Diagnostics::new(1_000)), len(Diagnostics::new(0)) + 1);
+    assert_eq!(
+        len(Diagnostics {
+            failed_sessions: Some(1),
+            ..Diagnostics::new(0)
+        }),
+        len(Diagnostics::new(0)) + 1
+    );
+}
+
+#[test]
+fn validates_values() {
+    assert_eq!(stats(Diagnostics::new(0)).validate(), Ok(()));
+    assert_eq!(stats(full()).validate(), Ok(()));
+
+    assert_eq!(
+        stats(Diagnostics {
+            wifi_channel: Some(0),
+            ..full()
+        })
+        .validate(),
+        Err(InvalidField {
+            field: "wifi_channel",
+            error: ValueError::OutOfRange
+        })
+    );
+    assert_eq!(
+        stats(Diagnostics {
+            min_free_heap: Some(120_001),
+            ..full()
+        })
+        .validate(),
+        Err(InvalidField {
+            field: "min_free_heap",
+            error: ValueError::OutOfRange
+        })
+    );
+    // The minimum can't be checked without the current value.
+    assert_eq!(
+        stats(Diagnostics {
+            free_heap: None,
+            ..full()
+        })
+        .validate(),
+        Ok(())
+    );
+}
@@ -0,0 +1,83 @@
+use pwmp_msg::{error::EncodeError, request::Request, response::Response, Message};
+use std::io::{self, Write};
+
+/// A writer that accepts a limited number of bytes.
+struct LimitedWriter(usize);
+
+impl Write for LimitedWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.0 == 0 {
+            return Err(io::ErrorKind::BrokenPipe.into());
+        }
+
+        let n = buf.len().min(self.0);
+        self.0 -= n;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn sample() -> Message {
+    Message::new_request(Request::SendNotification("Battery low".into()), 42)
+}
+
+#[test]
+fn all_methods_produce_identical_bytes() {
+    let message = sample();
+    let expected = message.clone().serialize();
+
+    let mut buffer = [0; 64];
+    assert_eq!(*message.serialize_into(&mut buffer).unwrap(), *expected);
+
+    let mut vec = Vec::new();
+    assert_eq!(message.serialize_append(&mut vec), Ok(expected.len()));
+    assert_eq!(*vec, *expected);
+
+    let mut output = Vec::new();
+    assert_eq!(message.serialize_to_writer(&mut output), Ok(expected.len()));
+    assert_eq!(*output, *expected);
+}
+
+#[test]
+fn buffer_too_small() {
+    let message = Message::new_response(Response::update_part(0, 4096, vec![0; 32].into()), 1);
+    let mut buffer = [0; 16];
+
+    assert_eq!(
+        message.serialize_into(&mut buffer),
+        Err(EncodeError::BufferFull)
+    );
+}
+
+#[test]
+fn buffer_can_be_reused() {
+    let mut buffer = [0; 16];
+
+    for id in 0..4 {
+        let message = Message::new_request(Request::Ping, id);
+        let bytes = message.serialize_into(&mut buffer).unwrap();
+
+        assert_eq!(Message::deserialize(bytes), Ok(message));
+    }
+}
+
+#[test]
+fn appends_after_existing_data() {
+    let mut vec = vec![1, 2, 3];
+    let written = sample().serialize_append(&mut vec).unwrap();
+
+    assert_eq!(vec[..3], [1, 2, 3]);
+    assert_eq!(vec.len(), 3 + written);
+    assert_eq!(Message::deserialize(&vec[3..]), Ok(sample()));
+}
+
+#[test]
+fn writer_error_is_reported() {
+    assert_eq!(
+        sample().serialize_to_writer(LimitedWriter(4)),
+        Err(EncodeError::Io(io::ErrorKind::BrokenPipe.into()))
+    );
+}
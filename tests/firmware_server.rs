@@ -0,0 +1,345 @@
+use pwmp_msg::{
+    assembler::{FirmwareSink, MemorySink, OtaAssembler},
+    compression::Compression,
+    delta::{diff, DeltaApplier},
+    firmware_server::{FirmwareServer, FirmwareServerError, DEFAULT_MAX_CHUNK_SIZE},
+    mac::Mac,
+    manifest::{DeltaManifest, FirmwareManifest, SignedManifest, SigningKey},
+    ota::sha256,
+    protocol::PROTOCOL_REVISION,
+    request::Request,
+    response::Response,
+    rollout::ReleaseChannel,
+    session::{NodeSession, ServerSession},
+    version::Version,
+    Message,
+};
+use std::fmt::Debug;
+
+const RUNNING: Version = Version::new(1, 0, 0);
+const RELEASE: Version = Version::new(1, 1, 0);
+
+fn image() -> Vec<u8> {
+    (0..10_000u32).map(|i| (i % 251) as u8).collect()
+}
+
+fn key() -> SigningKey {
+    SigningKey::from_bytes(&[9; 32])
+}
+
+/// The firmware the node is running.
+fn running_image() -> Vec<u8> {
+    let mut image = image();
+    image[1000..1100].fill(0);
+    image
+}
+
+fn manifest(image: &[u8], delta: Option<DeltaManifest>) -> SignedManifest {
+    FirmwareManifest {
+        version: RELEASE,
+        size: image.len() as u32,
+        sha256: sha256(image),
+        min_version: RUNNING,
+        target: "esp32s3".into(),
+        delta,
+    }
+    .sign(&key())
+}
+
+fn server(image: &[u8]) -> FirmwareServer {
+    FirmwareServer::new(image, manifest(image, None)).unwrap()
+}
+
+/// A server that also holds a patch from [`running_image()`].
+fn delta_server(image: &[u8]) -> FirmwareServer {
+    let base = running_image();
+    let patch = diff(&base, image);
+    let delta = DeltaManifest {
+        base: RUNNING,
+        base_sha256: sha256(&base),
+        size: patch.len() as u32,
+        sha256: sha256(&patch),
+    };
+
+    server(image)
+        .with_delta(patch, manifest(image, Some(delta)))
+        .unwrap()
+}
+
+#[test]
+fn answers_update_checks() {
+    let server = server(&image());
+
+    assert_eq!(server.version(), RELEASE);
+    assert_eq!(
+        server.check_update(RUNNING, false),
+        Response::UpdateAvailable(server.manifest().clone())
+    );
+    assert_eq!(
+        server.check_update(RELEASE, false),
+        Response::FirmwareUpToDate
+    );
+    assert_eq!(
+        server.check_update(Version::new(2, 0, 0), false),
+        Response::FirmwareUpToDate
+    );
+
+    // Older than the minimum version of the manifest.
+    assert_eq!(
+        server.check_update(Version::new(0, 9, 0), false),
+        Response::FirmwareUpToDate
+    );
+}
+
+#[test]
+fn clamps_chunk_size() {
+    let image = image();
+    let server = server(&image).with_max_chunk_size(1000);
+
+    let Response::UpdatePart {
+        offset,
+        total_size,
+        data,
+        ..
+    } = server.chunk(9500, 4096, Compression::None, None)
+    else {
+        panic!("expected an update part");
+    };
+    assert_eq!((offset, total_size), (9500, 10_000));
+    assert_eq!(*data, image[9500..]);
+
+    let Response::UpdatePart { data, .. } = server.chunk(0, 4096, Compression::None, None) else {
+        panic!("expected an update part");
+    };
+    assert_eq!(data.len(), 1000);
+
+    let Response::UpdatePart { data, .. } = server.chunk(0, 16, Compression::None, None) else {
+        panic!("expected an update part");
+    };
+    assert_eq!(data.len(), 16);
+}
+
+#[test]
+fn compresses_chunks_on_request() {
+    let image = image();
+    let server = server(&image);
+
+    let Response::UpdatePart {
+        compression, data, ..
+    } = server.chunk(0, 4096, Compression::Lzss, None)
+    else {
+        panic!("expected an update part");
+    };
+    assert_eq!(compression, Compression::Lzss);
+    assert!(data.len() < 4096);
+
+    let mut output = vec![0; 4096];
+    assert_eq!(compression.decompress(&data, &mut output), Ok(4096));
+    assert_eq!(output, image[..4096]);
+}
+
+#[test]
+fn ends_after_last_chunk() {
+    let server = server(&image());
+
+    assert_eq!(
+        server.chunk(10_000, 64, Compression::None, None),
+        Response::UpdateEnd
+    );
+    assert_eq!(
+        server.chunk(10_001, 64, Compression::None, None),
+        Response::InvalidRequest
+    );
+    assert_eq!(
+        server.chunk(0, 0, Compression::None, None),
+        Response::InvalidRequest
+    );
+}
+
+#[test]
+fn offers_delta_patches() {
+    let image = image();
+    let server = delta_server(&image);
+    let full = server.manifest().clone();
+
+    assert_eq!(server.delta_bases().collect::<Vec<_>>(), [RUNNING]);
+    assert_eq!(
+        server.check_update(RUNNING, false),
+        Response::UpdateAvailable(full.clone())
+    );
+    assert_eq!(
+        server.check_update(Version::new(1, 0, 1), true),
+        Response::UpdateAvailable(full)
+    );
+
+    let Response::UpdateAvailable(announced) = server.check_update(RUNNING, true) else {
+        panic!("expected an update");
+    };
+    let delta = announced.manifest_unverified().delta.unwrap();
+    assert_eq!(delta.base, RUNNING);
+
+    let Response::UpdatePart { total_size, .. } =
+        server.chunk(0, 64, Compression::None, Some(RUNNING))
+    else {
+        panic!("expected an update part");
+    };
+    assert_eq!(total_size, delta.size);
+    assert_eq!(
+        server.chunk(0, 64, Compression::None, Some(Version::new(1, 0, 1))),
+        Response::InvalidRequest
+    );
+}
+
+#[test]
+fn rejects_mismatching_delta() {
+    let image = image();
+    let base = running_image();
+    let patch = diff(&base, &image);
+    let delta = DeltaManifest {
+        base: RUNNING,
+        base_sha256: sha256(&base),
+        size: patch.len() as u32,
+        sha256: sha256(&patch),
+    };
+
+    assert_eq!(
+        FirmwareServer::new(image.as_slice(), manifest(&image, Some(delta))).unwrap_err(),
+        FirmwareServerError::ManifestMismatch
+    );
+    assert_eq!(
+        server(&image)
+            .with_delta(&patch[1..], manifest(&image, Some(delta)))
+            .unwrap_err(),
+        FirmwareServerError::DeltaMismatch
+    );
+    assert_eq!(
+        server(&image)
+            .with_delta(patch.as_slice(), manifest(&image, None))
+            .unwrap_err(),
+        FirmwareServerError::DeltaMismatch
+    );
+    assert_eq!(
+        server(&image)
+            .with_delta(patch.as_slice(), manifest(&base, Some(delta)))
+            .unwrap_err(),
+        FirmwareServerError::DeltaMismatch
+    );
+}
+
+#[test]
+fn ignores_unrelated_requests() {
+    let server = server(&image());
+
+    assert_eq!(server.respond(&Request::Ping), None);
+    assert_eq!(server.max_chunk_size(), DEFAULT_MAX_CHUNK_SIZE);
+}
+
+#[test]
+fn rejects_mismatching_manifest() {
+    let image = image();
+    let manifest = server(&image).manifest().clone();
+
+    assert_eq!(
+        FirmwareServer::new(&image[1..], manifest).unwrap_err(),
+        FirmwareServerError::ManifestMismatch
+    );
+}
+
+/// Runs a complete update through both session state machines, and returns the new image.
+fn update(delta: bool) -> Vec<u8> {
+    let firmware = delta_server(&image()).with_max_chunk_size(1024);
+    let mut node = NodeSession::new();
+    let mut server = ServerSession::new();
+
+    // Every message goes through the wire format and both session state machines.
+    let mut exchange = |id: u32, request: Request| -> Response {
+        node.send_request(&request).unwrap();
+        let request = Message::deserialize(&Message::new_request(request, id).serialize())
+            .unwrap()
+            .take_request()
+            .unwrap();
+        server.receive_request(&request).unwrap();
+
+        let response = match request {
+            Request::Handshake { .. } => Response::HandshakeAccepted {
+                protocol: PROTOCOL_REVISION,
+            },
+            request => firmware.respond(&request).unwrap(),
+        };
+        server.send_response(&response).unwrap();
+        let response = Message::deserialize(&Message::new_reply(response, id, id).serialize())
+            .unwrap()
+            .take_response()
+            .unwrap();
+        node.receive_response(&response).unwrap();
+
+        response
+    };
+
+    exchange(
+        0,
+        Request::Handshake {
+            mac: Mac::new(1, 2, 3, 4, 5, 6),
+            protocol: PROTOCOL_REVISION,
+            firmware: RUNNING,
+        },
+    );
+    let announcement = exchange(
+        1,
+        Request::UpdateCheck {
+            firmware: RUNNING,
+            channel: ReleaseChannel::Stable,
+            board: "esp32s3".into(),
+            delta,
+        },
+    );
+    let Response::UpdateAvailable(manifest) = &announcement else {
+        panic!("expected an update");
+    };
+    assert_eq!(manifest.manifest_unverified().delta.is_some(), delta);
+
+    // Patches are applied to the running firmware while they're downloaded.
+    if delta {
+        let base = running_image();
+        let sink = DeltaApplier::new(base.as_slice(), MemorySink::new());
+        download(&announcement, sink, exchange)
+            .into_inner()
+            .1
+            .into_inner()
+    } else {
+        download(&announcement, MemorySink::new(), exchange).into_inner()
+    }
+}
+
+fn download<S: FirmwareSink>(
+    announcement: &Response,
+    sink: S,
+    mut exchange: impl FnMut(u32, Request) -> Response,
+) -> S
+where
+    S::Error: Debug,
+{
+    let mut assembler =
+        OtaAssembler::new(announcement, &key().verifying_key(), sink, 4096).unwrap();
+
+    for id in 2.. {
+        let response = exchange(id, assembler.next_request());
+        if response == Response::UpdateEnd {
+            break;
+        }
+
+        assembler.push_part(&response).unwrap();
+    }
+
+    assembler.finish(&Response::UpdateEnd).unwrap()
+}
+
+#[test]
+fn end_to_end_update() {
+    assert_eq!(update(false), image());
+}
+
+#[test]
+fn end_to_end_delta_update() {
+    assert_eq!(update(true), image());
+}
@@ -0,0 +1,131 @@
+use pwmp_msg::{
+    framing::{FrameCodec, FrameError, HEADER_SIZE},
+    request::Request,
+    response::Response,
+    Message,
+};
+use std::io::Cursor;
+
+fn encode_all(codec: &FrameCodec, messages: &[Message]) -> Vec<u8> {
+    let mut stream = Vec::new();
+
+    for message in messages {
+        codec.encode(message, &mut stream).unwrap();
+    }
+
+    stream
+}
+
+#[test]
+fn header_contains_payload_length() {
+    let codec = FrameCodec::new();
+    let message = Message::new_request(Request::SendNotification("Hello".into()), 7);
+    let stream = encode_all(&codec, std::slice::from_ref(&message));
+
+    let payload = message.serialize();
+    assert_eq!(stream.len(), HEADER_SIZE + payload.len());
+    assert_eq!(
+        stream[..HEADER_SIZE],
+        u32::try_from(payload.len()).unwrap().to_be_bytes()
+    );
+    assert_eq!(stream[HEADER_SIZE..], *payload);
+}
+
+#[test]
+fn decodes_byte_by_byte() {
+    let mut codec = FrameCodec::new();
+    let message = Message::new_response(Response::update_part(0, 4096, vec![0xAB; 100].into()), 3);
+    let stream = encode_all(&codec, std::slice::from_ref(&message));
+
+    for (i, byte) in stream.iter().enumerate() {
+        assert_eq!(codec.decode(), Ok(None), "decoded early at byte {i}");
+        codec.push(&[*byte]);
+    }
+
+    assert_eq!(codec.decode(), Ok(Some(message)));
+    assert_eq!(codec.buffered(), 0);
+}
+
+#[test]
+fn decodes_multiple_frames_from_one_read() {
+    let mut codec = FrameCodec::new();
+    let messages = [
+        Message::new_request(Request::Ping, 1),
+        Message::new_response(Response::Pong, 2),
+        Message::new_request(Request::Bye, 3),
+    ];
+    let mut stream = encode_all(&codec, &messages);
+
+    // Start of a fourth frame.
+    stream.extend_from_slice(&[0, 0]);
+    codec.push(&stream);
+
+    for message in messages {
+        assert_eq!(codec.decode(), Ok(Some(message)));
+    }
+    assert_eq!(codec.decode(), Ok(None));
+    assert_eq!(codec.buffered(), 2);
+}
+
+#[test]
+fn rejects_oversized_frame_on_encode() {
+    let codec = FrameCodec::with_max_frame_size(8);
+    let message = Message::new_response(Response::update_part(0, 4096, vec![0; 64].into()), 1);
+    let mut stream = Vec::new();
+
+    assert!(matches!(
+        codec.encode(&message, &mut stream),
+        Err(FrameError::TooLarge { max: 8, .. })
+    ));
+    assert!(stream.is_empty());
+}
+
+#[test]
+fn rejects_oversized_frame_from_header() {
+    let mut codec = FrameCodec::with_max_frame_size(1024);
+    codec.push(&4096u32.to_be_bytes());
+
+    assert_eq!(
+        codec.decode(),
+        Err(FrameError::TooLarge {
+            size: 4096,
+            max: 1024
+        })
+    );
+}
+
+#[test]
+fn skips_malformed_frame() {
+    let mut codec = FrameCodec::new();
+    let valid = Message::new_request(Request::Ping, 1);
+
+    codec.push(&2u32.to_be_bytes());
+    codec.push(&[0xFF, 0xFF]);
+    codec.push(&encode_all(&codec, std::slice::from_ref(&valid)));
+
+    assert!(matches!(codec.decode(), Err(FrameError::Decode(_))));
+    assert_eq!(codec.decode(), Ok(Some(valid)));
+}
+
+#[test]
+fn reads_and_writes_frames() {
+    let mut codec = FrameCodec::new();
+    let messages = [
+        Message::new_request(Request::GetSettings, 10),
+        Message::new_response(Response::Settings(None), 11),
+    ];
+    let mut stream = Vec::new();
+
+    for message in &messages {
+        codec.write_frame(&mut stream, message).unwrap();
+    }
+
+    let mut reader = Cursor::new(stream);
+    for message in messages {
+        assert_eq!(codec.read_frame(&mut reader).unwrap(), message);
+    }
+    assert_eq!(
+        codec.read_frame(&mut reader),
+        Err(FrameError::UnexpectedEof)
+    );
+}
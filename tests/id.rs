@@ -0,0 +1,99 @@
+use pwmp_msg::id::{DedupCache, IdGenerator};
+use std::time::Duration;
+
+const fn secs(secs: u64) -> Duration {
+    Duration::from_secs(secs)
+}
+
+#[test]
+fn generates_sequential_ids() {
+    let mut ids = IdGenerator::new();
+
+    assert_eq!(ids.next_id(), 0);
+    assert_eq!(ids.next_id(), 1);
+    assert_eq!(ids.peek(), 2);
+    assert_eq!(ids.next_id(), 2);
+}
+
+#[test]
+fn wraps_around() {
+    let mut ids = IdGenerator::starting_at(u32::MAX);
+
+    assert_eq!(ids.next_id(), u32::MAX);
+    assert_eq!(ids.next_id(), 0);
+    assert_eq!(ids.next_id(), 1);
+}
+
+#[test]
+fn random_start_is_sequential() {
+    let mut ids = IdGenerator::random();
+    let first = ids.next_id();
+
+    assert_eq!(ids.next_id(), first.wrapping_add(1));
+}
+
+#[test]
+fn flags_duplicates() {
+    let mut cache = DedupCache::new(8, secs(60));
+
+    assert!(cache.insert(10, secs(0)));
+    assert!(cache.insert(11, secs(1)));
+    assert!(!cache.insert(10, secs(2)));
+    assert!(!cache.insert(11, secs(3)));
+    assert_eq!(cache.len(), 2);
+}
+
+#[test]
+fn evicts_after_window() {
+    let mut cache = DedupCache::new(8, secs(10));
+    cache.insert(1, secs(0));
+    cache.insert(2, secs(5));
+
+    // Duplicates don't refresh the entry.
+    assert!(!cache.insert(1, secs(10)));
+    assert!(cache.insert(1, secs(11)));
+    assert!(!cache.insert(2, secs(15)));
+
+    cache.evict_expired(secs(30));
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn evicts_oldest_when_full() {
+    let mut cache = DedupCache::new(3, secs(60));
+
+    for id in 0..4 {
+        assert!(cache.insert(id, secs(0)));
+    }
+
+    assert_eq!(cache.len(), 3);
+    assert!(!cache.contains(0));
+    assert!(cache.contains(3));
+    assert!(cache.insert(0, secs(1)));
+}
+
+#[test]
+fn zero_capacity_remembers_nothing() {
+    let mut cache = DedupCache::new(0, secs(60));
+
+    assert!(cache.insert(5, secs(0)));
+    assert!(cache.insert(5, secs(0)));
+}
+
+#[test]
+fn wrapped_ids_are_not_duplicates() {
+    let mut ids = IdGenerator::starting_at(u32::MAX - 1);
+    let mut cache = DedupCache::new(4, secs(5));
+    let mut now = secs(0);
+
+    for _ in 0..4 {
+        assert!(cache.insert(ids.next_id(), now));
+        now += secs(2);
+    }
+
+    // The generator has wrapped around and reuses IDs, which have been forgotten by now.
+    let mut reused = IdGenerator::starting_at(u32::MAX - 1);
+    now += secs(10);
+    assert!(cache.insert(reused.next_id(), now));
+    assert!(!cache.insert(u32::MAX - 1, now));
+}
@@ -0,0 +1,133 @@
+use pwmp_msg::{
+    manifest::{DeltaManifest, FirmwareManifest, ManifestError, SignedManifest, SigningKey},
+    ota::{crc32, sha256},
+    response::Response,
+    version::Version,
+    Message,
+};
+
+const IMAGE: &[u8] = b"firmware image built for the esp32s3 target";
+
+fn release_key() -> SigningKey {
+    SigningKey::from_bytes(&[42; 32])
+}
+
+fn manifest() -> FirmwareManifest {
+    FirmwareManifest {
+        version: Version::new(2, 1, 0),
+        size: IMAGE.len() as u32,
+        sha256: sha256(IMAGE),
+        min_version: Version::new(2, 0, 0),
+        target: "esp32s3".into(),
+        delta: None,
+    }
+}
+
+#[test]
+fn verifies_signed_manifest() {
+    let signed = manifest().sign(&release_key());
+
+    assert_eq!(
+        signed.verify(&release_key().verifying_key()),
+        Ok(&manifest())
+    );
+}
+
+#[test]
+fn signature_survives_transfer() {
+    let signed = manifest().sign(&release_key());
+    let bytes = Message::new_response(Response::UpdateAvailable(signed), 1).serialize();
+
+    let message = Message::deserialize(&bytes).unwrap();
+    let Some(Response::UpdateAvailable(received)) = message.response() else {
+        panic!("unexpected content: {message:?}");
+    };
+
+    assert!(received.verify(&release_key().verifying_key()).is_ok());
+}
+
+#[test]
+fn rejects_foreign_key() {
+    let signed = manifest().sign(&SigningKey::from_bytes(&[13; 32]));
+
+    assert_eq!(
+        signed.verify(&release_key().verifying_key()),
+        Err(ManifestError::InvalidSignature)
+    );
+}
+
+#[test]
+fn rejects_modified_manifest() {
+    let signed = manifest().sign(&release_key());
+    let mut modified = signed.manifest_unverified().clone();
+    modified.size += 1;
+    let forged = SignedManifest::from_parts(modified, *signed.signature());
+
+    assert_eq!(
+        forged.verify(&release_key().verifying_key()),
+        Err(ManifestError::InvalidSignature)
+    );
+}
+
+#[test]
+fn checks_compatibility() {
+    let manifest = manifest();
+
+    assert_eq!(
+        manifest.check_compatibility(Version::new(2, 0, 5), "esp32s3"),
+        Ok(())
+    );
+    assert_eq!(
+        manifest.check_compatibility(Version::new(1, 9, 9), "esp32s3"),
+        Err(ManifestError::UnsupportedVersion {
+            running: Version::new(1, 9, 9),
+            min_version: Version::new(2, 0, 0)
+        })
+    );
+    assert_eq!(
+        manifest.check_compatibility(Version::new(2, 0, 0), "esp32c3"),
+        Err(ManifestError::WrongTarget {
+            expected: "esp32s3".into()
+        })
+    );
+}
+
+#[test]
+fn checks_downloaded_image() {
+    let manifest = manifest();
+    let mut verifier = manifest.verifier();
+    verifier.verify_chunk(0, crc32(IMAGE), IMAGE).unwrap();
+
+    assert_eq!(verifier.finish(), Ok(()));
+    assert!(manifest.matches_image(IMAGE));
+    assert!(!manifest.matches_image(&IMAGE[1..]));
+}
+
+#[test]
+fn delta_patch_is_downloaded_instead() {
+    let patch = b"patch from 2.0.0";
+    let mut manifest = manifest();
+    manifest.delta = Some(DeltaManifest {
+        base: Version::new(2, 0, 0),
+        base_sha256: [0; 32],
+        size: patch.len() as u32,
+        sha256: sha256(patch),
+    });
+
+    let mut verifier = manifest.verifier();
+    verifier.verify_chunk(0, crc32(patch), patch).unwrap();
+
+    assert_eq!(verifier.finish(), Ok(()));
+    assert_eq!(manifest.download_size(), patch.len() as u32);
+    assert!(manifest.matches_image(IMAGE));
+    assert_eq!(
+        manifest.check_compatibility(Version::new(2, 0, 0), "esp32s3"),
+        Ok(())
+    );
+    assert_eq!(
+        manifest.check_compatibility(Version::new(2, 0, 5), "esp32s3"),
+        Err(ManifestError::WrongBase {
+            expected: Version::new(2, 0, 0)
+        })
+    );
+}
@@ -0,0 +1,297 @@
+use pwmp_msg::{
+    aliases::{AirPressure, BatteryVoltage, Humidity, Rssi, Temperature, ValueError},
+    diagnostics::Diagnostics,
+    measurement::{
+        MeasurementBatch, MeasurementBatchRef, Measurements, MeasurementsRef, Reading, Sample,
+        SampleTime, SensorKind,
+    },
+    request::{InvalidField, Request, RequestRef},
+    Message, MessageRef,
+};
+
+fn readings() -> [Reading; 4] {
+    [
+        Reading::new(SensorKind::Temperature, 21.5),
+        Reading::new(SensorKind::Temperature, -4.25).with_index(1),
+        Reading::new(SensorKind::Pm10, 17.0),
+        Reading::new(SensorKind::Rainfall, 0.2).with_index(0),
+    ]
+}
+
+#[test]
+fn roundtrips_readings() {
+    let measurements: Measurements = readings().into_iter().collect();
+
+    assert_eq!(measurements.len(), 4);
+    assert!(measurements.iter().eq(readings()));
+    assert_eq!(
+        measurements.first(SensorKind::Temperature),
+        Some(readings()[0])
+    );
+    assert_eq!(measurements.first(SensorKind::Co2), None);
+
+    // 5 bytes per reading, plus 1 byte for each index.
+    assert_eq!(measurements.as_bytes().len(), 4 * 5 + 2);
+}
+
+#[test]
+fn borrows_readings_from_buffer() {
+    let request = Request::PostMeasurements(readings().into_iter().collect());
+    let bytes = Message::new_request(request.clone(), 1).serialize();
+    let message = MessageRef::deserialize(&bytes).unwrap();
+
+    let Some(RequestRef::PostMeasurements(measurements)) = message.request() else {
+        panic!("unexpected content: {message:?}");
+    };
+    assert!(measurements.iter().eq(readings()));
+    assert_eq!(Request::from(*message.request().unwrap()), request);
+}
+
+#[test]
+fn keeps_unknown_sensor_kinds() {
+    let bytes = [0x7F, 0, 0, 0x80, 0x3F];
+    let measurements = MeasurementsRef::from_bytes(&bytes).unwrap();
+
+    assert_eq!(
+        measurements.iter().collect::<Vec<_>>(),
+        [Reading::new(SensorKind::from_code(0x7F).unwrap(), 1.0)]
+    );
+    assert_eq!(
+        Measurements::from(measurements)
+            .iter()
+            .next()
+            .unwrap()
+            .kind
+            .code(),
+        0x7F
+    );
+
+    for code in 0..=0x7F {
+        assert_eq!(SensorKind::from_code(code).unwrap().code(), code);
+    }
+}
+
+#[test]
+fn never_creates_unknown_kinds_with_invalid_codes() {
+    // Codes of known kinds are never unknown, so they can't be sent as a different kind.
+    assert_eq!(SensorKind::from_code(3), Some(SensorKind::Co2));
+    assert!(!matches!(
+        SensorKind::from_code(11),
+        Some(SensorKind::Other(..))
+    ));
+
+    // Codes that don't fit into 7 bits would collide with other codes on the wire.
+    assert_eq!(SensorKind::from_code(200), None);
+    assert_eq!(SensorKind::from_code(0x80), None);
+
+    for code in 0..=0x7F {
+        let kind = SensorKind::from_code(code).unwrap();
+        let measurements: Measurements = [Reading::new(kind, 1.0)].into_iter().collect();
+
+        assert_eq!(measurements.first(kind), Some(Reading::new(kind, 1.0)));
+    }
+}
+
+#[test]
+fn rejects_truncated_readings() {
+    assert!(MeasurementsRef::from_bytes(&[]).unwrap().is_empty());
+    assert!(MeasurementsRef::from_bytes(&[0, 0, 0, 0]).is_none());
+    assert!(MeasurementsRef::from_bytes(&[0x80, 1, 0, 0, 0]).is_none());
+
+    let measurements: Measurements = readings().into_iter().collect();
+    let packed_len = measurements.as_bytes().len();
+    let mut bytes = Message::new_request(Request::PostMeasurements(measurements), 1)
+        .serialize()
+        .into_vec();
+
+    // The packed readings are at the end of the message. Drop their last byte, and shorten
+    // their length prefix accordingly, so that only the last reading is truncated.
+    let prefix = bytes.len() - packed_len - 1;
+    bytes[prefix] -= 1;
+    bytes.pop();
+
+    assert!(Message::deserialize(&bytes).is_err());
+    assert!(MessageRef::deserialize(&bytes).is_err());
+}
+
+#[test]
+fn converts_results_into_readings() {
+    let request = Request::PostResults {
+        temperature: Temperature::from_centi_celsius(2150),
+        humidity: Humidity::new(40).unwrap(),
+        air_pressure: Some(AirPressure::new(1013).unwrap()),
+    };
+
+    assert!(request.measurements().unwrap().iter().eq([
+        Reading::new(SensorKind::Temperature, 21.5),
+        Reading::new(SensorKind::Humidity, 40.0),
+        Reading::new(SensorKind::AirPressure, 1013.0),
+    ]));
+    assert_eq!(Request::Ping.measurements(), None);
+}
+
+fn batch() -> MeasurementBatch {
+    let measurements: Measurements = readings().into_iter().collect();
+    let empty = Measurements::default();
+
+    [
+        Sample::new(SampleTime::Timestamp(1_700_000_000), &measurements),
+        Sample::new(SampleTime::Age(u32::MAX), &empty),
+        Sample::new(SampleTime::Age(60), &measurements),
+    ]
+    .into_iter()
+    .collect()
+}
+
+#[test]
+fn roundtrips_batches() {
+    let batch = batch();
+    let request = Request::PostMeasurementBatch(batch.clone());
+    let bytes = Message::new_request(request.clone(), 1).serialize();
+
+    assert_eq!(
+        Message::deserialize(&bytes).unwrap().take_request(),
+        Some(request)
+    );
+
+    let message = MessageRef::deserialize(&bytes).unwrap();
+    let Some(RequestRef::PostMeasurementBatch(borrowed)) = message.request() else {
+        panic!("unexpected content: {message:?}");
+    };
+    assert_eq!(borrowed.len(), 3);
+    assert!(borrowed.iter().eq(batch.iter()));
+
+    let times: Vec<_> = batch.iter().map(|sample| sample.time).collect();
+    assert_eq!(
+        times,
+        [
+            SampleTime::Timestamp(1_700_000_000),
+            SampleTime::Age(u32::MAX),
+            SampleTime::Age(60)
+        ]
+    );
+    assert!(batch
+        .iter()
+        .nth(2)
+        .unwrap()
+        .measurements
+        .iter()
+        .eq(readings()));
+}
+
+#[test]
+fn resolves_sample_times() {
+    assert_eq!(SampleTime::Timestamp(1000).to_timestamp(5000), 1000);
+    assert_eq!(SampleTime::Age(600).to_timestamp(5000), 4400);
+    assert_eq!(SampleTime::Age(6000).to_timestamp(5000), 0);
+}
+
+#[test]
+fn rejects_invalid_samples() {
+    assert!(MeasurementBatchRef::from_bytes(&[]).unwrap().is_empty());
+
+    // An unknown time tag.
+    assert!(MeasurementBatchRef::from_bytes(&[2, 0, 0]).is_none());
+    // An age that doesn't fit into 32 bits.
+    assert!(MeasurementBatchRef::from_bytes(&[1, 0x80, 0x80, 0x80, 0x80, 0x10, 0]).is_none());
+    // A timestamp that doesn't fit into 64 bits.
+    assert!(MeasurementBatchRef::from_bytes(&[
+        0, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x02, 0
+    ])
+    .is_none());
+    // Readings that are longer than the rest of the batch.
+    assert!(MeasurementBatchRef::from_bytes(&[0, 0, 6, 0, 0, 0, 0, 0]).is_none());
+    // A truncated reading.
+    assert!(MeasurementBatchRef::from_bytes(&[0, 0, 4, 0, 0, 0, 0]).is_none());
+
+    assert_eq!(
+        MeasurementBatchRef::from_bytes(&[
+            0, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01, 0
+        ])
+        .unwrap()
+        .iter()
+        .next()
+        .unwrap()
+        .time,
+        SampleTime::Timestamp(u64::MAX)
+    );
+}
+
+#[test]
+fn validates_readings() {
+    for reading in readings() {
+        assert_eq!(reading.validate(), Ok(()));
+    }
+
+    let invalid = |kind, value| Reading::new(kind, value).validate();
+    assert_eq!(
+        invalid(SensorKind::Temperature, f32::NAN),
+        Err(ValueError::NotFinite)
+    );
+    assert_eq!(
+        invalid(SensorKind::from_code(100).unwrap(), f32::INFINITY),
+        Err(ValueError::NotFinite)
+    );
+    assert_eq!(
+        invalid(SensorKind::Humidity, 250.0),
+        Err(ValueError::OutOfRange)
+    );
+    assert_eq!(
+        invalid(SensorKind::AirPressure, 12.0),
+        Err(ValueError::OutOfRange)
+    );
+    assert_eq!(invalid(SensorKind::Co2, -1.0), Err(ValueError::OutOfRange));
+    assert_eq!(
+        invalid(SensorKind::WindDirection, 361.0),
+        Err(ValueError::OutOfRange)
+    );
+    assert_eq!(invalid(SensorKind::from_code(100).unwrap(), -1e30), Ok(()));
+}
+
+#[test]
+fn validates_requests() {
+    let valid: Measurements = readings().into_iter().collect();
+    let invalid: Measurements = [Reading::new(SensorKind::Humidity, 250.0).with_index(1)]
+        .into_iter()
+        .collect();
+
+    assert_eq!(Request::PostMeasurements(valid.clone()).validate(), Ok(()));
+    assert_eq!(
+        Request::PostMeasurements(invalid.clone()).validate(),
+        Err(InvalidField {
+            field: "measurements",
+            error: ValueError::OutOfRange
+        })
+    );
+
+    assert_eq!(Request::PostMeasurementBatch(batch()).validate(), Ok(()));
+    let batch: MeasurementBatch = [
+        Sample::new(SampleTime::Age(60), &valid),
+        Sample::new(SampleTime::Age(0), &invalid),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(
+        Request::PostMeasurementBatch(batch).validate(),
+        Err(InvalidField {
+            field: "measurement_batch",
+            error: ValueError::OutOfRange
+        })
+    );
+
+    let stats = |wifi_ssid: &str| Request::PostStats {
+        battery: BatteryVoltage::from_millivolts(3700),
+        wifi_ssid: wifi_ssid.into(),
+        wifi_rssi: Rssi::new(-60).unwrap(),
+        diagnostics: Diagnostics::new(1_000),
+    };
+    assert_eq!(stats(&"a".repeat(32)).validate(), Ok(()));
+    assert_eq!(
+        stats(&"a".repeat(33)).validate(),
+        Err(InvalidField {
+            field: "wifi_ssid",
+            error: ValueError::OutOfRange
+        })
+    );
+    assert_eq!(Request::Ping.validate(), Ok(()));
+}
@@ -0,0 +1,256 @@
+mod common;
+
+use common::manifest;
+use pwmp_msg::{
+    aliases::{AirPressure, BatteryVoltage, Humidity, Rssi, Temperature},
+    compression::Compression,
+    diagnostics::{Diagnostics, ResetReason, WakeReason},
+    error::DecodeError,
+    mac::Mac,
+    measurement::{Measurements, Reading, Sample, SampleTime, SensorKind},
+    ota::RollbackReason,
+    protocol::{RejectReason, PROTOCOL_REVISION},
+    request::{Request, RequestRef},
+    response::{Response, ResponseRef},
+    rollout::ReleaseChannel,
+    settings::NodeSettings,
+    version::Version,
+    Message, MessageRef,
+};
+
+fn all_messages() -> Vec<Message> {
+    let requests = [
+        Request::Ping,
+        Request::Handshake {
+            mac: Mac::new(0, 1, 2, 3, 4, 5),
+            protocol: PROTOCOL_REVISION,
+            firmware: Version::new(1, 0, 0),
+        },
+        Request::PostResults {
+            temperature: Temperature::from_centi_celsius(2150),
+            humidity: Humidity::new(40).unwrap(),
+            air_pressure: Some(AirPressure::new(1013).unwrap()),
+        },
+        Request::PostMeasurements(
+            [
+                Reading::new(SensorKind::Co2, 612.0),
+                Reading::new(SensorKind::WindSpeed, 3.5).with_index(2),
+            ]
+            .into_iter()
+            .collect(),
+        ),
+        Request::PostMeasurementBatch(
+            [Sample::new(
+                SampleTime::Age(600),
+                &[Reading::new(SensorKind::Humidity, 45.0)]
+                    .into_iter()
+                    .collect::<Measurements>(),
+            )]
+            .into_iter()
+            .collect(),
+        ),
+        Request::PostStats {
+            battery: BatteryVoltage::from_millivolts(3700),
+            wifi_ssid: "Weather".into(),
+            wifi_rssi: Rssi::new(-60).unwrap(),
+            diagnostics: Diagnostics {
+                reset_reason: Some(ResetReason::Wake(WakeReason::Timer)),
+                free_heap: Some(182_340),
+                min_free_heap: Some(151_208),
+                wifi_channel: Some(11),
+                bssid: Some(Mac::new(0x02, 0x00, 0x5E, 0x10, 0x20, 0x30)),
+                connect_time: Some(1_840),
+                failed_sessions: Some(2),
+                ..Diagnostics::new(5_312)
+            },
+        },
+        Request::SendNotification("Battery low".into()),
+        Request::GetSettings,
+        Request::UpdateCheck {
+            firmware: Version::new(1, 2, 3),
+            channel: ReleaseChannel::Custom("greenhouse".into()),
+            board: "pws-v2".into(),
+            delta: false,
+        },
+        Request::NextUpdateChunk {
+            offset: 0,
+            max_size: 512,
+            compression: Compression::Lzss,
+            base: Some(Version::new(1, 2, 3)),
+        },
+        Request::ReportFirmwareUpdate {
+            previous: Version::new(1, 0, 0),
+            new: Version::new(1, 1, 0),
+            rollback: Some(RollbackReason::BootLoop),
+            diagnostic: Some("boot count exceeded".into()),
+        },
+        Request::Bye,
+    ];
+    let responses = [
+        Response::Pong,
+        Response::Ok,
+        Response::Reject(RejectReason::Unauthorized),
+        Response::InvalidRequest,
+        Response::RateLimitExceeded,
+        Response::InternalServerError,
+        Response::Stalling,
+        Response::FirmwareUpToDate,
+        Response::UpdateAvailable(manifest(Version::new(2, 0, 0))),
+        Response::update_part(0, 4096, vec![1, 2, 3, 4].into()),
+        Response::compressed_update_part(4, 4096, &[0; 512], Compression::Lzss),
+        Response::UpdateEnd,
+        Response::Settings(Some(NodeSettings::default())),
+        Response::HandshakeAccepted {
+            protocol: PROTOCOL_REVISION,
+        },
+    ];
+
+    requests
+        .into_iter()
+        .map(|req| Message::new_request(req, 1))
+        .chain(
+            responses
+                .into_iter()
+                .map(|res| Message::new_response(res, 2)),
+        )
+        .collect()
+}
+
+#[test]
+fn decodes_every_owned_message() {
+    for message in all_messages() {
+        let bytes = message.clone().serialize();
+        let borrowed = MessageRef::deserialize(&bytes).unwrap();
+
+        assert_eq!(borrowed.id(), message.id());
+        assert_eq!(borrowed.into_owned(), message);
+    }
+}
+
+#[test]
+fn encodes_identically() {
+    let mut buffer = [0; 128];
+
+    for message in all_messages() {
+        let bytes = message.clone().serialize();
+        let borrowed = MessageRef::from(&message);
+
+        assert_eq!(*borrowed.serialize_into(&mut buffer).unwrap(), *bytes);
+    }
+}
+
+#[test]
+fn borrows_strings() {
+    let message = Message::new_request(
+        Request::PostStats {
+            battery: BatteryVoltage::from_millivolts(4100),
+            wifi_ssid: "Weather".into(),
+            wifi_rssi: Rssi::new(-70).unwrap(),
+            diagnostics: Diagnostics::new(12_000),
+        },
+        5,
+    );
+    let bytes = message.serialize();
+    let borrowed = MessageRef::deserialize(&bytes).unwrap();
+
+    let Some(RequestRef::PostStats { wifi_ssid, .. }) = borrowed.request() else {
+        panic!("unexpected content: {borrowed:?}");
+    };
+    assert_eq!(*wifi_ssid, "Weather");
+    assert!(bytes.as_ptr_range().contains(&wifi_ssid.as_ptr()));
+}
+
+#[test]
+fn borrows_update_parts() {
+    let bytes = Message::new_response(Response::update_part(0, 4096, vec![0x55; 4096].into()), 9)
+        .serialize();
+    let borrowed = MessageRef::deserialize(&bytes).unwrap();
+
+    assert_eq!(borrowed.request(), None);
+    let Some(ResponseRef::UpdatePart { data, .. }) = borrowed.response() else {
+        panic!("unexpected content: {borrowed:?}");
+    };
+    assert!(data.iter().all(|byte| *byte == 0x55));
+    assert!(bytes.as_ptr_range().contains(&data.as_ptr()));
+}
+
+#[test]
+fn keeps_reply_id() {
+    let message = Message::new_reply(Response::Pong, 2, 1);
+    let bytes = message.clone().serialize();
+    let borrowed = MessageRef::deserialize(&bytes).unwrap();
+
+    assert_eq!(borrowed.reply_to(), Some(1));
+    assert_eq!(borrowed.into_owned(), message);
+}
+
+#[test]
+fn decodes_responses_without_reply_id() {
+    // Responses of servers that don't append the ID of the answered request.
+    let reject = [1, 1, 2, 1, 1, 1];
+    let accepted = [1, 1, 12, 11];
+    let settings = [1, 1, 11, 0];
+
+    assert_eq!(
+        Message::deserialize(&reject),
+        Ok(Message::new_response(
+            Response::Reject(RejectReason::UnsupportedProtocol { min: 1, max: 1 }),
+            1
+        ))
+    );
+    assert_eq!(
+        Message::deserialize(&accepted),
+        Ok(Message::new_response(
+            Response::HandshakeAccepted { protocol: 11 },
+            1
+        ))
+    );
+    assert_eq!(
+        Message::deserialize(&settings),
+        Ok(Message::new_response(Response::Settings(None), 1))
+    );
+
+    let borrowed = MessageRef::deserialize(&reject).unwrap();
+    assert_eq!(
+        borrowed.response(),
+        Some(&ResponseRef::Reject(RejectReason::UnsupportedProtocol {
+            min: 1,
+            max: 1
+        }))
+    );
+    assert_eq!(borrowed.reply_to(), None);
+}
+
+#[test]
+fn rejects_truncated_messages() {
+    // A request with a missing trailing option.
+    let mut request = Message::new_request(
+        Request::ReportFirmwareUpdate {
+            previous: Version::new(1, 0, 0),
+            new: Version::new(1, 1, 0),
+            rollback: None,
+            diagnostic: None,
+        },
+        1,
+    )
+    .serialize()
+    .into_vec();
+    request.pop();
+    assert_eq!(
+        Message::deserialize(&request),
+        Err(DecodeError::UnexpectedEnd {
+            offset: request.len()
+        })
+    );
+
+    // A reply ID with a missing value.
+    let reply = [1, 1, 0, 1];
+    assert_eq!(
+        Message::deserialize(&reply),
+        Err(DecodeError::UnexpectedEnd { offset: 4 })
+    );
+    assert_eq!(
+        MessageRef::deserialize(&reply),
+        Err(DecodeError::UnexpectedEnd { offset: 4 })
+    );
+}
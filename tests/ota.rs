@@ -0,0 +1,94 @@
+use pwmp_msg::ota::{crc32, sha256, ImageVerifier, VerifyError};
+
+const IMAGE: &[u8] = b"This is a firmware image that is split into multiple chunks.";
+
+fn verifier() -> ImageVerifier {
+    ImageVerifier::new(IMAGE.len() as u32, sha256(IMAGE))
+}
+
+#[test]
+fn checksums_match_reference_values() {
+    assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    assert_eq!(
+        sha256(b"abc")[..4],
+        [0xBA, 0x78, 0x16, 0xBF],
+        "unexpected SHA-256 digest"
+    );
+}
+
+#[test]
+fn verifies_complete_image() {
+    let mut verifier = verifier();
+
+    for (i, chunk) in IMAGE.chunks(16).enumerate() {
+        verifier
+            .verify_chunk(i as u32 * 16, crc32(chunk), chunk)
+            .unwrap();
+    }
+
+    assert_eq!(verifier.received(), verifier.size());
+    assert_eq!(verifier.finish(), Ok(()));
+}
+
+#[test]
+fn rejects_corrupted_chunk() {
+    let mut verifier = verifier();
+    let checksum = crc32(&IMAGE[..16]);
+    let mut corrupted = IMAGE[..16].to_vec();
+    corrupted[3] ^= 0x01;
+
+    assert_eq!(
+        verifier.verify_chunk(0, checksum, &corrupted),
+        Err(VerifyError::ChecksumMismatch { offset: 0 })
+    );
+    assert_eq!(verifier.received(), 0);
+}
+
+#[test]
+fn rejects_out_of_order_chunk() {
+    let mut verifier = verifier();
+    let chunk = &IMAGE[16..32];
+
+    assert_eq!(
+        verifier.verify_chunk(16, crc32(chunk), chunk),
+        Err(VerifyError::UnexpectedOffset {
+            expected: 0,
+            actual: 16
+        })
+    );
+}
+
+#[test]
+fn rejects_data_past_announced_size() {
+    let mut verifier = ImageVerifier::new(8, sha256(&IMAGE[..8]));
+    let chunk = &IMAGE[..16];
+
+    assert_eq!(
+        verifier.verify_chunk(0, crc32(chunk), chunk),
+        Err(VerifyError::Overflow { size: 8 })
+    );
+}
+
+#[test]
+fn rejects_incomplete_image() {
+    let mut verifier = verifier();
+    verifier
+        .verify_chunk(0, crc32(&IMAGE[..16]), &IMAGE[..16])
+        .unwrap();
+
+    assert_eq!(
+        verifier.finish(),
+        Err(VerifyError::Incomplete {
+            received: 16,
+            size: IMAGE.len() as u32
+        })
+    );
+}
+
+#[test]
+fn rejects_digest_mismatch() {
+    let mut verifier = ImageVerifier::new(IMAGE.len() as u32, sha256(b"another image"));
+    verifier.verify_chunk(0, crc32(IMAGE), IMAGE).unwrap();
+
+    assert_eq!(verifier.finish(), Err(VerifyError::DigestMismatch));
+}
@@ -0,0 +1,44 @@
+use pwmp_msg::protocol::{RejectReason, RevisionRange, PROTOCOL_REVISION};
+
+#[test]
+fn current_revision_is_supported() {
+    assert!(RevisionRange::CURRENT.contains(PROTOCOL_REVISION));
+    assert_eq!(
+        RevisionRange::CURRENT.negotiate(PROTOCOL_REVISION),
+        Ok(PROTOCOL_REVISION)
+    );
+}
+
+#[test]
+fn bounds_are_inclusive() {
+    let range = RevisionRange::new(3, 5);
+
+    assert_eq!(range.negotiate(3), Ok(3));
+    assert_eq!(range.negotiate(5), Ok(5));
+}
+
+#[test]
+fn rejects_outside_of_range() {
+    let range = RevisionRange::new(3, 5);
+    let reason = RejectReason::UnsupportedProtocol { min: 3, max: 5 };
+
+    assert_eq!(range.negotiate(2), Err(reason));
+    assert_eq!(range.negotiate(6), Err(reason));
+}
+
+#[test]
+fn swapped_bounds() {
+    let range = RevisionRange::new(5, 3);
+
+    assert_eq!(range.min(), 3);
+    assert_eq!(range.max(), 5);
+}
+
+#[test]
+fn both_sides_agree() {
+    let server = RevisionRange::new(1, 4);
+    let node = RevisionRange::new(3, 3);
+
+    let agreed = server.negotiate(node.max()).unwrap();
+    assert_eq!(node.negotiate(agreed), Ok(3));
+}
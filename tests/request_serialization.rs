@@ -1,4 +1,16 @@
-use pwmp_msg::{mac::Mac, request::Request, version::Version, Message};
+use pwmp_msg::{
+    aliases::{AirPressure, BatteryVoltage, Humidity, Rssi, Temperature},
+    compression::Compression,
+    diagnostics::{Diagnostics, ResetReason, WakeReason},
+    mac::Mac,
+    measurement::{Measurements, Reading, Sample, SampleTime, SensorKind},
+    ota::RollbackReason,
+    protocol::PROTOCOL_REVISION,
+    request::Request,
+    rollout::ReleaseChannel,
+    version::Version,
+    Message,
+};
 
 macro_rules! generate_test {
     ($test_name: ident, $req: expr) => {
@@ -15,24 +27,79 @@ generate_test!(
     can_serialize_hello,
     Request::Handshake {
         mac: Mac::new(0, 1, 2, 3, 4, 5),
+        protocol: PROTOCOL_REVISION,
+        firmware: Version::new(1, 4, 2),
     }
 );
 
 generate_test!(
     can_serialize_post_results,
     Request::PostResults {
-        temperature: f32::default(),
-        humidity: 50,
-        air_pressure: Some(u16::MAX),
+        temperature: Temperature::from_centi_celsius(-1250),
+        humidity: Humidity::new(50).unwrap(),
+        air_pressure: Some(AirPressure::MAX),
     }
 );
 
+generate_test!(
+    can_serialize_post_measurements,
+    Request::PostMeasurements(
+        [
+            Reading::new(SensorKind::Temperature, 21.5),
+            Reading::new(SensorKind::Temperature, -4.25).with_index(1),
+            Reading::new(SensorKind::from_code(100).unwrap(), 1.0),
+        ]
+        .into_iter()
+        .collect()
+    )
+);
+
+generate_test!(
+    can_serialize_post_measurement_batch,
+    Request::PostMeasurementBatch(
+        [
+            Sample::new(
+                SampleTime::Timestamp(1_700_000_000),
+                &Measurements::default()
+            ),
+            Sample::new(
+                SampleTime::Age(3600),
+                &[Reading::new(SensorKind::Temperature, 21.5)]
+                    .into_iter()
+                    .collect::<Measurements>()
+            ),
+        ]
+        .into_iter()
+        .collect()
+    )
+);
+
 generate_test!(
     can_serialize_post_stats,
     Request::PostStats {
-        battery: f32::default(),
+        battery: BatteryVoltage::from_millivolts(3300),
+        wifi_ssid: "ABC 123".into(),
+        wifi_rssi: Rssi::new(-15).unwrap(),
+        diagnostics: Diagnostics::new(0),
+    }
+);
+
+generate_test!(
+    can_serialize_post_stats_with_diagnostics,
+    Request::PostStats {
+        battery: BatteryVoltage::from_millivolts(3300),
         wifi_ssid: "ABC 123".into(),
-        wifi_rssi: -15,
+        wifi_rssi: Rssi::new(-15).unwrap(),
+        diagnostics: Diagnostics {
+            reset_reason: Some(ResetReason::Wake(WakeReason::Timer)),
+            free_heap: Some(182_340),
+            min_free_heap: Some(151_208),
+            wifi_channel: Some(11),
+            bssid: Some(Mac::new(0x02, 0x00, 0x5E, 0x10, 0x20, 0x30)),
+            connect_time: Some(1_840),
+            failed_sessions: Some(2),
+            ..Diagnostics::new(5_312)
+        },
     }
 );
 
@@ -45,17 +112,40 @@ generate_test!(can_serialize_get_settings, Request::GetSettings);
 
 generate_test!(
     can_serialize_update_check,
-    Request::UpdateCheck(Version::new(1, 0, 1))
+    Request::UpdateCheck {
+        firmware: Version::new(1, 0, 1),
+        channel: ReleaseChannel::Beta,
+        board: "pws-v2".into(),
+        delta: true,
+    }
 );
 
-generate_test!(can_serialize_update_chunk, Request::NextUpdateChunk(256));
+generate_test!(
+    can_serialize_update_chunk,
+    Request::NextUpdateChunk {
+        offset: 1024,
+        max_size: 256,
+        compression: Compression::None,
+        base: Some(Version::new(1, 0, 0)),
+    }
+);
 
 generate_test!(
     can_serialize_report_pos_fw_update,
-    Request::ReportFirmwareUpdate(true)
+    Request::ReportFirmwareUpdate {
+        previous: Version::new(1, 0, 0),
+        new: Version::new(1, 1, 0),
+        rollback: None,
+        diagnostic: None,
+    }
 );
 
 generate_test!(
     can_serialize_report_neg_fw_update,
-    Request::ReportFirmwareUpdate(false)
+    Request::ReportFirmwareUpdate {
+        previous: Version::new(1, 0, 0),
+        new: Version::new(1, 1, 0),
+        rollback: Some(RollbackReason::WatchdogReset),
+        diagnostic: Some("task wdt: sensor_task".into()),
+    }
 );
@@ -1,4 +1,13 @@
-use pwmp_msg::{response::Response, settings::NodeSettings, version::Version, Message};
+mod common;
+
+use common::manifest;
+use pwmp_msg::{
+    protocol::{RejectReason, PROTOCOL_REVISION},
+    response::Response,
+    settings::NodeSettings,
+    version::Version,
+    Message,
+};
 
 macro_rules! generate_test {
     ($test_name: ident, $res: expr) => {
@@ -13,18 +22,26 @@ generate_test!(can_serialize_pong, Response::Pong);
 
 generate_test!(can_serialize_ok, Response::Ok);
 
-generate_test!(can_serialize_reject, Response::Reject);
+generate_test!(
+    can_serialize_reject,
+    Response::Reject(RejectReason::Unauthorized)
+);
+
+generate_test!(
+    can_serialize_reject_protocol,
+    Response::Reject(RejectReason::UnsupportedProtocol { min: 1, max: 3 })
+);
 
 generate_test!(can_serialize_fw_up_to_date, Response::FirmwareUpToDate);
 
 generate_test!(
     can_serialize_update_available,
-    Response::UpdateAvailable(Version::default())
+    Response::UpdateAvailable(manifest(Version::default()))
 );
 
 generate_test!(
     can_serialize_update_part,
-    Response::UpdatePart(b"blob".to_vec().into_boxed_slice())
+    Response::update_part(1024, 4096, b"blob".to_vec().into_boxed_slice())
 );
 
 generate_test!(can_serialize_update_end, Response::UpdateEnd);
@@ -40,3 +57,10 @@ generate_test!(
         mute_notifications: true
     }))
 );
+
+generate_test!(
+    can_serialize_handshake_accepted,
+    Response::HandshakeAccepted {
+        protocol: PROTOCOL_REVISION
+    }
+);
@@ -0,0 +1,194 @@
+use pwmp_msg::{
+    mac::Mac,
+    request::Request,
+    rollout::{rollout_bucket, Ineligible, NodeInfo, Release, ReleaseChannel, ReleaseChannelRef},
+    version::Version,
+    Message, MessageRef,
+};
+
+const MAC: Mac = Mac::new(0x24, 0x0A, 0xC4, 0x12, 0x34, 0x56);
+
+fn release(channel: ReleaseChannel, rollout: u8) -> Release {
+    Release {
+        version: Version::new(1, 2, 0),
+        channel,
+        board: "pws-v2".into(),
+        min_version: Version::new(1, 0, 0),
+        rollout,
+    }
+}
+
+fn node(channel: ReleaseChannelRef<'static>) -> NodeInfo<'static> {
+    NodeInfo {
+        mac: MAC,
+        firmware: Version::new(1, 1, 0),
+        channel,
+        board: "pws-v2",
+    }
+}
+
+#[test]
+fn channels_receive_more_stable_releases() {
+    let cases = [
+        (ReleaseChannelRef::Stable, ReleaseChannel::Stable, true),
+        (ReleaseChannelRef::Stable, ReleaseChannel::Beta, false),
+        (ReleaseChannelRef::Beta, ReleaseChannel::Stable, true),
+        (ReleaseChannelRef::Beta, ReleaseChannel::Nightly, false),
+        (ReleaseChannelRef::Nightly, ReleaseChannel::Beta, true),
+        (
+            ReleaseChannelRef::Custom("lab"),
+            ReleaseChannel::Custom("lab".into()),
+            true,
+        ),
+        (
+            ReleaseChannelRef::Custom("lab"),
+            ReleaseChannel::Custom("field".into()),
+            false,
+        ),
+        (
+            ReleaseChannelRef::Custom("lab"),
+            ReleaseChannel::Stable,
+            false,
+        ),
+        (
+            ReleaseChannelRef::Nightly,
+            ReleaseChannel::Custom("lab".into()),
+            false,
+        ),
+    ];
+
+    for (node_channel, release_channel, expected) in cases {
+        assert_eq!(
+            release(release_channel.clone(), 100).applies_to(&node(node_channel)),
+            expected,
+            "{node_channel} node, {release_channel} release"
+        );
+    }
+}
+
+#[test]
+fn normalizes_custom_names_of_built_in_channels() {
+    assert!(ReleaseChannelRef::Custom("beta").receives(ReleaseChannelRef::Beta));
+    assert!(ReleaseChannelRef::Nightly.receives(ReleaseChannelRef::Custom("stable")));
+    assert_eq!(
+        ReleaseChannelRef::from(&ReleaseChannel::Custom("beta".into())),
+        ReleaseChannelRef::Beta
+    );
+    assert_eq!(
+        ReleaseChannel::from(ReleaseChannelRef::Custom("nightly")),
+        ReleaseChannel::Nightly
+    );
+
+    let check = Request::UpdateCheck {
+        firmware: Version::new(1, 1, 0),
+        channel: ReleaseChannel::Custom("beta".into()),
+        board: "pws-v2".into(),
+        delta: false,
+    };
+    let bytes = Message::new_request(check, 1).serialize();
+
+    let Request::UpdateCheck { channel, .. } = Message::deserialize(&bytes)
+        .unwrap()
+        .take_request()
+        .unwrap()
+    else {
+        unreachable!();
+    };
+    assert_eq!(channel, ReleaseChannel::Beta);
+
+    let message = MessageRef::deserialize(&bytes).unwrap();
+    let node = NodeInfo::from_update_check(MAC, *message.request().unwrap()).unwrap();
+    assert_eq!(node.channel, ReleaseChannelRef::Beta);
+}
+
+#[test]
+fn rejects_ineligible_nodes() {
+    let release = release(ReleaseChannel::Stable, 100);
+
+    let mut other_board = node(ReleaseChannelRef::Stable);
+    other_board.board = "pws-v1";
+    assert_eq!(release.check(&other_board), Err(Ineligible::WrongBoard));
+
+    let mut up_to_date = node(ReleaseChannelRef::Stable);
+    up_to_date.firmware = Version::new(1, 2, 0);
+    assert_eq!(release.check(&up_to_date), Err(Ineligible::UpToDate));
+
+    let mut too_old = node(ReleaseChannelRef::Stable);
+    too_old.firmware = Version::new(0, 9, 0);
+    assert_eq!(
+        release.check(&too_old),
+        Err(Ineligible::UnsupportedVersion {
+            min_version: Version::new(1, 0, 0)
+        })
+    );
+
+    assert_eq!(release.check(&node(ReleaseChannelRef::Stable)), Ok(()));
+}
+
+#[test]
+fn staged_rollout_is_deterministic_and_monotonic() {
+    let bucket = rollout_bucket(MAC, Version::new(1, 2, 0));
+    let node = node(ReleaseChannelRef::Stable);
+
+    assert_eq!(bucket, rollout_bucket(MAC, Version::new(1, 2, 0)));
+    assert_eq!(
+        release(ReleaseChannel::Stable, 0).check(&node),
+        Err(Ineligible::NotInRollout)
+    );
+    assert_eq!(
+        release(ReleaseChannel::Stable, bucket).check(&node),
+        Err(Ineligible::NotInRollout)
+    );
+
+    for percentage in bucket + 1..=255 {
+        assert!(release(ReleaseChannel::Stable, percentage).applies_to(&node));
+    }
+}
+
+#[test]
+fn staged_rollout_covers_roughly_the_percentage() {
+    let version = Version::new(1, 2, 0);
+    let included = (0..=u16::MAX)
+        .map(|i| {
+            let [high, low] = i.to_be_bytes();
+            Mac::new(0x24, 0x0A, 0xC4, 0x00, high, low)
+        })
+        .filter(|mac| rollout_bucket(*mac, version) < 10)
+        .count();
+
+    // 10 % of 65536 nodes, give or take 5 %.
+    assert!(
+        (6226..=6881).contains(&included),
+        "{included} nodes included"
+    );
+}
+
+#[test]
+fn node_info_from_update_check() {
+    let request = Request::UpdateCheck {
+        firmware: Version::new(1, 1, 0),
+        channel: ReleaseChannel::Custom("lab".into()),
+        board: "pws-v2".into(),
+        delta: false,
+    };
+    let bytes = Message::new_request(request.clone(), 1).serialize();
+    let message = MessageRef::deserialize(&bytes).unwrap();
+
+    let node = NodeInfo::from_update_check(MAC, *message.request().unwrap()).unwrap();
+    assert_eq!(node.firmware, Version::new(1, 1, 0));
+    assert_eq!(node.channel, ReleaseChannelRef::Custom("lab"));
+    assert_eq!(node.board, "pws-v2");
+    assert_eq!(NodeInfo::from_update_check(MAC, &request), Some(node));
+
+    assert_eq!(NodeInfo::from_update_check(MAC, &Request::Ping), None);
+}
+
+#[test]
+fn channel_names() {
+    for name in ["stable", "beta", "nightly", "lab"] {
+        let channel = ReleaseChannelRef::from_name(name);
+
+        assert_eq!(channel.name(), name);
+        assert_eq!(ReleaseChannel::from(channel).to_string(), name);
+    }
+}
@@ -0,0 +1,336 @@
+mod common;
+
+use common::manifest;
+use pwmp_msg::{
+    aliases::{BatteryVoltage, Humidity, Rssi, Temperature},
+    compression::Compression,
+    diagnostics::Diagnostics,
+    mac::Mac,
+    measurement::{Sample, SampleTime},
+    protocol::{RejectReason, PROTOCOL_REVISION},
+    request::Request,
+    response::Response,
+    rollout::ReleaseChannel,
+    session::{NodeSession, ProtocolViolation, ServerSession},
+    settings::NodeSettings,
+    version::Version,
+};
+
+fn handshake() -> Request {
+    Request::Handshake {
+        mac: Mac::new(0, 1, 2, 3, 4, 5),
+        protocol: PROTOCOL_REVISION,
+        firmware: Version::new(1, 0, 0),
+    }
+}
+
+fn results() -> Request {
+    Request::PostResults {
+        temperature: Temperature::from_centi_celsius(2100),
+        humidity: Humidity::new(50).unwrap(),
+        air_pressure: None,
+    }
+}
+
+fn update_check() -> Request {
+    Request::UpdateCheck {
+        firmware: Version::new(1, 0, 0),
+        channel: ReleaseChannel::Stable,
+        board: "esp32".into(),
+        delta: false,
+    }
+}
+
+const ACCEPTED: Response = Response::HandshakeAccepted {
+    protocol: PROTOCOL_REVISION,
+};
+
+/// Feeds an exchange into both perspectives, checking that they agree.
+fn exchange(
+    node: &mut NodeSession,
+    server: &mut ServerSession,
+    request: &Request,
+    response: Option<&Response>,
+) {
+    node.send_request(request).unwrap();
+    server.receive_request(request).unwrap();
+
+    if let Some(response) = response {
+        server.send_response(response).unwrap();
+        node.receive_response(response).unwrap();
+    }
+}
+
+fn established() -> (NodeSession, ServerSession) {
+    let mut node = NodeSession::new();
+    let mut server = ServerSession::new();
+    exchange(&mut node, &mut server, &handshake(), Some(&ACCEPTED));
+
+    (node, server)
+}
+
+#[test]
+fn full_session() {
+    let mut node = NodeSession::new();
+    let mut server = ServerSession::new();
+    let exchanges = [
+        (handshake(), Some(ACCEPTED)),
+        (
+            Request::GetSettings,
+            Some(Response::Settings(Some(NodeSettings::default()))),
+        ),
+        (results(), Some(Response::Ok)),
+        (
+            Request::PostStats {
+                battery: BatteryVoltage::from_millivolts(3900),
+                wifi_ssid: "Weather".into(),
+                wifi_rssi: Rssi::new(-50).unwrap(),
+                diagnostics: Diagnostics::new(3_000),
+            },
+            Some(Response::Ok),
+        ),
+        (
+            update_check(),
+            Some(Response::UpdateAvailable(manifest(Version::new(1, 1, 0)))),
+        ),
+        (
+            Request::NextUpdateChunk {
+                offset: 0,
+                max_size: 4,
+                compression: Compression::None,
+                base: None,
+            },
+            Some(Response::update_part(0, 4, vec![1, 2, 3, 4].into())),
+        ),
+        (
+            Request::NextUpdateChunk {
+                offset: 4,
+                max_size: 4,
+                compression: Compression::None,
+                base: None,
+            },
+            Some(Response::UpdateEnd),
+        ),
+        (Request::Bye, None),
+    ];
+
+    for (request, response) in exchanges {
+        exchange(&mut node, &mut server, &request, response.as_ref());
+        assert!(!node.is_closed() || request == Request::Bye);
+    }
+
+    assert!(node.is_closed());
+    assert!(server.is_closed());
+}
+
+#[test]
+fn handshake_must_come_first() {
+    let mut server = ServerSession::new();
+
+    assert_eq!(
+        server.receive_request(&Request::Ping),
+        Err(ProtocolViolation::NotAuthenticated)
+    );
+    assert!(server.receive_request(&handshake()).is_ok());
+}
+
+#[test]
+fn only_one_handshake() {
+    let (mut node, _) = established();
+
+    assert_eq!(
+        node.send_request(&handshake()),
+        Err(ProtocolViolation::DuplicateHandshake)
+    );
+}
+
+#[test]
+fn rejection_closes_session() {
+    let mut node = NodeSession::new();
+    node.send_request(&handshake()).unwrap();
+    node.receive_response(&Response::Reject(RejectReason::Unauthorized))
+        .unwrap();
+
+    assert!(node.is_closed());
+    assert_eq!(
+        node.send_request(&Request::Ping),
+        Err(ProtocolViolation::SessionClosed)
+    );
+}
+
+#[test]
+fn only_one_results_post() {
+    let (_, mut server) = established();
+    server.receive_request(&results()).unwrap();
+    server.send_response(&Response::Ok).unwrap();
+
+    assert_eq!(
+        server.receive_request(&results()),
+        Err(ProtocolViolation::DuplicateResults)
+    );
+
+    // Measurements of other sensors count as results, too.
+    let measurements = results().measurements().unwrap();
+    assert_eq!(
+        server.receive_request(&Request::PostMeasurements(measurements)),
+        Err(ProtocolViolation::DuplicateResults)
+    );
+}
+
+#[test]
+fn retries_rejected_results() {
+    let (mut node, mut server) = established();
+    exchange(
+        &mut node,
+        &mut server,
+        &results(),
+        Some(&Response::InvalidRequest),
+    );
+    exchange(
+        &mut node,
+        &mut server,
+        &results(),
+        Some(&Response::InternalServerError),
+    );
+    exchange(&mut node, &mut server, &results(), Some(&Response::Ok));
+
+    assert_eq!(
+        node.send_request(&results()),
+        Err(ProtocolViolation::DuplicateResults)
+    );
+}
+
+#[test]
+fn any_number_of_batches() {
+    let (mut node, mut server) = established();
+    let measurements = results().measurements().unwrap();
+    let batch = Request::PostMeasurementBatch(
+        [Sample::new(SampleTime::Age(900), &measurements)]
+            .into_iter()
+            .collect(),
+    );
+
+    exchange(&mut node, &mut server, &results(), Some(&Response::Ok));
+    exchange(&mut node, &mut server, &batch, Some(&Response::Ok));
+    exchange(&mut node, &mut server, &batch, Some(&Response::Ok));
+}
+
+#[test]
+fn chunks_require_update_check() {
+    let (mut node, _) = established();
+
+    assert_eq!(
+        node.send_request(&Request::NextUpdateChunk {
+            offset: 0,
+            max_size: 64,
+            compression: Compression::None,
+            base: None,
+        }),
+        Err(ProtocolViolation::UpdateNotAvailable)
+    );
+
+    node.send_request(&update_check()).unwrap();
+    node.receive_response(&Response::FirmwareUpToDate).unwrap();
+
+    assert_eq!(
+        node.send_request(&Request::NextUpdateChunk {
+            offset: 0,
+            max_size: 64,
+            compression: Compression::None,
+            base: None,
+        }),
+        Err(ProtocolViolation::UpdateNotAvailable)
+    );
+}
+
+#[test]
+fn no_chunks_after_update_end() {
+    let (mut node, _) = established();
+    node.send_request(&update_check()).unwrap();
+    node.receive_response(&Response::UpdateAvailable(manifest(Version::new(2, 0, 0))))
+        .unwrap();
+    node.send_request(&Request::NextUpdateChunk {
+        offset: 0,
+        max_size: 64,
+        compression: Compression::None,
+        base: None,
+    })
+    .unwrap();
+    node.receive_response(&Response::UpdateEnd).unwrap();
+
+    assert_eq!(
+        node.send_request(&Request::NextUpdateChunk {
+            offset: 0,
+            max_size: 64,
+            compression: Compression::None,
+            base: None,
+        }),
+        Err(ProtocolViolation::UpdateNotAvailable)
+    );
+}
+
+#[test]
+fn resumes_download_in_new_session() {
+    let (mut node, mut server) = established();
+    exchange(
+        &mut node,
+        &mut server,
+        &update_check(),
+        Some(&Response::UpdateAvailable(manifest(Version::new(2, 0, 0)))),
+    );
+    exchange(
+        &mut node,
+        &mut server,
+        &Request::NextUpdateChunk {
+            offset: 2048,
+            max_size: 1024,
+            compression: Compression::None,
+            base: None,
+        },
+        Some(&Response::update_part(2048, 4096, vec![0; 1024].into())),
+    );
+
+    assert!(node.pending().is_none());
+    assert!(server.pending().is_none());
+}
+
+#[test]
+fn requests_and_responses_alternate() {
+    let (mut node, mut server) = established();
+    node.send_request(&Request::Ping).unwrap();
+
+    assert_eq!(
+        node.send_request(&Request::Ping),
+        Err(ProtocolViolation::RequestPending)
+    );
+    assert_eq!(
+        server.send_response(&Response::Pong),
+        Err(ProtocolViolation::UnexpectedResponse)
+    );
+}
+
+#[test]
+fn response_must_match_request() {
+    let (mut node, _) = established();
+    node.send_request(&Request::Ping).unwrap();
+
+    assert_eq!(
+        node.receive_response(&Response::Ok),
+        Err(ProtocolViolation::InvalidResponse)
+    );
+    assert!(node.pending().is_some());
+    assert!(node.receive_response(&Response::Pong).is_ok());
+    assert!(node.pending().is_none());
+}
+
+#[test]
+fn nothing_after_bye() {
+    let (_, mut server) = established();
+    server.receive_request(&Request::Bye).unwrap();
+
+    assert!(server.pending().is_none());
+    assert_eq!(
+        server.receive_request(&Request::Ping),
+        Err(ProtocolViolation::SessionClosed)
+    );
+}
@@ -0,0 +1,146 @@
+use pwmp_msg::{
+    request::Request,
+    response::Response,
+    rollout::ReleaseChannel,
+    tracker::{PendingRequests, TrackError},
+    version::Version,
+    Message,
+};
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+fn tracker_with(requests: &[(Request, u32)]) -> PendingRequests {
+    let mut tracker = PendingRequests::new(TIMEOUT);
+
+    for (request, id) in requests {
+        tracker
+            .register(&Message::new_request(request.clone(), *id), Duration::ZERO)
+            .unwrap();
+    }
+
+    tracker
+}
+
+fn update_check() -> Request {
+    Request::UpdateCheck {
+        firmware: Version::new(1, 0, 0),
+        channel: ReleaseChannel::Stable,
+        board: "esp32".into(),
+        delta: false,
+    }
+}
+
+#[test]
+fn matches_by_reply_id() {
+    let mut tracker = tracker_with(&[(Request::Ping, 1), (Request::GetSettings, 2)]);
+
+    assert_eq!(
+        tracker.resolve(&Message::new_reply(Response::Settings(None), 50, 2)),
+        Ok(Request::GetSettings)
+    );
+    assert!(tracker.contains(1));
+    assert!(!tracker.contains(2));
+}
+
+#[test]
+fn falls_back_to_oldest_request() {
+    let mut tracker = tracker_with(&[(Request::Ping, 1), (Request::GetSettings, 2)]);
+
+    assert_eq!(
+        tracker.resolve(&Message::new_response(Response::Pong, 50)),
+        Ok(Request::Ping)
+    );
+    assert_eq!(tracker.len(), 1);
+}
+
+#[test]
+fn rejects_mismatched_response() {
+    let mut tracker = tracker_with(&[(update_check(), 1)]);
+
+    assert_eq!(
+        tracker.resolve(&Message::new_reply(Response::Pong, 50, 1)),
+        Err(TrackError::InvalidResponse {
+            request: update_check()
+        })
+    );
+    assert!(tracker.is_empty());
+}
+
+#[test]
+fn error_responses_answer_any_request() {
+    let mut tracker = tracker_with(&[(Request::GetSettings, 1)]);
+
+    assert_eq!(
+        tracker.resolve(&Message::new_reply(Response::RateLimitExceeded, 50, 1)),
+        Ok(Request::GetSettings)
+    );
+}
+
+#[test]
+fn unknown_and_missing_requests() {
+    let mut tracker = tracker_with(&[(Request::Ping, 1)]);
+
+    assert_eq!(
+        tracker.resolve(&Message::new_reply(Response::Pong, 50, 9)),
+        Err(TrackError::UnknownRequest(9))
+    );
+
+    tracker.clear();
+    assert_eq!(
+        tracker.resolve(&Message::new_response(Response::Pong, 50)),
+        Err(TrackError::NoPendingRequest)
+    );
+}
+
+#[test]
+fn registration_errors() {
+    let mut tracker = tracker_with(&[(Request::Ping, 1)]);
+
+    assert_eq!(
+        tracker.register(&Message::new_request(Request::Ping, 1), Duration::ZERO),
+        Err(TrackError::DuplicateId(1))
+    );
+    assert_eq!(
+        tracker.register(&Message::new_response(Response::Pong, 2), Duration::ZERO),
+        Err(TrackError::NotARequest)
+    );
+    assert_eq!(
+        tracker.resolve(&Message::new_request(Request::Ping, 3)),
+        Err(TrackError::NotAResponse)
+    );
+}
+
+#[test]
+fn bye_is_not_tracked() {
+    let tracker = tracker_with(&[(Request::Bye, 1)]);
+
+    assert!(tracker.is_empty());
+}
+
+#[test]
+fn reports_timeouts() {
+    let mut tracker = PendingRequests::new(TIMEOUT);
+    tracker
+        .register(&Message::new_request(Request::Ping, 1), Duration::ZERO)
+        .unwrap();
+    tracker
+        .register(
+            &Message::new_request(Request::GetSettings, 2),
+            Duration::from_secs(5),
+        )
+        .unwrap();
+
+    assert_eq!(tracker.next_deadline(), Some(TIMEOUT));
+    assert!(tracker.expire(TIMEOUT).is_empty());
+    assert_eq!(
+        tracker.expire(Duration::from_secs(11)),
+        vec![(1, Request::Ping)]
+    );
+    assert_eq!(tracker.next_deadline(), Some(Duration::from_secs(15)));
+    assert_eq!(
+        tracker.expire(Duration::from_secs(16)),
+        vec![(2, Request::GetSettings)]
+    );
+    assert_eq!(tracker.next_deadline(), None);
+}
@@ -0,0 +1,130 @@
+use pwmp_msg::{
+    aliases::{AirPressure, Humidity, Temperature},
+    units::{
+        absolute_humidity, celsius_to_fahrenheit, dew_point, fahrenheit_to_celsius, heat_index,
+        hpa_to_inhg, hpa_to_mmhg, sea_level_pressure,
+    },
+};
+
+fn assert_close(actual: f32, expected: f32, tolerance: f32) {
+    assert!(
+        (actual - expected).abs() <= tolerance,
+        "{actual} is not within {tolerance} of {expected}"
+    );
+}
+
+fn temperature(celsius: f32) -> Temperature {
+    Temperature::try_from(celsius).unwrap()
+}
+
+fn humidity(percent: u8) -> Humidity {
+    Humidity::new(percent).unwrap()
+}
+
+#[test]
+fn converts_temperatures() {
+    assert_close(celsius_to_fahrenheit(0.0), 32.0, 1e-4);
+    assert_close(celsius_to_fahrenheit(100.0), 212.0, 1e-4);
+    assert_close(celsius_to_fahrenheit(-40.0), -40.0, 1e-4);
+    assert_close(fahrenheit_to_celsius(98.6), 37.0, 1e-4);
+    assert_close(temperature(21.5).fahrenheit(), 70.7, 1e-4);
+}
+
+#[test]
+fn converts_pressures() {
+    assert_close(hpa_to_inhg(1013.25), 29.921, 1e-3);
+    assert_close(hpa_to_mmhg(1013.25), 760.0, 1e-2);
+    assert_close(
+        AirPressure::new(1000).unwrap().inches_of_mercury(),
+        29.530,
+        1e-3,
+    );
+    assert_close(
+        AirPressure::new(1000).unwrap().millimeters_of_mercury(),
+        750.06,
+        1e-2,
+    );
+}
+
+#[test]
+fn computes_dew_point() {
+    assert_close(
+        dew_point(temperature(20.0), humidity(50)).unwrap(),
+        9.26,
+        0.01,
+    );
+    assert_close(
+        dew_point(temperature(30.0), humidity(80)).unwrap(),
+        26.17,
+        0.01,
+    );
+    assert_close(
+        dew_point(temperature(-10.0), humidity(70)).unwrap(),
+        -14.44,
+        0.01,
+    );
+    assert_close(
+        dew_point(temperature(15.0), Humidity::MAX).unwrap(),
+        15.0,
+        1e-4,
+    );
+    assert_eq!(dew_point(temperature(20.0), humidity(0)), None);
+}
+
+#[test]
+fn computes_heat_index() {
+    // Values of the heat index chart of the US National Weather Service, in °F.
+    for (fahrenheit, percent, expected) in [
+        (80.0, 40, 80.0),
+        (90.0, 50, 95.0),
+        (96.0, 65, 121.0),
+        (104.0, 40, 119.0),
+        (86.0, 90, 105.0),
+    ] {
+        let index = heat_index(
+            temperature(fahrenheit_to_celsius(fahrenheit)),
+            humidity(percent),
+        );
+
+        assert_close(celsius_to_fahrenheit(index), expected, 1.0);
+    }
+
+    // Cool weather is close to the temperature, 50 °F feel like 47 °F.
+    assert_close(
+        celsius_to_fahrenheit(heat_index(temperature(10.0), humidity(50))),
+        47.0,
+        0.1,
+    );
+}
+
+#[test]
+fn computes_absolute_humidity() {
+    assert_close(
+        absolute_humidity(temperature(20.0), humidity(50)),
+        8.64,
+        0.02,
+    );
+    assert_close(
+        absolute_humidity(temperature(30.0), humidity(80)),
+        24.3,
+        0.1,
+    );
+    assert_close(
+        absolute_humidity(temperature(0.0), Humidity::MAX),
+        4.85,
+        0.02,
+    );
+    assert_eq!(absolute_humidity(temperature(25.0), humidity(0)), 0.0);
+}
+
+#[test]
+fn computes_sea_level_pressure() {
+    let pressure = |hpa| AirPressure::new(hpa).unwrap();
+
+    assert_close(sea_level_pressure(pressure(1013), 0.0), 1013.0, 1e-3);
+    // Pressures of the standard atmosphere at 500 m and 1000 m.
+    assert_close(sea_level_pressure(pressure(955), 500.0), 1013.6, 0.2);
+    assert_close(sea_level_pressure(pressure(899), 1000.0), 1013.5, 0.2);
+    // Below sea level.
+    assert_close(sea_level_pressure(pressure(1025), -100.0), 1012.9, 0.2);
+}